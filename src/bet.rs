@@ -1,7 +1,18 @@
 use std::collections::HashMap;
 
+use serde::{Deserialize, Serialize};
+
+use crate::ledger::{Reason, Source};
+use crate::odds::{Cashier, Payout, Resolution};
+use crate::error::Error;
+use crate::player::Player;
+
+#[derive(Serialize, Deserialize)]
 pub struct Bet {
-    pub chips: HashMap<Chip, u32> // token -> count
+    #[serde(with = "chip_map")]
+    pub chips: HashMap<Chip, u32>, // token -> count
+    /// expected share of the stake the house keeps, for theoretical loss
+    pub house_edge: f64,
 }
 
 impl Default for Bet {
@@ -14,22 +25,29 @@ impl Bet {
 
     pub fn new() -> Self {
         Self {
-            chips: HashMap::new()
+            chips: HashMap::new(),
+            house_edge: 0.0,
         }
     }
 
-    pub fn place_chip(&mut self, chip: Chip, count: u32) {
+    pub fn with_house_edge(mut self, house_edge: f64) -> Self {
+        self.house_edge = house_edge;
+        self
+    }
+
+    // Adds chips without touching any wallet; `escrow` takes them from a player.
+    pub(crate) fn place_chip(&mut self, chip: Chip, count: u32) {
         *self.chips.entry(chip).or_insert(0) += count;
     }
 
 
-    pub fn place_multiple(&mut self, chips: HashMap<Chip, u32>) {
+    pub(crate) fn place_multiple(&mut self, chips: HashMap<Chip, u32>) {
         chips.iter().for_each(|(chip, count)| {
             self.place_chip(chip.clone(), *count);
         });
     }
 
-    pub fn remove_chip(&mut self, chip: &Chip, count: u32) -> Result<(), &'static str> {
+    pub fn remove_chip(&mut self, chip: &Chip, count: u32) -> Result<(), Error> {
         match self.chips.get_mut(chip) {
             Some(n) if *n >= count => {
                 *n -= count;
@@ -38,7 +56,7 @@ impl Bet {
                 }
                 Ok(())
             }
-            _ => Err("insufficient chips in bet"),
+            held => Err(Error::InsufficientChips { chip: chip.name.clone(), wanted: count, held: held.map_or(0, |n| *n) }),
         }
     }
 
@@ -46,13 +64,360 @@ impl Bet {
         self.chips.iter().map(|(tok, &count)| tok.value_cents.saturating_mul(count as u64)).sum()
     }
 
+    // Take `chips` from the player's wallet and hold them in this bet, recorded as a wager.
+    // Nothing moves unless the wallet holds every chip and the player's limits allow it.
+    pub fn escrow(&mut self, player: &mut Player, chips: HashMap<Chip, u32>, source: Option<Source>) -> Result<(), Error> {
+        player.debit(&chips, Reason::Wager, source)?;
+        self.place_multiple(chips);
+        Ok(())
+    }
 
+    // Resolve the bet: a win or push hands the stake (and any winnings) back to the player,
+    // a loss leaves the stake with the house. Returns None for a loss. The caller clears the
+    // bet once it has settled; if the cashier cannot pay, nothing moves and it stays put.
+    pub fn settle(
+        &self,
+        player: &mut Player,
+        cashier: &mut Cashier,
+        resolution: Resolution,
+        source: Option<Source>,
+    ) -> Result<Option<Payout>, Error> {
+        let payout = cashier.settle(player, &self.chips, resolution, source)?;
+        player.record_decision(self.total_cents(), self.house_edge);
+        Ok(payout)
+    }
+
+    // What settling with `resolution` would hand back to `player`, without moving any chips.
+    pub fn quote(&self, player: &Player, cashier: &Cashier, resolution: Resolution) -> Result<Option<Payout>, Error> {
+        cashier.quote_settlement(player.id(), &self.chips, resolution)
+    }
 }
 
-#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub struct Chip {
     pub name: String,
     pub display: String,
     /// value in cents to avoid floating point rounding issues
     pub value_cents: u64,
 }
+
+impl Chip {
+    pub fn new(name: impl Into<String>, display: impl Into<String>, value_cents: u64) -> Self {
+        Self { name: name.into(), display: display.into(), value_cents }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Denomination {
+    pub chip: Chip,
+    pub colour: String,
+}
+
+/// The denominations a casino issues, largest first. No two share a value or a colour.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "ChipSetRaw")]
+pub struct ChipSet {
+    denominations: Vec<Denomination>,
+}
+
+// A chip set as saved, checked by `ChipSet::new` when it is read back.
+#[derive(Deserialize)]
+struct ChipSetRaw {
+    denominations: Vec<Denomination>,
+}
+
+impl TryFrom<ChipSetRaw> for ChipSet {
+    type Error = String;
+
+    fn try_from(raw: ChipSetRaw) -> Result<Self, String> {
+        ChipSet::new(raw.denominations)
+            .ok_or_else(|| "chip set is empty or has a worthless or repeated chip".to_string())
+    }
+}
+
+impl ChipSet {
+    // Returns None if the set is empty, or any chip is worth nothing or shares a value,
+    // name or colour with another.
+    pub fn new(denominations: Vec<Denomination>) -> Option<Self> {
+        if denominations.is_empty() || denominations.iter().any(|d| d.chip.value_cents == 0) {
+            return None;
+        }
+        for (i, d) in denominations.iter().enumerate() {
+            if denominations[..i].iter().any(|other| {
+                other.chip.value_cents == d.chip.value_cents || other.chip.name == d.chip.name || other.colour == d.colour
+            }) {
+                return None;
+            }
+        }
+        let mut denominations = denominations;
+        denominations.sort_by_key(|d| std::cmp::Reverse(d.chip.value_cents));
+        Some(Self { denominations })
+    }
+
+    // The usual US table chips, $1 to $5,000, including the $2.50 pink for 3:2 payouts.
+    pub fn standard() -> Self {
+        let chip = |colour: &str, display: &str, value_cents| Denomination {
+            chip: Chip::new(colour, display, value_cents),
+            colour: colour.to_string(),
+        };
+        Self::new(vec![
+            chip("white", "$1", 100),
+            chip("pink", "$2.50", 250),
+            chip("red", "$5", 500),
+            chip("green", "$25", 2_500),
+            chip("black", "$100", 10_000),
+            chip("purple", "$500", 50_000),
+            chip("orange", "$1,000", 100_000),
+            chip("brown", "$5,000", 500_000),
+        ])
+        .expect("standard chips are distinct")
+    }
+
+    pub fn denominations(&self) -> &[Denomination] {
+        &self.denominations
+    }
+
+    pub fn chip(&self, value_cents: u64) -> Option<&Chip> {
+        self.denominations.iter().map(|d| &d.chip).find(|c| c.value_cents == value_cents)
+    }
+
+    pub fn by_colour(&self, colour: &str) -> Option<&Chip> {
+        self.denominations.iter().find(|d| d.colour == colour).map(|d| &d.chip)
+    }
+
+    pub fn contains(&self, chip: &Chip) -> bool {
+        self.denominations.iter().any(|d| &d.chip == chip)
+    }
+
+    // The fewest chips worth exactly `cents`, or None if no combination adds up to it.
+    pub fn break_amount(&self, cents: u64) -> Option<HashMap<Chip, u32>> {
+        self.fewest(cents, |_| true)
+    }
+
+    // The largest amount no more than `cents` that these chips can pay exactly.
+    pub fn round_down(&self, cents: u64) -> u64 {
+        let unit = self.denominations.iter().fold(0, |g, d| gcd(g, d.chip.value_cents));
+        let mut amount = cents - cents % unit;
+        while self.break_amount(amount).is_none() {
+            amount -= unit;
+        }
+        amount
+    }
+
+    // Break a single chip into the fewest smaller chips.
+    pub fn make_change(&self, chip: &Chip) -> Option<HashMap<Chip, u32>> {
+        if !self.contains(chip) {
+            return None;
+        }
+        self.fewest(chip.value_cents, |c| c.value_cents < chip.value_cents)
+    }
+
+    // Swap every chip of this set in the player's wallet for the fewest chips of the same
+    // total value. Chips from other sets are left alone.
+    pub fn colour_up(&self, player: &mut Player) {
+        let held: HashMap<Chip, u32> = player
+            .wallet()
+            .iter()
+            .filter(|(chip, _)| self.contains(chip))
+            .map(|(chip, &count)| (chip.clone(), count))
+            .collect();
+        let total: u64 = held.iter().map(|(chip, count)| chip.value_cents * *count as u64).sum();
+        let Some(coloured) = self.break_amount(total) else {
+            return;
+        };
+        player.debit(&held, Reason::ColourUp, None).expect("chips were just counted");
+        player.credit(coloured, Reason::ColourUp, None);
+    }
+
+    // Fewest chips drawn from the allowed denominations. A greedy pick is not always optimal
+    // (with $1 and $2.50 chips, $3 is three whites, not a pink and change), so this is an
+    // exact search. Only a bounded remainder needs the search: using lcm(c, L) / c chips of
+    // value c when lcm(c, L) / L of the largest chip L would do is never optimal, so the
+    // smaller chips together cover at most the sum of (lcm(c, L) / c - 1) * c.
+    fn fewest(&self, cents: u64, allowed: impl Fn(&Chip) -> bool) -> Option<HashMap<Chip, u32>> {
+        let chips: Vec<&Chip> = self.denominations.iter().map(|d| &d.chip).filter(|c| allowed(c)).collect();
+        let Some((largest, smaller)) = chips.split_first() else {
+            return (cents == 0).then(HashMap::new);
+        };
+        let unit = chips.iter().fold(0, |g, c| gcd(g, c.value_cents));
+        if !cents.is_multiple_of(unit) {
+            return None;
+        }
+        let large = largest.value_cents / unit;
+        let values: Vec<u64> = smaller.iter().map(|c| c.value_cents / unit).collect();
+        let amount = cents / unit;
+        let bound: u64 = values.iter().map(|&v| (v * large / gcd(v, large) / v - 1) * v).sum();
+        let top = amount.min(bound) as usize;
+
+        // best[x]: fewest smaller chips worth x units, and the last chip used
+        let mut best: Vec<Option<(u32, usize)>> = vec![None; top + 1];
+        best[0] = Some((0, usize::MAX));
+        for x in 1..=top {
+            best[x] = values
+                .iter()
+                .enumerate()
+                .filter(|&(_, &v)| v as usize <= x)
+                .filter_map(|(i, &v)| best[x - v as usize].map(|(n, _)| (n + 1, i)))
+                .min();
+        }
+
+        let mut rest = amount % large;
+        let mut choice = None;
+        while rest <= top as u64 {
+            if let Some((n, _)) = best[rest as usize] {
+                let total = n as u64 + (amount - rest) / large;
+                if choice.is_none_or(|(t, _)| total < t) {
+                    choice = Some((total, rest));
+                }
+            }
+            rest += large;
+        }
+        let (_, mut rest) = choice?;
+        let mut counts = HashMap::new();
+        if amount > rest {
+            counts.insert((*largest).clone(), u32::try_from((amount - rest) / large).ok()?);
+        }
+        while rest > 0 {
+            let (_, i) = best[rest as usize].expect("reconstructing a reachable amount");
+            *counts.entry(smaller[i].clone()).or_insert(0) += 1;
+            rest -= values[i];
+        }
+        Some(counts)
+    }
+}
+
+fn gcd(a: u64, b: u64) -> u64 {
+    if b == 0 { a } else { gcd(b, a % b) }
+}
+
+// Chip-keyed maps are stored as (chip, count) pairs, largest chip first, since JSON only
+// allows string keys.
+pub(crate) mod chip_map {
+    use std::collections::HashMap;
+
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    use super::Chip;
+
+    pub fn serialize<S: Serializer>(chips: &HashMap<Chip, u32>, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut pairs: Vec<(&Chip, &u32)> = chips.iter().collect();
+        pairs.sort_by(|a, b| b.0.value_cents.cmp(&a.0.value_cents).then_with(|| a.0.name.cmp(&b.0.name)));
+        serializer.collect_seq(pairs)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<HashMap<Chip, u32>, D::Error> {
+        Ok(Vec::<(Chip, u32)>::deserialize(deserializer)?.into_iter().collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::odds::Odds;
+
+    fn count(chips: &HashMap<Chip, u32>) -> u32 {
+        chips.values().sum()
+    }
+
+    fn total(chips: &HashMap<Chip, u32>) -> u64 {
+        chips.iter().map(|(c, &n)| c.value_cents * n as u64).sum()
+    }
+
+    #[test]
+    fn test_chip_set_validation() {
+        let d = |name: &str, colour: &str, value| Denomination { chip: Chip::new(name, name, value), colour: colour.to_string() };
+        assert!(ChipSet::new(vec![]).is_none());
+        assert!(ChipSet::new(vec![d("one", "white", 100), d("other one", "blue", 100)]).is_none());
+        assert!(ChipSet::new(vec![d("one", "white", 100), d("five", "white", 500)]).is_none());
+        assert!(ChipSet::new(vec![d("zero", "grey", 0)]).is_none());
+        let set = ChipSet::new(vec![d("one", "white", 100), d("five", "red", 500)]).unwrap();
+        assert_eq!(set.denominations()[0].chip.value_cents, 500);
+        assert_eq!(set.by_colour("white").unwrap().value_cents, 100);
+    }
+
+    #[test]
+    fn test_break_amount() {
+        let set = ChipSet::standard();
+        // 3:2 on $25
+        let payout = set.break_amount(3_750).unwrap();
+        assert_eq!(total(&payout), 3_750);
+        assert_eq!(count(&payout), 4);
+        assert_eq!(payout[set.by_colour("pink").unwrap()], 1);
+        // greedy would take a pink and get stuck
+        let three = set.break_amount(300).unwrap();
+        assert_eq!(three, HashMap::from([(set.chip(100).unwrap().clone(), 3)]));
+        // $12,345: two browns, two oranges, three blacks, a green and four reds
+        assert_eq!(count(&set.break_amount(1_234_500).unwrap()), 12);
+        assert!(set.break_amount(50).is_none());
+        assert!(set.break_amount(0).unwrap().is_empty());
+        assert_eq!(set.round_down(475), 450);
+        assert_eq!(set.round_down(199), 100);
+        assert_eq!(set.round_down(99), 0);
+    }
+
+    #[test]
+    fn test_make_change() {
+        let set = ChipSet::standard();
+        let black = set.chip(10_000).unwrap();
+        assert_eq!(set.make_change(black).unwrap(), HashMap::from([(set.chip(2_500).unwrap().clone(), 4)]));
+        let white = set.chip(100).unwrap();
+        assert!(set.make_change(white).is_none());
+        assert!(set.make_change(&Chip::new("foreign", "$5", 500)).is_none());
+    }
+
+    #[test]
+    fn test_escrow_is_atomic() {
+        let set = ChipSet::standard();
+        let (red, green) = (set.chip(500).unwrap().clone(), set.chip(2_500).unwrap().clone());
+        let mut player = Player::new("Alice");
+        player.deposit(red.clone(), 3).unwrap();
+        let mut bet = Bet::new();
+        assert!(bet.escrow(&mut player, HashMap::from([(red.clone(), 2), (green.clone(), 1)]), None).is_err());
+        assert_eq!(player.total_cents(), 1_500);
+        assert_eq!(bet.total_cents(), 0);
+        bet.escrow(&mut player, HashMap::from([(red.clone(), 2)]), None).unwrap();
+        assert_eq!((player.total_cents(), bet.total_cents()), (500, 1_000));
+    }
+
+    #[test]
+    fn test_settle_moves_money_once() {
+        let set = ChipSet::standard();
+        let red = set.chip(500).unwrap().clone();
+        let mut player = Player::new("Bob");
+        player.deposit(red.clone(), 4).unwrap();
+        let mut cashier = Cashier::default();
+        let escrowed = |player: &mut Player| {
+            let mut bet = Bet::new();
+            bet.escrow(player, HashMap::from([(red.clone(), 2)]), None).unwrap();
+            bet
+        };
+        let lost = escrowed(&mut player).settle(&mut player, &mut cashier, Resolution::Lose, None);
+        assert_eq!(lost, Ok(None));
+        assert_eq!(player.total_cents(), 1_000);
+        assert_eq!(cashier.house_net_cents(), 1_000);
+        let won = escrowed(&mut player).settle(&mut player, &mut cashier, Resolution::Win(Odds::new(3, 2).unwrap()), None);
+        assert_eq!(won.unwrap().unwrap().returned_cents(), 2_500);
+        assert_eq!(player.total_cents(), 2_500);
+        assert_eq!(cashier.house_net_cents(), -500);
+        // the house and the player together still hold the original $20
+        assert_eq!(player.total_cents() as i64 + cashier.house_net_cents(), 2_000);
+    }
+
+    #[test]
+    fn test_colour_up() {
+        let set = ChipSet::standard();
+        let mut player = Player::new("Alice");
+        player.deposit(set.chip(100).unwrap().clone(), 30).unwrap();
+        player.deposit(set.chip(500).unwrap().clone(), 15).unwrap();
+        let foreign = Chip::new("souvenir", "$1", 100);
+        player.deposit(foreign.clone(), 2).unwrap();
+        let before = player.total_cents();
+        set.colour_up(&mut player);
+        assert_eq!(player.total_cents(), before);
+        // $105 becomes a black and a red; the foreign chips stay
+        assert_eq!(player.token_count(set.chip(10_000).unwrap()), 1);
+        assert_eq!(player.token_count(set.chip(500).unwrap()), 1);
+        assert_eq!(player.token_count(set.chip(100).unwrap()), 0);
+        assert_eq!(player.token_count(&foreign), 2);
+    }
+}
@@ -3,9 +3,10 @@
 #[cfg(feature = "python")]
 pub mod python_bindings;
 
-use rand::rng;
+use rand::{Rng, rng};
+use serde::{Deserialize, Serialize};
 
-#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum Suit {
     Hearts,
     Diamonds,
@@ -55,7 +56,7 @@ pub trait BaccaratRank: Rank {
     fn baccarat_value(&self) -> u8;
 }
 
-#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub struct Card<R: Rank> {
     pub suit: Suit,
     pub rank: R,
@@ -85,7 +86,7 @@ impl<R: BlackjackRank> Card<R> {
     }
 }
 
-#[derive(Clone, Debug, PartialEq)]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub struct CardCollection<R: Rank> {
     pub(crate) cards: Vec<Card<R>>,
 }
@@ -102,9 +103,13 @@ impl<R: Rank> CardCollection<R> {
     }
 
     pub fn shuffle(&mut self) {
-        use rand::seq::SliceRandom;
         let mut rng = rng();
-        self.cards.shuffle(&mut rng);
+        self.shuffle_with(&mut rng);
+    }
+
+    pub fn shuffle_with<G: Rng + ?Sized>(&mut self, rng: &mut G) {
+        use rand::seq::SliceRandom;
+        self.cards.shuffle(rng);
     }
 
     pub fn deal(&mut self) -> Option<Card<R>> {
@@ -149,13 +154,20 @@ impl<R: Rank> Deck<R> {
         self.cards.shuffle();
     }
 
+    pub fn shuffle_with<G: Rng + ?Sized>(&mut self, rng: &mut G) {
+        self.cards.shuffle_with(rng);
+    }
+
     pub fn deal(&mut self) -> Option<Card<R>> {
         self.cards.deal()
     }
 }
 
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub struct Shoe<R: Rank> {
     pub shoe: CardCollection<R>,
+    /// cards played since the shoe was filled, in the order they were discarded
+    pub discards: CardCollection<R>,
 }
 
 impl<R: Rank> Shoe<R> {
@@ -168,20 +180,33 @@ impl<R: Rank> Shoe<R> {
                 }
             }
         }
-        Shoe { shoe }
+        Shoe { shoe, discards: CardCollection::new() }
     }
 
     pub fn shuffle(&mut self) {
         self.shoe.shuffle();
     }
 
+    pub fn shuffle_with<G: Rng + ?Sized>(&mut self, rng: &mut G) {
+        self.shoe.shuffle_with(rng);
+    }
+
     pub fn deal(&mut self) -> Option<Card<R>> {
         self.shoe.deal()
     }
 
+    // Put cards cleared from the table in the discard tray.
+    pub fn discard(&mut self, cards: impl IntoIterator<Item = Card<R>>) {
+        cards.into_iter().for_each(|card| self.discards.push(card));
+    }
+
     pub fn len(&self) -> usize {
         self.shoe.len()
     }
+
+    pub fn is_empty(&self) -> bool {
+        self.shoe.is_empty()
+    }
 }
 
 // A generic Hand type if needed by callers; games often define their own.
@@ -0,0 +1,230 @@
+//! Statistical detection of loaded or controlled dice from a roll history.
+//!
+//! Each die position gets a chi-square test over its faces, and the combined faces of every
+//! roll get one more, which catches dice that look fair alone but land together (as with
+//! dice control). The tests are Bonferroni-corrected so the chance of flagging fair dice
+//! stays at `1 - confidence`, and so are the per-face binomial tests. Repeated looks at a
+//! growing history in `rolls_to_detect` are corrected the same way.
+
+use rand::Rng;
+
+use crate::games::craps::CrapsGame;
+use crate::stats;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct FaceDeviation {
+    pub face: u8,
+    pub observed: u64,
+    pub expected: f64,
+    /// Exact two-sided binomial p-value for this face alone.
+    pub p_value: f64,
+    /// Confidence that this face deviates, after correcting for the number of faces tested.
+    pub confidence: f64,
+}
+
+impl FaceDeviation {
+    pub fn is_hot(&self) -> bool {
+        self.observed as f64 > self.expected
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct DieReport {
+    pub chi_square: f64,
+    pub p_value: f64,
+    pub faces: Vec<FaceDeviation>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct DiceBiasReport {
+    pub rolls: u64,
+    /// One report per die position.
+    pub dice: Vec<DieReport>,
+    /// Chi-square over every combination of faces, e.g. 36 cells for a pair.
+    pub joint_chi_square: f64,
+    pub joint_p_value: f64,
+    /// True when any test rejects fair, independent dice at the detector's confidence.
+    pub biased: bool,
+}
+
+impl DiceBiasReport {
+    // (die position, face) for every face whose individual test is significant.
+    pub fn deviating(&self, confidence: f64) -> Vec<(usize, FaceDeviation)> {
+        self.dice
+            .iter()
+            .enumerate()
+            .flat_map(|(i, die)| die.faces.iter().filter(|f| f.confidence >= confidence).map(move |f| (i, *f)))
+            .collect()
+    }
+}
+
+pub struct DiceBiasDetector {
+    pub sides: u8,
+    /// e.g. 0.99 to reject fair dice at the 1% level
+    pub confidence: f64,
+}
+
+impl DiceBiasDetector {
+    pub fn new(sides: u8, confidence: f64) -> Self {
+        DiceBiasDetector { sides, confidence }
+    }
+
+    // Rolls with a face outside 1..=sides are ignored.
+    pub fn analyze<const N: usize>(&self, history: &[[u8; N]]) -> DiceBiasReport {
+        let sides = self.sides as usize;
+        let valid: Vec<&[u8; N]> = history
+            .iter()
+            .filter(|roll| roll.iter().all(|&f| f >= 1 && f <= self.sides))
+            .collect();
+        let rolls = valid.len() as u64;
+        let p_value_of = |observed: &[u64]| {
+            let expected = vec![rolls as f64 / observed.len() as f64; observed.len()];
+            let chi_square = stats::chi_square_statistic(observed, &expected);
+            let p = if rolls == 0 { 1.0 } else { stats::chi_square_sf(chi_square, observed.len() - 1) };
+            (chi_square, p)
+        };
+
+        let dice: Vec<DieReport> = (0..N)
+            .map(|position| {
+                let mut observed = vec![0u64; sides];
+                for roll in &valid {
+                    observed[roll[position] as usize - 1] += 1;
+                }
+                let (chi_square, p_value) = p_value_of(&observed);
+                let p = 1.0 / sides as f64;
+                let faces = observed
+                    .iter()
+                    .enumerate()
+                    .map(|(i, &count)| {
+                        let p_value = stats::binomial_two_sided(count, rolls, p);
+                        let corrected = (p_value * (sides * N) as f64).min(1.0);
+                        FaceDeviation {
+                            face: i as u8 + 1,
+                            observed: count,
+                            expected: rolls as f64 * p,
+                            p_value,
+                            confidence: 1.0 - corrected,
+                        }
+                    })
+                    .collect();
+                DieReport { chi_square, p_value, faces }
+            })
+            .collect();
+
+        let mut joint = vec![0u64; sides.pow(N as u32)];
+        for roll in &valid {
+            let cell = roll.iter().fold(0, |cell, &f| cell * sides + f as usize - 1);
+            joint[cell] += 1;
+        }
+        let (joint_chi_square, joint_p_value) = p_value_of(&joint);
+
+        let alpha = (1.0 - self.confidence) / (N + 1) as f64;
+        let biased = rolls > 0 && (joint_p_value < alpha || dice.iter().any(|d| d.p_value < alpha));
+        DiceBiasReport { rolls, dice, joint_chi_square, joint_p_value, biased }
+    }
+
+    // Keep throwing in `game` until its roll history is flagged, re-testing every
+    // `check_every` rolls. Returns the history length at that point, or None if it reached
+    // `max_rolls` first.
+    //
+    // Each look is tested at 1/k of the detector's false-positive rate, with k looks planned
+    // up to `max_rolls`, so fair dice are flagged at some look with chance at most
+    // `1 - confidence`.
+    pub fn rolls_to_detect<R: Rng + ?Sized>(
+        &self,
+        game: &mut CrapsGame,
+        check_every: usize,
+        max_rolls: usize,
+        rng: &mut R,
+    ) -> Option<usize> {
+        let check_every = check_every.max(1);
+        let looks = max_rolls.saturating_sub(game.roll_history().len()).div_ceil(check_every).max(1);
+        let look = DiceBiasDetector::new(self.sides, 1.0 - (1.0 - self.confidence) / looks as f64);
+        while game.roll_history().len() < max_rolls {
+            for _ in 0..check_every.min(max_rolls - game.roll_history().len()) {
+                game.roll_dice_with(rng);
+            }
+            if look.analyze(game.roll_history()).biased {
+                return Some(game.roll_history().len());
+            }
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dice::loaded::{DiceControl, LoadedDice, WeightedDie};
+    use crate::games::craps::CrapsType;
+    use crate::player::Player;
+    use rand::SeedableRng;
+    use rand::rngs::StdRng;
+
+    fn craps() -> CrapsGame {
+        CrapsGame::new(CrapsType::StandardCraps, vec![Player::new("Shooter")])
+    }
+
+    #[test]
+    fn test_fair_dice_not_flagged() {
+        let mut game = craps();
+        let mut rng = StdRng::seed_from_u64(40);
+        for _ in 0..20_000 {
+            game.roll_dice_with(&mut rng);
+        }
+        let report = DiceBiasDetector::new(6, 0.999).analyze(game.roll_history());
+        assert_eq!(report.rolls, 20_000);
+        assert_eq!(report.dice.len(), 2);
+        assert!(!report.biased);
+        assert!(report.deviating(0.999).is_empty());
+    }
+
+    #[test]
+    fn test_loaded_die_flagged() {
+        let loaded = LoadedDice::new(vec![WeightedDie::fair(6).unwrap(), WeightedDie::loaded(6, 6, 1.5).unwrap()]).unwrap();
+        let mut game = craps().with_roller(loaded).unwrap();
+        let mut rng = StdRng::seed_from_u64(2);
+        for _ in 0..20_000 {
+            game.roll_dice_with(&mut rng);
+        }
+        let report = DiceBiasDetector::new(6, 0.99).analyze(game.roll_history());
+        assert!(report.biased);
+        let deviating = report.deviating(0.99);
+        assert!(deviating.iter().any(|&(die, face)| die == 1 && face.face == 6 && face.is_hot()));
+        assert!(deviating.iter().all(|&(die, _)| die == 1));
+    }
+
+    #[test]
+    fn test_dice_control_caught_jointly() {
+        // all-sevens set: each die alone stays close to fair, but the pairs do not
+        let mut game = craps().with_roller(DiceControl::all_sevens_set(0.15).unwrap()).unwrap();
+        let mut rng = StdRng::seed_from_u64(3);
+        let detector = DiceBiasDetector::new(6, 0.99);
+        let rolls = detector.rolls_to_detect(&mut game, 250, 50_000, &mut rng);
+        assert!(rolls.is_some());
+        assert_eq!(rolls.unwrap() % 250, 0);
+        let report = detector.analyze(game.roll_history());
+        assert!(report.joint_p_value < report.dice[0].p_value);
+    }
+
+    #[test]
+    fn test_rolls_to_detect_fair_dice_false_alarms() {
+        // 20 looks per game; uncorrected, each game would be flagged far more than 5% of the time
+        let detector = DiceBiasDetector::new(6, 0.95);
+        let mut rng = StdRng::seed_from_u64(5);
+        let flagged = (0..100)
+            .filter(|_| detector.rolls_to_detect(&mut craps(), 100, 2_000, &mut rng).is_some())
+            .count();
+        assert!(flagged <= 10, "{flagged} fair games flagged");
+    }
+
+    #[test]
+    fn test_three_dice_history() {
+        let mut rng = StdRng::seed_from_u64(4);
+        let history: Vec<[u8; 3]> =
+            (0..5_000).map(|_| [0; 3].map(|_| rng.random_range(1..=6))).collect();
+        let report = DiceBiasDetector::new(6, 0.999).analyze(&history);
+        assert_eq!(report.dice.len(), 3);
+        assert!(!report.biased);
+    }
+}
@@ -0,0 +1,555 @@
+//! Dice expressions such as `3d6+2`, `4d6kh3`, `2d10!` and `d%`.
+//!
+//! An expression is a sum of dice terms and constants joined by `+` or `-`. A dice term is
+//! `NdS` (N defaults to 1, S may be `%` for 100) followed by any of these modifiers:
+//!
+//! - `khN` / `klN`: keep the highest or lowest N dice (`k` alone means `kh`)
+//! - `dhN` / `dlN`: drop the highest or lowest N dice
+//! - `!`: explode, rolling an extra die whenever a die shows its highest face
+//! - `rN`: reroll any die showing N until it doesn't; `roN` rerolls only once
+//!
+//! The number after `k`, `d` and `!` defaults to 1 (or the highest face for `!`). Explode
+//! and reroll targets may be written as `>N` (N or more), `<N` (N or less) or `=N`, e.g.
+//! `3d6!>5` or `4d6r<2`.
+
+use std::fmt;
+use std::str::FromStr;
+
+use rand::Rng;
+
+use crate::dice::Die;
+
+// Keeps a typo like `1000000d6` from allocating forever.
+const MAX_DICE: u32 = 1000;
+// Upper bound on rerolls and explosions per die.
+const MAX_REROLLS: usize = 100;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ParseError {
+    /// character offset into the expression
+    pub position: usize,
+    pub message: String,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid dice expression at position {}: {}", self.position, self.message)
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Compare {
+    Equal(u32),
+    AtLeast(u32),
+    AtMost(u32),
+}
+
+impl Compare {
+    pub fn matches(&self, value: u32) -> bool {
+        match *self {
+            Compare::Equal(n) => value == n,
+            Compare::AtLeast(n) => value >= n,
+            Compare::AtMost(n) => value <= n,
+        }
+    }
+
+    fn matches_every_face(&self, sides: u32) -> bool {
+        (1..=sides).all(|face| self.matches(face))
+    }
+}
+
+impl fmt::Display for Compare {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Compare::Equal(n) => write!(f, "{n}"),
+            Compare::AtLeast(n) => write!(f, ">{n}"),
+            Compare::AtMost(n) => write!(f, "<{n}"),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Keep {
+    Highest(u32),
+    Lowest(u32),
+    DropHighest(u32),
+    DropLowest(u32),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Reroll {
+    pub when: Compare,
+    /// reroll at most once rather than until the die no longer matches
+    pub once: bool,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DiceTerm {
+    pub count: u32,
+    pub die: Die,
+    pub keep: Option<Keep>,
+    pub explode: Option<Compare>,
+    pub reroll: Option<Reroll>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Term {
+    Dice(DiceTerm),
+    Constant(u32),
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SignedTerm {
+    pub negative: bool,
+    pub term: Term,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DiceExpr {
+    pub terms: Vec<SignedTerm>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DieRoll {
+    /// the face the die finished on
+    pub value: u32,
+    /// every face rolled, including rerolls, in order
+    pub rolls: Vec<u32>,
+    /// true for the extra dice added by explosions
+    pub exploded: bool,
+    /// false if a keep or drop modifier discarded the die
+    pub kept: bool,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TermRoll {
+    pub negative: bool,
+    /// empty for constants
+    pub dice: Vec<DieRoll>,
+    /// the term's value before its sign is applied
+    pub subtotal: i64,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Roll {
+    pub total: i64,
+    pub terms: Vec<TermRoll>,
+}
+
+impl DiceExpr {
+    pub fn parse(expr: &str) -> Result<Self, ParseError> {
+        Parser { chars: expr.chars().collect(), pos: 0 }.expression()
+    }
+
+    pub fn roll(&self) -> Roll {
+        let mut rng = rand::rng();
+        self.roll_with(&mut rng)
+    }
+
+    pub fn roll_with<R: Rng + ?Sized>(&self, rng: &mut R) -> Roll {
+        let terms: Vec<TermRoll> = self
+            .terms
+            .iter()
+            .map(|signed| match &signed.term {
+                Term::Constant(n) => TermRoll { negative: signed.negative, dice: Vec::new(), subtotal: *n as i64 },
+                Term::Dice(dice) => {
+                    let dice = dice.roll_with(rng);
+                    let subtotal = dice.iter().filter(|d| d.kept).map(|d| d.value as i64).sum();
+                    TermRoll { negative: signed.negative, dice, subtotal }
+                }
+            })
+            .collect();
+        let total = terms.iter().map(|t| if t.negative { -t.subtotal } else { t.subtotal }).sum();
+        Roll { total, terms }
+    }
+}
+
+impl DiceTerm {
+    pub fn roll_with<R: Rng + ?Sized>(&self, rng: &mut R) -> Vec<DieRoll> {
+        let mut dice = Vec::new();
+        for _ in 0..self.count {
+            let mut roll = self.roll_one(false, rng);
+            let mut explosions = 0;
+            while self.explode.is_some_and(|e| e.matches(roll.value)) && explosions < MAX_REROLLS {
+                dice.push(roll);
+                roll = self.roll_one(true, rng);
+                explosions += 1;
+            }
+            dice.push(roll);
+        }
+        if let Some(keep) = self.keep {
+            let mut order: Vec<usize> = (0..dice.len()).collect();
+            order.sort_by_key(|&i| dice[i].value);
+            let n = dice.len();
+            let dropped: Vec<usize> = match keep {
+                Keep::Highest(k) => order[..n.saturating_sub(k as usize)].to_vec(),
+                Keep::Lowest(k) => order[(k as usize).min(n)..].to_vec(),
+                Keep::DropHighest(k) => order[n.saturating_sub(k as usize)..].to_vec(),
+                Keep::DropLowest(k) => order[..(k as usize).min(n)].to_vec(),
+            };
+            for i in dropped {
+                dice[i].kept = false;
+            }
+        }
+        dice
+    }
+
+    fn roll_one<R: Rng + ?Sized>(&self, exploded: bool, rng: &mut R) -> DieRoll {
+        let mut rolls = vec![u32::from(self.die.roll_with(rng))];
+        if let Some(reroll) = self.reroll {
+            let limit = if reroll.once { 1 } else { MAX_REROLLS };
+            while reroll.when.matches(rolls[rolls.len() - 1]) && rolls.len() <= limit {
+                rolls.push(u32::from(self.die.roll_with(rng)));
+            }
+        }
+        DieRoll { value: rolls[rolls.len() - 1], rolls, exploded, kept: true }
+    }
+}
+
+impl FromStr for DiceExpr {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        DiceExpr::parse(s)
+    }
+}
+
+impl fmt::Display for DiceTerm {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}d{}", self.count, self.die.sides())?;
+        match self.keep {
+            Some(Keep::Highest(n)) => write!(f, "kh{n}")?,
+            Some(Keep::Lowest(n)) => write!(f, "kl{n}")?,
+            Some(Keep::DropHighest(n)) => write!(f, "dh{n}")?,
+            Some(Keep::DropLowest(n)) => write!(f, "dl{n}")?,
+            None => {}
+        }
+        match self.explode {
+            Some(Compare::Equal(n)) if n == u32::from(self.die.sides()) => write!(f, "!")?,
+            Some(when) => write!(f, "!{when}")?,
+            None => {}
+        }
+        if let Some(reroll) = self.reroll {
+            write!(f, "r{}{}", if reroll.once { "o" } else { "" }, reroll.when)?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for DiceExpr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, signed) in self.terms.iter().enumerate() {
+            match (i, signed.negative) {
+                (0, true) => write!(f, "-")?,
+                (0, false) => {}
+                (_, true) => write!(f, "-")?,
+                (_, false) => write!(f, "+")?,
+            }
+            match &signed.term {
+                Term::Dice(dice) => write!(f, "{dice}")?,
+                Term::Constant(n) => write!(f, "{n}")?,
+            }
+        }
+        Ok(())
+    }
+}
+
+struct Parser {
+    chars: Vec<char>,
+    pos: usize,
+}
+
+impl Parser {
+    fn error<T>(&self, position: usize, message: impl Into<String>) -> Result<T, ParseError> {
+        Err(ParseError { position, message: message.into() })
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self.chars.get(self.pos).is_some_and(|c| c.is_whitespace()) {
+            self.pos += 1;
+        }
+    }
+
+    fn peek(&mut self) -> Option<char> {
+        self.skip_whitespace();
+        self.chars.get(self.pos).map(|c| c.to_ascii_lowercase())
+    }
+
+    fn eat(&mut self, c: char) -> bool {
+        if self.peek() == Some(c) {
+            self.pos += 1;
+            return true;
+        }
+        false
+    }
+
+    fn number(&mut self) -> Result<Option<u32>, ParseError> {
+        self.skip_whitespace();
+        let start = self.pos;
+        while self.chars.get(self.pos).is_some_and(|c| c.is_ascii_digit()) {
+            self.pos += 1;
+        }
+        if start == self.pos {
+            return Ok(None);
+        }
+        let digits: String = self.chars[start..self.pos].iter().collect();
+        match digits.parse() {
+            Ok(n) => Ok(Some(n)),
+            Err(_) => self.error(start, "number too large"),
+        }
+    }
+
+    fn expression(mut self) -> Result<DiceExpr, ParseError> {
+        let mut terms = Vec::new();
+        let mut negative = self.eat('-');
+        loop {
+            terms.push(SignedTerm { negative, term: self.term()? });
+            negative = match self.peek() {
+                Some('+') => false,
+                Some('-') => true,
+                None => break,
+                Some(c) => return self.error(self.pos, format!("unexpected '{c}'")),
+            };
+            self.pos += 1;
+        }
+        Ok(DiceExpr { terms })
+    }
+
+    fn term(&mut self) -> Result<Term, ParseError> {
+        let start = self.pos;
+        let count = self.number()?;
+        if !self.eat('d') {
+            return match count {
+                Some(n) => Ok(Term::Constant(n)),
+                None if self.peek().is_none() => self.error(self.pos, "expected a term"),
+                None => self.error(self.pos, "expected a number or dice"),
+            };
+        }
+        let count = count.unwrap_or(1);
+        if count == 0 || count > MAX_DICE {
+            return self.error(start, format!("dice count must be between 1 and {MAX_DICE}"));
+        }
+        let sides_at = self.pos;
+        let sides = if self.eat('%') {
+            100
+        } else {
+            match self.number()? {
+                Some(n) => n,
+                None => return self.error(sides_at, "expected the number of sides"),
+            }
+        };
+        let Some(die) = u8::try_from(sides).ok().and_then(Die::custom) else {
+            return self.error(sides_at, format!("a die must have between 1 and {} sides", u8::MAX));
+        };
+        let mut dice = DiceTerm { count, die, keep: None, explode: None, reroll: None };
+        self.modifiers(&mut dice)?;
+        Ok(Term::Dice(dice))
+    }
+
+    fn modifiers(&mut self, dice: &mut DiceTerm) -> Result<(), ParseError> {
+        let sides = u32::from(dice.die.sides());
+        loop {
+            let at = self.pos;
+            match self.peek() {
+                Some('k') | Some('d') => {
+                    let drop = self.chars[self.pos].eq_ignore_ascii_case(&'d');
+                    self.pos += 1;
+                    let lowest = match self.peek() {
+                        Some('h') => false,
+                        Some('l') => true,
+                        _ if !drop => {
+                            self.pos -= 1;
+                            false
+                        }
+                        _ => return self.error(self.pos, "expected 'h' or 'l' after 'd'"),
+                    };
+                    self.pos += 1;
+                    if dice.keep.is_some() {
+                        return self.error(at, "only one keep or drop modifier is allowed");
+                    }
+                    let n = self.number()?.unwrap_or(1);
+                    dice.keep = Some(match (drop, lowest) {
+                        (false, false) => Keep::Highest(n),
+                        (false, true) => Keep::Lowest(n),
+                        (true, false) => Keep::DropHighest(n),
+                        (true, true) => Keep::DropLowest(n),
+                    });
+                }
+                Some('!') => {
+                    self.pos += 1;
+                    if dice.explode.is_some() {
+                        return self.error(at, "dice can only explode once");
+                    }
+                    let when = self.compare()?.unwrap_or(Compare::Equal(sides));
+                    if when.matches_every_face(sides) {
+                        return self.error(at, "dice would explode on every face");
+                    }
+                    dice.explode = Some(when);
+                }
+                Some('r') => {
+                    self.pos += 1;
+                    let once = self.eat('o');
+                    if dice.reroll.is_some() {
+                        return self.error(at, "only one reroll modifier is allowed");
+                    }
+                    let Some(when) = self.compare()? else {
+                        return self.error(self.pos, "expected a reroll target");
+                    };
+                    if !once && when.matches_every_face(sides) {
+                        return self.error(at, "dice would reroll on every face");
+                    }
+                    dice.reroll = Some(Reroll { when, once });
+                }
+                _ => return Ok(()),
+            }
+        }
+    }
+
+    fn compare(&mut self) -> Result<Option<Compare>, ParseError> {
+        let at = self.pos;
+        let make: fn(u32) -> Compare = if self.eat('>') {
+            Compare::AtLeast
+        } else if self.eat('<') {
+            Compare::AtMost
+        } else {
+            self.eat('=');
+            Compare::Equal
+        };
+        match self.number()? {
+            Some(n) => Ok(Some(make(n))),
+            None if self.pos > at => self.error(self.pos, "expected a number"),
+            None => Ok(None),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+    use rand::rngs::StdRng;
+
+    fn parse(expr: &str) -> DiceExpr {
+        DiceExpr::parse(expr).unwrap()
+    }
+
+    #[test]
+    fn test_parse_and_display() {
+        assert_eq!(parse("3d6+2").to_string(), "3d6+2");
+        assert_eq!(parse(" 4D6 kh3 ").to_string(), "4d6kh3");
+        assert_eq!(parse("d%").to_string(), "1d100");
+        assert_eq!(parse("2d10!").to_string(), "2d10!");
+        assert_eq!(parse("-1d4-1+d8").to_string(), "-1d4-1+1d8");
+        assert_eq!(parse("3d6!>5r<1").to_string(), "3d6!>5r<1");
+        assert_eq!(parse("4d6k").to_string(), "4d6kh1");
+        assert_eq!(parse("4d6dl").to_string(), "4d6dl1");
+        assert_eq!(parse("2d7ro1").to_string(), "2d7ro1");
+        let Term::Dice(dice) = &parse("3d13").terms[0].term else { panic!("expected dice") };
+        assert_eq!(dice.die.sides(), 13);
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let err = |expr: &str| DiceExpr::parse(expr).unwrap_err();
+        assert_eq!(err("").position, 0);
+        assert_eq!(err("3d").message, "expected the number of sides");
+        assert_eq!(err("3d6+").position, 4);
+        assert_eq!(err("3d6 x").message, "unexpected 'x'");
+        assert_eq!(err("0d6").message, "dice count must be between 1 and 1000");
+        assert_eq!(err("2d0").message, "a die must have between 1 and 255 sides");
+        assert_eq!(err("1d256").message, "a die must have between 1 and 255 sides");
+        assert_eq!(err("1d4000000000").position, 2);
+        assert_eq!(err("1d1!").message, "dice would explode on every face");
+        assert_eq!(err("2d6r<6").message, "dice would reroll on every face");
+        assert_eq!(err("2d6r").message, "expected a reroll target");
+        assert_eq!(err("2d6kh1kl1").message, "only one keep or drop modifier is allowed");
+        assert_eq!(err("99999999999").message, "number too large");
+        assert_eq!(
+            err("2d6dx").to_string(),
+            "invalid dice expression at position 4: expected 'h' or 'l' after 'd'"
+        );
+    }
+
+    #[test]
+    fn test_roll_totals() {
+        let mut rng = StdRng::seed_from_u64(37);
+        let expr = parse("3d6+2");
+        for _ in 0..500 {
+            let roll = expr.roll_with(&mut rng);
+            assert!((5..=20).contains(&roll.total));
+            assert_eq!(roll.terms[0].dice.len(), 3);
+            let dice: i64 = roll.terms[0].dice.iter().map(|d| d.value as i64).sum();
+            assert_eq!(roll.total, dice + 2);
+        }
+        let roll = parse("d4-10").roll_with(&mut rng);
+        assert!((-9..=-6).contains(&roll.total));
+    }
+
+    #[test]
+    fn test_keep_and_drop() {
+        let mut rng = StdRng::seed_from_u64(1);
+        for expr in ["4d6kh3", "4d6dl1"] {
+            for _ in 0..200 {
+                let roll = parse(expr).roll_with(&mut rng);
+                let dice = &roll.terms[0].dice;
+                assert_eq!(dice.iter().filter(|d| d.kept).count(), 3);
+                let lowest = dice.iter().map(|d| d.value).min().unwrap();
+                let dropped = dice.iter().find(|d| !d.kept).unwrap();
+                assert_eq!(dropped.value, lowest);
+                let kept: i64 = dice.iter().filter(|d| d.kept).map(|d| d.value as i64).sum();
+                assert_eq!(roll.total, kept);
+            }
+        }
+        let roll = parse("2d20kl1").roll_with(&mut rng);
+        let dice = &roll.terms[0].dice;
+        assert_eq!(roll.total, dice.iter().map(|d| d.value as i64).min().unwrap());
+    }
+
+    #[test]
+    fn test_exploding() {
+        let mut rng = StdRng::seed_from_u64(10);
+        let mut exploded = 0;
+        for _ in 0..1000 {
+            let roll = parse("2d6!").roll_with(&mut rng);
+            let dice = &roll.terms[0].dice;
+            assert_eq!(dice.iter().filter(|d| !d.exploded).count(), 2);
+            // every 6 is followed by an exploded die
+            for (i, die) in dice.iter().enumerate() {
+                if die.value == 6 {
+                    assert!(dice[i + 1].exploded);
+                }
+            }
+            exploded += dice.iter().filter(|d| d.exploded).count();
+        }
+        // each die chains explosions with chance 1/6: 2 * (1/6) / (1 - 1/6) = 0.4 extra dice a roll
+        assert!((300..500).contains(&exploded));
+    }
+
+    #[test]
+    fn test_reroll() {
+        let mut rng = StdRng::seed_from_u64(2);
+        for _ in 0..500 {
+            let roll = parse("4d6r<2").roll_with(&mut rng);
+            for die in &roll.terms[0].dice {
+                assert!(die.value >= 3);
+                assert!(die.rolls[..die.rolls.len() - 1].iter().all(|&r| r <= 2));
+            }
+            let roll = parse("4d6ro1").roll_with(&mut rng);
+            for die in &roll.terms[0].dice {
+                assert!(die.rolls.len() <= 2);
+                if die.rolls.len() == 2 {
+                    assert_eq!(die.rolls[0], 1);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_percentile() {
+        let mut rng = StdRng::seed_from_u64(100);
+        let roll = "d%".parse::<DiceExpr>().unwrap().roll_with(&mut rng);
+        assert!((1..=100).contains(&roll.total));
+    }
+}
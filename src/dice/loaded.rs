@@ -0,0 +1,284 @@
+//! Non-uniform dice for testing and fraud modelling.
+//!
+//! Games roll through the `DiceRoller` trait, so a table can be handed fair dice (any `Die`),
+//! loaded dice with explicit per-face probabilities, or a `DiceControl` shooter who keeps the
+//! dice on axis.
+
+use rand::distr::Distribution;
+use rand::distr::weighted::WeightedIndex;
+use rand::{Rng, RngCore};
+
+use crate::dice::Die;
+
+pub trait DiceRoller {
+    // Roll `count` dice and return each face, in order.
+    fn roll_dice(&mut self, count: usize, rng: &mut dyn RngCore) -> Vec<u32>;
+
+    // Number of faces on each die thrown; every face rolled is within 1..=sides.
+    fn sides(&self) -> u32;
+}
+
+impl DiceRoller for Die {
+    fn sides(&self) -> u32 {
+        u32::from(Die::sides(self))
+    }
+
+    fn roll_dice(&mut self, count: usize, rng: &mut dyn RngCore) -> Vec<u32> {
+        (0..count).map(|_| u32::from(self.roll_with(rng))).collect()
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct WeightedDie {
+    /// probability of each face, 1 first; always sums to 1
+    probabilities: Vec<f64>,
+    dist: WeightedIndex<f64>,
+}
+
+impl WeightedDie {
+    // Weights are normalised. Returns None if there are no faces or a weight is negative,
+    // non-finite, or they are all zero.
+    pub fn new(weights: Vec<f64>) -> Option<Self> {
+        if weights.iter().any(|w| !w.is_finite()) {
+            return None;
+        }
+        let dist = WeightedIndex::new(&weights).ok()?;
+        let total: f64 = weights.iter().sum();
+        Some(Self { probabilities: weights.iter().map(|w| w / total).collect(), dist })
+    }
+
+    // A die with `sides` faces where `face` is `factor` times as likely as each other face.
+    pub fn loaded(sides: u32, face: u32, factor: f64) -> Option<Self> {
+        if face == 0 || face > sides {
+            return None;
+        }
+        let mut weights = vec![1.0; sides as usize];
+        weights[face as usize - 1] = factor;
+        Self::new(weights)
+    }
+
+    pub fn fair(sides: u32) -> Option<Self> {
+        Self::new(vec![1.0; sides as usize])
+    }
+
+    pub fn sides(&self) -> u32 {
+        self.probabilities.len() as u32
+    }
+
+    pub fn probability(&self, face: u32) -> f64 {
+        match face {
+            0 => 0.0,
+            f => self.probabilities.get(f as usize - 1).copied().unwrap_or(0.0),
+        }
+    }
+
+    pub fn roll_with<R: Rng + ?Sized>(&self, rng: &mut R) -> u32 {
+        self.dist.sample(rng) as u32 + 1
+    }
+}
+
+impl DiceRoller for WeightedDie {
+    fn sides(&self) -> u32 {
+        WeightedDie::sides(self)
+    }
+
+    fn roll_dice(&mut self, count: usize, rng: &mut dyn RngCore) -> Vec<u32> {
+        (0..count).map(|_| self.roll_with(rng)).collect()
+    }
+}
+
+// A mixed set, e.g. one loaded die slipped in with a fair one. Die `i` of a roll is
+// `dice[i % dice.len()]`.
+#[derive(Clone, Debug)]
+pub struct LoadedDice {
+    dice: Vec<WeightedDie>,
+}
+
+impl LoadedDice {
+    // Returns None for an empty set or dice with different numbers of sides.
+    pub fn new(dice: Vec<WeightedDie>) -> Option<Self> {
+        let sides = dice.first()?.sides();
+        dice.iter().all(|die| die.sides() == sides).then_some(Self { dice })
+    }
+
+    pub fn dice(&self) -> &[WeightedDie] {
+        &self.dice
+    }
+}
+
+impl DiceRoller for LoadedDice {
+    fn sides(&self) -> u32 {
+        self.dice[0].sides()
+    }
+
+    fn roll_dice(&mut self, count: usize, rng: &mut dyn RngCore) -> Vec<u32> {
+        (0..count).map(|i| self.dice[i % self.dice.len()].roll_with(rng)).collect()
+    }
+}
+
+// The four faces around the axis through `face` and its opposite, in rolling order.
+fn axis_ring(face: u32) -> [u32; 4] {
+    match face {
+        1 | 6 => [2, 3, 5, 4],
+        2 | 5 => [1, 3, 6, 4],
+        _ => [1, 2, 6, 5],
+    }
+}
+
+// A skilled craps shooter. The two dice are set with `tops` showing and thrown to roll
+// around the same axis. A controlled throw (chance `skill`) keeps both dice on axis and in
+// step, so the pair can only land on the four combinations the set allows; any other throw
+// is fair.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct DiceControl {
+    /// one end of the rotation axis; the other end is `7 - axis`
+    axis: u32,
+    tops: [u32; 2],
+    skill: f64,
+}
+
+impl DiceControl {
+    // Returns None unless both tops lie on the ring around the axis and skill is within [0, 1]
+    // (NaN is not).
+    pub fn new(axis: u32, tops: [u32; 2], skill: f64) -> Option<Self> {
+        if !(1..=6).contains(&axis) || !(0.0..=1.0).contains(&skill) {
+            return None;
+        }
+        let ring = axis_ring(axis);
+        if tops.iter().any(|top| !ring.contains(top)) {
+            return None;
+        }
+        Some(Self { axis, tops, skill })
+    }
+
+    // Both dice set with the same face up: on axis, only hardway pairs can show. Returns None
+    // unless skill is within [0, 1].
+    pub fn hardway_set(skill: f64) -> Option<Self> {
+        Self::new(1, [2, 2], skill)
+    }
+
+    // Opposite faces up: on axis, every pair totals seven. Used on the come-out roll. Returns
+    // None unless skill is within [0, 1].
+    pub fn all_sevens_set(skill: f64) -> Option<Self> {
+        Self::new(1, [2, 5], skill)
+    }
+
+    pub fn axis(&self) -> u32 {
+        self.axis
+    }
+
+    pub fn tops(&self) -> [u32; 2] {
+        self.tops
+    }
+
+    // Chance that a throw is controlled.
+    pub fn skill(&self) -> f64 {
+        self.skill
+    }
+
+    // The pairs a controlled throw can land on.
+    pub fn controlled_pairs(&self) -> [[u32; 2]; 4] {
+        let ring = axis_ring(self.axis);
+        let position = |face| ring.iter().position(|&f| f == face).unwrap_or(0);
+        let offset = (position(self.tops[1]) + 4 - position(self.tops[0])) % 4;
+        [0, 1, 2, 3].map(|k| [ring[k], ring[(k + offset) % 4]])
+    }
+
+    // Exact chance of each total from 2 to 12 on one throw of the pair.
+    pub fn total_probability(&self, total: u32) -> f64 {
+        let fair = (1..=6).filter(|&a| (1..=6).contains(&(total as i64 - a))).count() as f64 / 36.0;
+        let controlled = self.controlled_pairs().iter().filter(|p| p[0] + p[1] == total).count() as f64 / 4.0;
+        self.skill * controlled + (1.0 - self.skill) * fair
+    }
+}
+
+impl DiceRoller for DiceControl {
+    fn sides(&self) -> u32 {
+        6
+    }
+
+    fn roll_dice(&mut self, count: usize, rng: &mut dyn RngCore) -> Vec<u32> {
+        let mut faces = Vec::with_capacity(count);
+        if count >= 2 && rng.random_bool(self.skill) {
+            faces.extend(self.controlled_pairs()[rng.random_range(0..4)]);
+        }
+        while faces.len() < count {
+            faces.push(u32::from(Die::D6.roll_with(rng)));
+        }
+        faces
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+    use rand::rngs::StdRng;
+
+    #[test]
+    fn test_weighted_die() {
+        assert!(WeightedDie::new(vec![]).is_none());
+        assert!(WeightedDie::new(vec![0.0, 0.0]).is_none());
+        assert!(WeightedDie::new(vec![1.0, -1.0]).is_none());
+        assert!(WeightedDie::loaded(6, 7, 2.0).is_none());
+        let die = WeightedDie::loaded(6, 6, 5.0).unwrap();
+        assert_eq!(die.sides(), 6);
+        assert!((die.probability(6) - 0.5).abs() < 1e-12);
+        assert!((die.probability(1) - 0.1).abs() < 1e-12);
+
+        let mut rng = StdRng::seed_from_u64(40);
+        let sixes = (0..10_000).filter(|_| die.roll_with(&mut rng) == 6).count();
+        assert!((4_800..5_200).contains(&sixes));
+    }
+
+    #[test]
+    fn test_loaded_dice_positions() {
+        assert!(LoadedDice::new(vec![]).is_none());
+        assert!(LoadedDice::new(vec![WeightedDie::fair(6).unwrap(), WeightedDie::fair(4).unwrap()]).is_none());
+        let mut dice = LoadedDice::new(vec![
+            WeightedDie::fair(6).unwrap(),
+            WeightedDie::new(vec![0.0, 0.0, 1.0, 0.0, 0.0, 0.0]).unwrap(),
+        ])
+        .unwrap();
+        assert_eq!(DiceRoller::sides(&dice), 6);
+        let mut rng = StdRng::seed_from_u64(1);
+        for _ in 0..100 {
+            let faces = dice.roll_dice(3, &mut rng);
+            assert!((1..=6).contains(&faces[0]));
+            assert_eq!(faces[1], 3);
+        }
+    }
+
+    #[test]
+    fn test_dice_control_sets() {
+        assert!(DiceControl::new(1, [1, 2], 0.5).is_none());
+        assert!(DiceControl::new(3, [1, 6], 1.5).is_none());
+        assert!(DiceControl::new(3, [1, 6], f64::NAN).is_none());
+        assert!(DiceControl::hardway_set(2.0).is_none());
+        assert!(DiceControl::all_sevens_set(f64::NAN).is_none());
+        let hardway = DiceControl::hardway_set(1.0).unwrap();
+        assert_eq!((hardway.axis(), hardway.tops(), hardway.skill()), (1, [2, 2], 1.0));
+        assert!(hardway.controlled_pairs().iter().all(|p| p[0] == p[1]));
+        assert_eq!(hardway.total_probability(7), 0.0);
+        let sevens = DiceControl::all_sevens_set(1.0).unwrap();
+        assert!(sevens.controlled_pairs().iter().all(|p| p[0] + p[1] == 7));
+
+        // a 20% controlled shooter sevens out 0.8 / 6 of the time
+        let shooter = DiceControl::hardway_set(0.2).unwrap();
+        assert!((shooter.total_probability(7) - 0.8 / 6.0).abs() < 1e-12);
+        let total: f64 = (2..=12).map(|t| shooter.total_probability(t)).sum();
+        assert!((total - 1.0).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_dice_control_rolls() {
+        let mut shooter = DiceControl::hardway_set(0.3).unwrap();
+        let mut rng = StdRng::seed_from_u64(7);
+        let rolls = 20_000;
+        let sevens = (0..rolls)
+            .filter(|_| shooter.roll_dice(2, &mut rng).iter().sum::<u32>() == 7)
+            .count();
+        let expected = shooter.total_probability(7) * rolls as f64;
+        assert!((sevens as f64 - expected).abs() < 4.0 * expected.sqrt());
+    }
+}
@@ -1,10 +1,15 @@
-/// A module for Dice related functions.
+//! A module for Dice related functions.
 
+pub mod bias;
+pub mod expr;
+pub mod loaded;
+pub mod pmf;
 #[cfg(feature = "python")]
 pub mod python_bindings;
 
 use rand::Rng;
 
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
 pub enum Die {
     D4,
     D6,
@@ -13,6 +18,19 @@ pub enum Die {
     D12,
     D20,
     D100,
+    /// any other number of sides, built with `Die::custom`
+    Custom(Sides),
+}
+
+// The side count of a `Die::Custom`: from 1 to 255 and never that of a standard die, so each
+// number of sides has exactly one `Die`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Sides(u8);
+
+impl Sides {
+    pub fn get(&self) -> u8 {
+        self.0
+    }
 }
 
 impl Die {
@@ -29,6 +47,14 @@ impl Die {
         }
     }
 
+    // A die with 1 to 255 sides, using the standard variant when there is one.
+    pub fn custom(sides: u8) -> Option<Self> {
+        match sides {
+            0 => None,
+            s => Some(Die::new(s).unwrap_or(Die::Custom(Sides(s)))),
+        }
+    }
+
     pub fn sides(&self) -> u8 {
         match self {
             Die::D4 => 4,
@@ -38,11 +64,16 @@ impl Die {
             Die::D12 => 12,
             Die::D20 => 20,
             Die::D100 => 100,
+            Die::Custom(sides) => sides.get(),
         }
     }
 
     pub fn roll(&self) -> u8 {
         let mut rng = rand::rng();
+        self.roll_with(&mut rng)
+    }
+
+    pub fn roll_with<R: Rng + ?Sized>(&self, rng: &mut R) -> u8 {
         rng.random_range(1..=self.sides())
     }
 }
@@ -68,18 +99,37 @@ mod tests {
         assert_eq!(die.sides(), 4);
     }
 
+    #[test]
+    fn test_custom_die() {
+        assert_eq!(Die::custom(6), Some(Die::D6));
+        assert_eq!(Die::custom(7).unwrap().sides(), 7);
+        assert_eq!(Die::custom(255).unwrap().sides(), 255);
+        assert!(Die::custom(0).is_none());
+    }
+
+    #[test]
+    fn test_custom_die_equals_standard_die() {
+        use std::collections::HashSet;
+        assert!(matches!(Die::custom(20), Some(Die::D20)));
+        assert_ne!(Die::custom(7), Some(Die::D6));
+        let dice: HashSet<Die> = [Die::D20, Die::custom(20).unwrap(), Die::custom(13).unwrap()].into_iter().collect();
+        assert_eq!(dice.len(), 2);
+        let one = Die::custom(1).unwrap();
+        assert!((0..20).all(|_| one.roll() == 1));
+    }
+
     #[test]
     fn test_die_roll() {
         let die = Die::new(6).unwrap();
         for _ in 0..100 {
             let roll = die.roll();
-            assert!(roll >= 1 && roll <= 6);
+            assert!((1..=6).contains(&roll));
         }
 
         let die = Die::new(20).unwrap();
         for _ in 0..100 {
             let roll = die.roll();
-            assert!(roll >= 1 && roll <= 20);
+            assert!((1..=20).contains(&roll));
         }
     }
 }
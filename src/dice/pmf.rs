@@ -0,0 +1,232 @@
+//! Exact probability mass functions for dice totals.
+//!
+//! A `Pmf` counts the ways each total can occur out of every equally likely combination,
+//! so probabilities come out as exact rationals rather than sampled estimates. Sums of dice
+//! are built by convolution, which stays fast for hundreds of dice.
+
+use std::ops::Add;
+
+use num_bigint::{BigInt, BigUint};
+use num_rational::BigRational;
+use num_traits::{One, Zero};
+
+use crate::dice::Die;
+use crate::dice::expr::{DiceExpr, Term};
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Pmf {
+    /// smallest possible total; `counts[i]` is the number of ways to roll `min + i`
+    min: i64,
+    counts: Vec<BigUint>,
+    outcomes: BigUint,
+}
+
+impl Pmf {
+    // A fixed total that always occurs.
+    pub fn constant(value: i64) -> Self {
+        Self { min: value, counts: vec![BigUint::one()], outcomes: BigUint::one() }
+    }
+
+    pub fn die(die: &Die) -> Self {
+        let sides = die.sides() as usize;
+        Self { min: 1, counts: vec![BigUint::one(); sides], outcomes: BigUint::from(sides) }
+    }
+
+    // The distribution of the sum of `dice`.
+    pub fn sum(dice: &[Die]) -> Self {
+        dice.iter().fold(Pmf::constant(0), |pmf, die| pmf.add_die(die))
+    }
+
+    // Adds one roll of `die`. Each new count is the sum of a window of `sides` old counts,
+    // so a running sum keeps this linear in the number of totals.
+    fn add_die(&self, die: &Die) -> Pmf {
+        let sides = die.sides() as usize;
+        let mut counts = Vec::with_capacity(self.counts.len() + sides - 1);
+        let mut window = BigUint::zero();
+        for k in 0..self.counts.len() + sides - 1 {
+            if let Some(entering) = self.counts.get(k) {
+                window += entering;
+            }
+            if let Some(leaving) = k.checked_sub(sides).and_then(|i| self.counts.get(i)) {
+                window -= leaving;
+            }
+            counts.push(window.clone());
+        }
+        Pmf { min: self.min + 1, counts, outcomes: &self.outcomes * BigUint::from(sides) }
+    }
+
+    // Exact distribution of a plain sum such as `3d6+2` or `d20-1d4`. Returns None if the
+    // expression keeps, drops, explodes or rerolls dice.
+    pub fn of_expr(expr: &DiceExpr) -> Option<Self> {
+        let mut pmf = Pmf::constant(0);
+        for signed in &expr.terms {
+            let term = match &signed.term {
+                Term::Constant(n) => Pmf::constant(*n as i64),
+                Term::Dice(dice) if dice.keep.is_none() && dice.explode.is_none() && dice.reroll.is_none() => {
+                    Pmf::sum(&vec![dice.die; dice.count as usize])
+                }
+                Term::Dice(_) => return None,
+            };
+            pmf = pmf.convolve(&if signed.negative { term.negate() } else { term });
+        }
+        Some(pmf)
+    }
+
+    // The distribution of the sum of independent rolls from `self` and `other`.
+    pub fn convolve(&self, other: &Pmf) -> Pmf {
+        let mut counts = vec![BigUint::zero(); self.counts.len() + other.counts.len() - 1];
+        for (i, a) in self.counts.iter().enumerate().filter(|(_, a)| !a.is_zero()) {
+            for (j, b) in other.counts.iter().enumerate() {
+                counts[i + j] += a * b;
+            }
+        }
+        Pmf { min: self.min + other.min, counts, outcomes: &self.outcomes * &other.outcomes }
+    }
+
+    pub fn negate(&self) -> Pmf {
+        Pmf {
+            min: -self.max(),
+            counts: self.counts.iter().rev().cloned().collect(),
+            outcomes: self.outcomes.clone(),
+        }
+    }
+
+    pub fn min(&self) -> i64 {
+        self.min
+    }
+
+    pub fn max(&self) -> i64 {
+        self.min + self.counts.len() as i64 - 1
+    }
+
+    // Number of equally likely combinations, e.g. 216 for three six-sided dice.
+    pub fn outcomes(&self) -> &BigUint {
+        &self.outcomes
+    }
+
+    // Number of combinations that give `total`.
+    pub fn ways(&self, total: i64) -> BigUint {
+        if total < self.min || total > self.max() {
+            return BigUint::zero();
+        }
+        self.counts[(total - self.min) as usize].clone()
+    }
+
+    // P(total = n)
+    pub fn probability(&self, total: i64) -> BigRational {
+        self.ratio(self.ways(total))
+    }
+
+    // P(total >= n)
+    pub fn at_least(&self, total: i64) -> BigRational {
+        let start = (total - self.min).clamp(0, self.counts.len() as i64) as usize;
+        self.ratio(self.counts[start..].iter().sum())
+    }
+
+    // P(total <= n)
+    pub fn at_most(&self, total: i64) -> BigRational {
+        BigRational::one() - self.at_least(total + 1)
+    }
+
+    pub fn expected_value(&self) -> BigRational {
+        let weighted: BigInt = self
+            .counts
+            .iter()
+            .enumerate()
+            .map(|(i, ways)| BigInt::from(self.min + i as i64) * BigInt::from(ways.clone()))
+            .sum();
+        BigRational::new(weighted, BigInt::from(self.outcomes.clone()))
+    }
+
+    // Every possible total with its probability, smallest first.
+    pub fn iter(&self) -> impl Iterator<Item = (i64, BigRational)> + '_ {
+        self.counts
+            .iter()
+            .enumerate()
+            .filter(|(_, ways)| !ways.is_zero())
+            .map(|(i, ways)| (self.min + i as i64, self.ratio(ways.clone())))
+    }
+
+    fn ratio(&self, ways: BigUint) -> BigRational {
+        BigRational::new(BigInt::from(ways), BigInt::from(self.outcomes.clone()))
+    }
+}
+
+impl Add for &Pmf {
+    type Output = Pmf;
+
+    fn add(self, other: &Pmf) -> Pmf {
+        self.convolve(other)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ratio(n: i64, d: i64) -> BigRational {
+        BigRational::new(BigInt::from(n), BigInt::from(d))
+    }
+
+    #[test]
+    fn test_two_dice() {
+        let pmf = Pmf::sum(&[Die::D6, Die::D6]);
+        assert_eq!((pmf.min(), pmf.max()), (2, 12));
+        assert_eq!(pmf.outcomes(), &BigUint::from(36u32));
+        assert_eq!(pmf.probability(7), ratio(1, 6));
+        assert_eq!(pmf.probability(2), ratio(1, 36));
+        assert_eq!(pmf.probability(13), ratio(0, 1));
+        // craps come-out: natural 7 or 11 wins 8/36
+        assert_eq!(pmf.probability(7) + pmf.probability(11), ratio(2, 9));
+        assert_eq!(pmf.expected_value(), ratio(7, 1));
+        assert_eq!(pmf.iter().map(|(_, p)| p).sum::<BigRational>(), ratio(1, 1));
+    }
+
+    #[test]
+    fn test_three_dice_tails() {
+        let pmf = Pmf::sum(&[Die::D6; 3]);
+        // Sic Bo big: totals 11 to 17, before the triple exclusion
+        assert_eq!(pmf.at_least(11), ratio(1, 2));
+        assert_eq!(pmf.at_least(11) - pmf.probability(18), ratio(107, 216));
+        assert_eq!(pmf.at_most(10), ratio(1, 2));
+        assert_eq!(pmf.at_least(0), ratio(1, 1));
+        assert_eq!(pmf.at_least(19), ratio(0, 1));
+        assert_eq!(pmf.ways(10), BigUint::from(27u32));
+    }
+
+    #[test]
+    fn test_mixed_and_custom_dice() {
+        let pmf = Pmf::sum(&[Die::D4, Die::D8, Die::custom(3).unwrap()]);
+        assert_eq!((pmf.min(), pmf.max()), (3, 15));
+        // 2.5 + 4.5 + 2
+        assert_eq!(pmf.expected_value(), ratio(9, 1));
+        assert_eq!(&Pmf::die(&Die::D4) + &Pmf::die(&Die::D8), Pmf::sum(&[Die::D8, Die::D4]));
+    }
+
+    #[test]
+    fn test_large_sums_stay_exact() {
+        // 6^100 overflows every primitive integer
+        let pmf = Pmf::sum(&[Die::D6; 100]);
+        assert_eq!(pmf.outcomes(), &BigUint::from(6u32).pow(100));
+        assert_eq!(pmf.probability(600), BigRational::new(BigInt::one(), BigInt::from(pmf.outcomes().clone())));
+        assert_eq!(pmf.expected_value(), ratio(350, 1));
+    }
+
+    #[test]
+    fn test_many_large_dice() {
+        let pmf = Pmf::of_expr(&DiceExpr::parse("40d255").unwrap()).unwrap();
+        assert_eq!((pmf.min(), pmf.max()), (40, 10_200));
+        assert_eq!(pmf.outcomes(), &BigUint::from(255u32).pow(40));
+        assert_eq!(pmf.expected_value(), ratio(5_120, 1));
+        assert_eq!(pmf.ways(41), BigUint::from(40u32));
+    }
+
+    #[test]
+    fn test_from_expression() {
+        let pmf = Pmf::of_expr(&DiceExpr::parse("d20-1d4+2").unwrap()).unwrap();
+        assert_eq!((pmf.min(), pmf.max()), (-1, 21));
+        assert_eq!(pmf.expected_value(), ratio(21 - 5 + 4, 2));
+        assert_eq!(pmf.probability(-1), ratio(1, 80));
+        assert!(Pmf::of_expr(&DiceExpr::parse("4d6kh3").unwrap()).is_none());
+    }
+}
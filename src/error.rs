@@ -0,0 +1,141 @@
+//! The crate-wide error type.
+//!
+//! Wallet, bet and table calls all fail with `Error`, and each variant carries what a caller
+//! needs to explain the failure. Errors from parsing a slot config or a dice expression, and
+//! from building a slot's par sheet, keep their own types and convert into `Error` with `?`.
+
+use std::fmt;
+
+use crate::dice::expr::ParseError;
+use crate::limits::Refusal;
+use crate::odds::Odds;
+use crate::slots::config::ConfigError;
+use crate::slots::par_sheet::ParSheetError;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Error {
+    /// the wallet or bet holds fewer of `chip` than asked for
+    InsufficientChips { chip: String, wanted: u32, held: u32 },
+    /// the player's responsible-gaming limits do not allow it
+    Refused(Refusal),
+    SeatOutOfRange { seat: usize, seats: usize },
+    BelowTableMinimum { bet_cents: u64, minimum_cents: u64 },
+    AboveTableMaximum { bet_cents: u64, maximum_cents: u64 },
+    /// not enough cards left to finish a round
+    ShoeExhausted { remaining: usize },
+    /// e.g. a point roll before the point is set
+    IllegalAction { action: &'static str, state: String },
+    InvalidBet(String),
+    /// winnings too large to count in cents
+    PayoutOverflow { stake_cents: u64, odds: Odds },
+    /// no combination of the table's chips adds up to `cents`
+    Unpayable { cents: u64 },
+    /// the game is played with `expected`-sided dice
+    WrongDice { sides: u32, expected: u32 },
+    /// a dice total the game's dice cannot throw
+    ImpossibleRoll { total: u8 },
+    Config(ConfigError),
+    DiceExpr(ParseError),
+    ParSheet(ParSheetError),
+    /// a table snapshot that could not be written or read back
+    Snapshot(String),
+}
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::InsufficientChips { chip, wanted, held } => {
+                write!(f, "insufficient chips: wanted {wanted} {chip}, holding {held}")
+            }
+            Error::Refused(refusal) => write!(f, "refused: {refusal}"),
+            Error::SeatOutOfRange { seat, seats } => write!(f, "seat {seat} is out of range for a {seats}-seat table"),
+            Error::BelowTableMinimum { bet_cents, minimum_cents } => {
+                write!(f, "bet of {bet_cents} cents is below the table minimum of {minimum_cents}")
+            }
+            Error::AboveTableMaximum { bet_cents, maximum_cents } => {
+                write!(f, "bet of {bet_cents} cents is above the table maximum of {maximum_cents}")
+            }
+            Error::ShoeExhausted { remaining } => write!(f, "shoe exhausted with {remaining} cards left"),
+            Error::IllegalAction { action, state } => write!(f, "cannot {action} while {state}"),
+            Error::InvalidBet(bet) => write!(f, "invalid bet: {bet}"),
+            Error::PayoutOverflow { stake_cents, odds } => {
+                write!(f, "winnings at {odds} on {stake_cents} cents overflow")
+            }
+            Error::Unpayable { cents } => write!(f, "the table's chips cannot pay {cents} cents"),
+            Error::WrongDice { sides, expected } => {
+                write!(f, "the game needs {expected}-sided dice, not {sides}-sided")
+            }
+            Error::ImpossibleRoll { total } => write!(f, "the dice cannot total {total}"),
+            Error::Config(e) => e.fmt(f),
+            Error::DiceExpr(e) => e.fmt(f),
+            Error::ParSheet(e) => e.fmt(f),
+            Error::Snapshot(reason) => write!(f, "bad snapshot: {reason}"),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Refused(e) => Some(e),
+            Error::Config(e) => Some(e),
+            Error::DiceExpr(e) => Some(e),
+            Error::ParSheet(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<Refusal> for Error {
+    fn from(refusal: Refusal) -> Self {
+        Error::Refused(refusal)
+    }
+}
+
+impl From<ConfigError> for Error {
+    fn from(err: ConfigError) -> Self {
+        Error::Config(err)
+    }
+}
+
+impl From<ParseError> for Error {
+    fn from(err: ParseError) -> Self {
+        Error::DiceExpr(err)
+    }
+}
+
+impl From<ParSheetError> for Error {
+    fn from(err: ParSheetError) -> Self {
+        Error::ParSheet(err)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dice::expr::DiceExpr;
+
+    #[test]
+    fn test_conversions_keep_the_source() {
+        fn parse(expr: &str) -> Result<DiceExpr> {
+            Ok(DiceExpr::parse(expr)?)
+        }
+        let err = parse("3d").unwrap_err();
+        assert!(matches!(err, Error::DiceExpr(_)));
+        assert!(std::error::Error::source(&err).is_some());
+        assert_eq!(err.to_string(), std::error::Error::source(&err).unwrap().to_string());
+
+        let err = Error::from(ConfigError::NoReels);
+        assert_eq!(err.to_string(), "the game has no reels");
+    }
+
+    #[test]
+    fn test_messages_carry_context() {
+        let err = Error::SeatOutOfRange { seat: 7, seats: 5 };
+        assert_eq!(err.to_string(), "seat 7 is out of range for a 5-seat table");
+        let err = Error::IllegalAction { action: "roll for the point", state: "ComeOut".to_string() };
+        assert_eq!(err.to_string(), "cannot roll for the point while ComeOut");
+    }
+}
@@ -1,9 +1,16 @@
 use std::collections::HashMap;
-use crate::bet::Bet;
+use rand::{Rng, RngCore, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use serde::{Deserialize, Serialize};
+use crate::bet::Chip;
 use crate::cards::{Card, Shoe, Rank as RankTrait, BaccaratRank as BaccaratRankTrait};
+use crate::games::{Round, Seating, Seats, TableGame};
+use crate::ledger::Source;
+use crate::snapshot;
+use crate::odds::{Cashier, Odds, Resolution};
 use crate::player::Player;
 
-#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum BacRank {
     Two,
     Three,
@@ -79,6 +86,7 @@ impl BaccaratRankTrait for BacRank {
     }
 }
 
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum BaccaratBet {
     Player,
     Banker,
@@ -87,14 +95,80 @@ pub enum BaccaratBet {
     BankerPair,
 }
 
-#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PayTable {
+    pub player: Odds,
+    /// before commission
+    pub banker: Odds,
+    /// taken from each winning banker bet, per unit staked
+    pub commission: Odds,
+    pub tie: Odds,
+    pub pair: Odds,
+}
+
+impl Default for PayTable {
+    fn default() -> Self {
+        Self::standard()
+    }
+}
+
+impl PayTable {
+    // Even money on player, even money less a 5% commission on banker, 8:1 tie and 11:1 pairs.
+    pub fn standard() -> Self {
+        Self {
+            player: Odds::even(),
+            banker: Odds::even(),
+            commission: Odds::new(1, 20).unwrap(),
+            tie: Odds::to_one(8),
+            pair: Odds::to_one(11),
+        }
+    }
+
+    // Expected share of each stake the house keeps. Outcome chances are the usual eight-deck
+    // figures, within 0.01% of this six-deck shoe; pairs are exact for six decks.
+    pub fn house_edge(&self, bet: BaccaratBet) -> f64 {
+        const BANKER: f64 = 0.458_597;
+        const PLAYER: f64 = 0.446_247;
+        const TIE: f64 = 0.095_156;
+        const PAIR: f64 = 23.0 / 311.0;
+        let returned = match bet {
+            BaccaratBet::Player => PLAYER * (1.0 + self.player.to_f64()) + TIE,
+            BaccaratBet::Banker => BANKER * (1.0 + self.banker.to_f64() - self.commission.to_f64()) + TIE,
+            BaccaratBet::Tie => TIE * (1.0 + self.tie.to_f64()),
+            BaccaratBet::PlayerPair | BaccaratBet::BankerPair => PAIR * (1.0 + self.pair.to_f64()),
+        };
+        1.0 - returned
+    }
+
+    // Player and banker bets push on a tie.
+    pub fn resolve(&self, bet: BaccaratBet, result: &CoupResult) -> Resolution {
+        let win = |wins: bool, odds| if wins { Resolution::Win(odds) } else { Resolution::Lose };
+        match (bet, result.winner) {
+            (BaccaratBet::Player | BaccaratBet::Banker, Winner::Tie) => Resolution::Push,
+            (BaccaratBet::Player, winner) => win(winner == Winner::Player, self.player),
+            (BaccaratBet::Banker, Winner::Banker) if self.commission != Odds::to_one(0) => {
+                Resolution::WinLessCommission { odds: self.banker, commission: self.commission }
+            }
+            (BaccaratBet::Banker, winner) => win(winner == Winner::Banker, self.banker),
+            (BaccaratBet::Tie, winner) => win(winner == Winner::Tie, self.tie),
+            (BaccaratBet::PlayerPair, _) => win(result.player_pair, self.pair),
+            (BaccaratBet::BankerPair, _) => win(result.banker_pair, self.pair),
+        }
+    }
+}
+
+pub use crate::error::Error;
+
+pub type Settlement = crate::games::Settlement<BaccaratBet>;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub enum Winner {
     Player,
     Banker,
     Tie,
 }
 
-#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub struct CoupResult {
     pub winner: Winner,
     pub player_pair: bool,
@@ -111,10 +185,17 @@ impl CoupResult {
     }
 }
 
+#[derive(Serialize, Deserialize)]
 pub struct Hand {
     cards: Vec<Card<BacRank>>
 }
 
+impl Default for Hand {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl Hand {
     pub fn new() -> Self {
         Self { cards: Vec::new() }
@@ -126,53 +207,115 @@ impl Hand {
     }
 }
 
-pub struct PlayerSeat {
-    pub player: Player,
-    pub player_bet: HashMap<BaccaratBet, Bet>
-}
+pub type PlayerSeat = crate::games::PlayerSeat<BaccaratBet>;
 
-impl PlayerSeat {
-    pub fn new(player: Player) -> Self {
-        Self {
-            player,
-            player_bet: HashMap::new()
-        }
-    }
-
-    pub fn get_player_name(&self) -> &str{
-        &self.player.name
-    }
-}
+#[derive(Serialize, Deserialize)]
 pub struct BaccaratGame {
-    players: Vec<PlayerSeat>,
+    pub pay_table: PayTable,
+    pub cashier: Cashier,
+    players: Seats<PlayerSeat>,
     shoe: Shoe<BacRank>,
     player_hand: Hand,
     banker_hand: Hand,
     history: Vec<CoupResult>,
+    /// shuffles for `play`, `play_n` and `play_round`; saved with the table so a restored one
+    /// plays the same coups the original would
+    rng: ChaCha8Rng,
 }
 
 impl BaccaratGame {
     pub fn new(players: Vec<Player>) -> Self {
         BaccaratGame {
-            players: players.into_iter().map(PlayerSeat::new).collect(),
+            pay_table: PayTable::standard(),
+            cashier: Cashier::default(),
+            players: Seats::new(players),
             shoe: Shoe::<BacRank>::new(6),
             player_hand: Hand::new(),
             banker_hand: Hand::new(),
             history: Vec::new(),
+            rng: ChaCha8Rng::from_rng(&mut rand::rng()),
         }
     }
 
-    // Play a single round, record the result, and return it
-    pub fn play(&mut self) -> CoupResult {
+    // Shuffle with a generator seeded from `seed` rather than a random one.
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.rng = ChaCha8Rng::seed_from_u64(seed);
+        self
+    }
+
+    // The whole table as a portable JSON snapshot; see `crate::snapshot`.
+    pub fn save(&self) -> Result<String, Error> {
+        snapshot::save(self)
+    }
+
+    pub fn restore(json: &str) -> Result<Self, Error> {
+        snapshot::restore(json)
+    }
+
+    // Ledger source for the coup now being bet on.
+    fn source(&self) -> Source {
+        self.players.source("baccarat", self.history.len() as u64 + 1)
+    }
+
+    pub fn place_bet(&mut self, seat: usize, bet: BaccaratBet, chips: HashMap<Chip, u32>) -> Result<(), Error> {
+        let source = self.source();
+        let seats = self.players.len();
+        let seat = self.players.get_mut(seat).ok_or(Error::SeatOutOfRange { seat, seats })?;
+        let house_edge = self.pay_table.house_edge(bet);
+        seat.place(bet, house_edge, chips, source)
+    }
+
+    // Pay or take every bet on the layout for `result` and clear it. If the cashier cannot pay
+    // one of them nothing moves.
+    pub fn settle(&mut self, result: &CoupResult) -> Result<Vec<Settlement>, Error> {
+        let source = self.source();
+        let pay_table = &self.pay_table;
+        self.players.settle(&mut self.cashier, source, |bet| Some(pay_table.resolve(bet, result)))
+    }
+
+    // Start a fresh shoe, e.g. after `play` reports it exhausted
+    pub fn reload_shoe(&mut self) {
+        self.shoe = Shoe::<BacRank>::new(6);
+    }
+
+    pub fn cards_remaining(&self) -> usize {
+        self.shoe.len()
+    }
+
+    fn deal(&mut self) -> Result<Card<BacRank>, Error> {
+        let remaining = self.shoe.len();
+        self.shoe.deal().ok_or(Error::ShoeExhausted { remaining })
+    }
+
+    // Play a single round, settle bets, record the result, and return it. A coup can take up
+    // to six cards; with fewer left the shoe is exhausted and nothing is dealt.
+    pub fn play(&mut self) -> Result<CoupResult, Error> {
+        let mut rng = self.rng.clone();
+        let result = self.play_with(&mut rng);
+        self.rng = rng;
+        result
+    }
+
+    // As `play`, shuffling the shoe with `rng`.
+    pub fn play_with<R: Rng + ?Sized>(&mut self, rng: &mut R) -> Result<CoupResult, Error> {
+        self.play_coup(rng).map(|(result, _)| result)
+    }
+
+    fn play_coup<R: Rng + ?Sized>(&mut self, rng: &mut R) -> Result<(CoupResult, Vec<Settlement>), Error> {
+        if self.shoe.len() < 6 {
+            return Err(Error::ShoeExhausted { remaining: self.shoe.len() });
+        }
         // Clear previous hands and shuffle for this round
-        self.player_hand.cards.clear();
-        self.banker_hand.cards.clear();
-        self.shoe.shuffle();
+        self.shoe.discard(self.player_hand.cards.drain(..));
+        self.shoe.discard(self.banker_hand.cards.drain(..));
+        self.shoe.shuffle_with(rng);
 
         // deal initial cards
         for _ in 0..2 {
-            self.player_hand.cards.push(self.shoe.deal().unwrap());
-            self.banker_hand.cards.push(self.shoe.deal().unwrap());
+            let card = self.deal()?;
+            self.player_hand.cards.push(card);
+            let card = self.deal()?;
+            self.banker_hand.cards.push(card);
         }
 
         // Track initial pairs (only first two cards count)
@@ -191,31 +334,21 @@ impl BaccaratGame {
         let player_value = self.player_hand.value();
         let banker_value = self.banker_hand.value();
 
-        println!("Player hand value: {}", player_value);
-        println!("Banker hand value: {}", banker_value);
-
         // Natural check: if either is 8 or 9, both stand
         if !(player_value == 8 || player_value == 9 || banker_value == 8 || banker_value == 9) {
             // Player third-card rule
             let mut player_third_val: Option<u8> = None;
             if player_value <= 5 {
-                let c = self.shoe.deal().unwrap();
+                let c = self.deal()?;
                 let c_val = c.rank.baccarat_value();
                 player_third_val = Some(c_val);
                 self.player_hand.cards.push(c);
-                println!("Player draws a third card");
-            } else {
-                println!("Player stands");
             }
 
             // Banker third-card rules
             let b_val = self.banker_hand.value();
-            let banker_draw = if player_third_val.is_none() {
-                // If player stood, banker draws on 0-5, stands on 6-7
-                b_val <= 5
-            } else {
+            let banker_draw = if let Some(v) = player_third_val {
                 // Use the banker drawing table based on player's third card
-                let v = player_third_val.unwrap();
                 match b_val {
                     0..=2 => true,
                     3 => v != 8,
@@ -224,24 +357,20 @@ impl BaccaratGame {
                     6 => v == 6 || v == 7,
                     _ => false, // 7 stands; 8-9 already handled by natural check
                 }
+            } else {
+                // If player stood, banker draws on 0-5, stands on 6-7
+                b_val <= 5
             };
 
             if banker_draw {
-                let c = self.shoe.deal().unwrap();
+                let c = self.deal()?;
                 self.banker_hand.cards.push(c);
-                println!("Banker draws a third card");
-            } else {
-                println!("Banker stands");
             }
-        } else {
-            println!("Natural - no more cards drawn");
         }
 
         // Final evaluation and outcome
         let final_player = self.player_hand.value();
         let final_banker = self.banker_hand.value();
-        println!("Final Player value: {}", final_player);
-        println!("Final Banker value: {}", final_banker);
 
         let winner = if final_player > final_banker {
             Winner::Player
@@ -257,18 +386,18 @@ impl BaccaratGame {
             banker_pair,
         };
 
-        println!("Outcome: {} (pairs: P={} B={})", result.label(), result.player_pair, result.banker_pair);
-
-        // Save result to history and return it
+        // Settle bets, save result to history and return it
+        let settlements = self.settle(&result)?;
         self.history.push(result);
-        result
+        Ok((result, settlements))
     }
 
-    // Play n rounds, collecting their results in history
-    pub fn play_n(&mut self, rounds: usize) {
+    // Play n rounds, collecting their results in history. Stops at the first error.
+    pub fn play_n(&mut self, rounds: usize) -> Result<(), Error> {
         for _ in 0..rounds {
-            let _ = self.play();
+            self.play()?;
         }
+        Ok(())
     }
 
     // Bead-plate style grid (top-to-bottom, left-to-right), limited to the last n results.
@@ -307,16 +436,55 @@ impl BaccaratGame {
         out
     }
 
+    pub fn players(&self) -> &[PlayerSeat] {
+        &self.players
+    }
+
     // Access the raw history (e.g., for analytics)
     pub fn history(&self) -> &Vec<CoupResult> {
         &self.history
     }
 }
 
+impl TableGame for BaccaratGame {
+    type Bet = BaccaratBet;
+    type Outcome = CoupResult;
+
+    fn name(&self) -> &'static str {
+        "baccarat"
+    }
+
+    fn seating(&self) -> &dyn Seating {
+        &self.players
+    }
+
+    fn seating_mut(&mut self) -> &mut dyn Seating {
+        &mut self.players
+    }
+
+    fn place_bet(&mut self, seat: usize, bet: BaccaratBet, chips: HashMap<Chip, u32>) -> Result<(), Error> {
+        BaccaratGame::place_bet(self, seat, bet, chips)
+    }
+
+    fn play_round_with(&mut self, rng: &mut dyn RngCore) -> Result<Round<CoupResult, BaccaratBet>, Error> {
+        let number = self.history.len() as u64 + 1;
+        let (outcome, settlements) = self.play_coup(rng)?;
+        Ok(Round { number, outcome, settlements })
+    }
+
+    fn play_round(&mut self) -> Result<Round<CoupResult, BaccaratBet>, Error> {
+        let mut rng = self.rng.clone();
+        let round = self.play_round_with(&mut rng);
+        self.rng = rng;
+        round
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use crate::cards::Suit;
     use super::*;
+    use crate::ledger::Reason;
     #[test]
     fn test_hand_value() {
         let hand = Hand::new();
@@ -341,7 +509,7 @@ mod tests {
         let player1 = Player::new("Player 1");
         let player2 = Player::new("Player 2");
         let mut game = BaccaratGame::new(vec![player1, player2]);
-        let result = game.play();
+        let result = game.play().unwrap();
         assert!(matches!(result.winner, Winner::Player | Winner::Banker | Winner::Tie));
     }
 
@@ -350,10 +518,70 @@ mod tests {
         let player1 = Player::new("Player 1");
         let player2 = Player::new("Player 2");
         let mut game = BaccaratGame::new(vec![player1, player2]);
-        game.play_n(50);
+        game.play_n(50).unwrap();
         assert_eq!(game.history.len(), 50);
         assert!(matches!(game.history[0].winner, Winner::Player | Winner::Banker | Winner::Tie));
         // print the bead plate
         println!("{}", game.bead_plate_string(5, 50));
     }
+
+    #[test]
+    fn test_exhausted_shoe_is_an_error() {
+        let mut game = BaccaratGame::new(vec![Player::new("Player 1")]);
+        let rounds = (0..100).take_while(|_| game.play().is_ok()).count();
+        assert!(rounds > 40);
+        assert!(matches!(game.play(), Err(Error::ShoeExhausted { remaining }) if remaining < 6));
+        game.reload_shoe();
+        assert!(game.play().is_ok());
+    }
+
+    #[test]
+    fn test_resolve_bets() {
+        let table = PayTable::standard();
+        let coup = |winner, player_pair| CoupResult { winner, player_pair, banker_pair: false };
+        assert_eq!(
+            table.resolve(BaccaratBet::Banker, &coup(Winner::Banker, false)),
+            Resolution::WinLessCommission { odds: Odds::even(), commission: Odds::new(1, 20).unwrap() }
+        );
+        assert_eq!(table.resolve(BaccaratBet::Player, &coup(Winner::Tie, false)), Resolution::Push);
+        assert_eq!(table.resolve(BaccaratBet::Tie, &coup(Winner::Tie, false)), Resolution::Win(Odds::to_one(8)));
+        assert_eq!(table.resolve(BaccaratBet::Tie, &coup(Winner::Player, false)), Resolution::Lose);
+        assert_eq!(table.resolve(BaccaratBet::PlayerPair, &coup(Winner::Banker, true)), Resolution::Win(Odds::to_one(11)));
+        assert_eq!(table.resolve(BaccaratBet::BankerPair, &coup(Winner::Banker, true)), Resolution::Lose);
+        assert!((table.house_edge(BaccaratBet::Banker) - 0.0106).abs() < 1e-4);
+        assert!((table.house_edge(BaccaratBet::Player) - 0.0124).abs() < 1e-4);
+        assert!((table.house_edge(BaccaratBet::Tie) - 0.1436).abs() < 1e-4);
+    }
+
+    #[test]
+    fn test_banker_commission_settles_exactly() {
+        let red = crate::bet::ChipSet::standard().chip(500).unwrap().clone();
+        let mut player = Player::new("Punto");
+        player.deposit(red.clone(), 4).unwrap();
+        let mut game = BaccaratGame::new(vec![player]);
+        game.place_bet(0, BaccaratBet::Banker, HashMap::from([(red.clone(), 2)])).unwrap();
+        game.place_bet(0, BaccaratBet::Player, HashMap::from([(red.clone(), 1)])).unwrap();
+        assert!(matches!(
+            game.place_bet(0, BaccaratBet::Tie, HashMap::from([(red.clone(), 2)])),
+            Err(Error::InsufficientChips { wanted: 2, held: 1, .. })
+        ));
+        assert_eq!(game.place_bet(1, BaccaratBet::Tie, HashMap::new()), Err(Error::SeatOutOfRange { seat: 1, seats: 1 }));
+
+        let settlements = game.settle(&CoupResult { winner: Winner::Banker, player_pair: false, banker_pair: false }).unwrap();
+        let banker = settlements.iter().find(|s| s.bet == BaccaratBet::Banker).unwrap().payout.unwrap();
+        // $10 at even money, less 50 cents commission
+        assert_eq!(banker.exact_cents, num_rational::Ratio::from_integer(1_000));
+        assert_eq!(banker.returned_cents(), 1_950);
+        assert_eq!(banker.commission_cents, 50);
+        assert!(settlements.iter().find(|s| s.bet == BaccaratBet::Player).unwrap().payout.is_none());
+        assert_eq!(game.players()[0].player.total_cents(), 500 + 1_950);
+        assert!(game.players()[0].bets.is_empty());
+
+        // the ledger shows the win at even money and the commission taken from it
+        let ledger = game.players()[0].player.ledger();
+        let amounts: Vec<(Reason, i64)> = ledger.entries().iter().rev().take(2).map(|tx| (tx.reason, tx.amount_cents)).collect();
+        assert_eq!(amounts, vec![(Reason::Commission, -50), (Reason::Win, 2_000)]);
+        assert_eq!(game.players()[0].player.token_count(&Cashier::coin()), 0);
+        assert_eq!(ledger.verify(), Ok(()));
+    }
 }
\ No newline at end of file
@@ -1,9 +1,16 @@
 use std::collections::HashMap;
+use rand::{Rng, RngCore, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use serde::{Deserialize, Serialize};
 use crate::cards::{Card, Shoe, Rank as RankTrait, BlackjackRank as BlackjackRankTrait};
 use crate::player::Player;
-use crate::bet::Chip;
+use crate::bet::{Bet, Chip};
+use crate::games::{Round, Seat, Seating, Seats, Settlement, TableGame};
+use crate::ledger::Source;
+use crate::snapshot;
+use crate::odds::{Cashier, Odds, Payout, Resolution};
 
-#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum BJRank {
     Two,
     Three,
@@ -91,10 +98,12 @@ pub enum GameState {
     RoundOver,
 }
 
-// define error type
-#[derive(Debug)]
-pub enum Error {
-    InsufficientChips,
+pub use crate::error::Error;
+
+// The only bet a seat can make: the stake on its hand.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum BlackjackBet {
+    Hand,
 }
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
@@ -111,14 +120,19 @@ pub enum Outcome {
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub struct RoundResult {
     pub player_index: usize,
+    /// which of the seat's hands, 0 unless it split
+    pub hand: usize,
     pub player_value: u8,
     pub dealer_value: u8,
     pub outcome: Outcome,
+    /// None if the hand lost or had no bet
+    pub payout: Option<Payout>,
 }
 
+#[derive(Serialize, Deserialize)]
 pub struct Hand {
     cards: Vec<Card<BJRank>>,
-    bet: HashMap<Chip, u32>
+    bet: Bet,
 }
 
 impl Hand {
@@ -146,43 +160,70 @@ impl Hand {
     pub fn is_bust(&self) -> bool {
         self.value() > 21
     }
+
+    pub fn bet(&self) -> &HashMap<Chip, u32> {
+        &self.bet.chips
+    }
 }
 
+#[derive(Serialize, Deserialize)]
 pub struct PlayerSeat {
     player: Player,
     hands: Vec<Hand>, // multiple when splitting
 }
 
-impl PlayerSeat {
-    pub fn new(player: Player) -> Self {
+impl Seat for PlayerSeat {
+    fn new(player: Player) -> Self {
         Self {
             player,
             hands: vec![],
         }
     }
 
-    pub fn get_player_name(&self) -> &str{
-        &self.player.name
+    fn player(&self) -> &Player {
+        &self.player
+    }
+
+    fn into_player(self) -> Player {
+        self.player
+    }
+
+    fn staked_cents(&self) -> u64 {
+        self.hands.iter().map(|hand| hand.bet.total_cents()).sum()
     }
 }
+
+#[derive(Serialize, Deserialize)]
 pub struct Dealer {
     face_down_card: Option<Card<BJRank>>,
     hand: Vec<Card<BJRank>>,
 }
 
+#[derive(Serialize, Deserialize)]
 pub struct BlackjackGame {
     dealer: Dealer,
-    players: Vec<PlayerSeat>,
+    players: Seats<PlayerSeat>,
     shoe: Shoe<BJRank>,
+    /// table limits in whole dollars
     min_bet: u32,
     max_bet: u32,
     num_decks: usize,
+    blackjack_odds: Odds,
+    /// edge the table's strategy gives up, used for players' theoretical loss; None to
+    /// work it out from `blackjack_odds`
+    house_edge: Option<f64>,
+    cashier: Cashier,
+    /// rounds played so far
+    rounds: u64,
+    /// shuffles for `play`, `play_n`, `play_round` and `shuffle_shoe`; saved with the table
+    /// so a restored one deals the same rounds the original would
+    rng: ChaCha8Rng,
 }
 
 impl BlackjackGame {
     pub fn new(players: Vec<Player>, num_decks: usize, min_bet: u32, max_bet: u32) -> Self {
         let shoe = Shoe::<BJRank>::new(num_decks);
-        let players = players.into_iter().map(PlayerSeat::new).collect();
+        let players = Seats::new(players);
         Self {
             dealer: Dealer { face_down_card: None, hand: vec![] },
             players,
@@ -190,11 +231,77 @@ impl BlackjackGame {
             min_bet,
             max_bet,
             num_decks,
+            blackjack_odds: Odds::new(3, 2).unwrap(),
+            house_edge: None,
+            cashier: Cashier::default(),
+            rounds: 0,
+            rng: ChaCha8Rng::from_rng(&mut rand::rng()),
         }
     }
 
+    // Shuffle with a generator seeded from `seed` rather than a random one, starting with the
+    // shoe already in the rack.
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.rng = ChaCha8Rng::seed_from_u64(seed);
+        self.shuffle_shoe();
+        self
+    }
+
+    // The whole table as a portable JSON snapshot; see `crate::snapshot`.
+    pub fn save(&self) -> Result<String, Error> {
+        snapshot::save(self)
+    }
+
+    pub fn restore(json: &str) -> Result<Self, Error> {
+        snapshot::restore(json)
+    }
+
+    // Ledger source for the round now being bet on.
+    fn source(&self) -> Source {
+        self.players.source("blackjack", self.rounds + 1)
+    }
+
+    // Pay naturals at something other than 3:2, e.g. 6:5.
+    pub fn with_blackjack_odds(mut self, odds: Odds) -> Self {
+        self.blackjack_odds = odds;
+        self
+    }
+
+    // Replaces the edge worked out from the blackjack odds, e.g. when the rules change.
+    pub fn with_house_edge(mut self, house_edge: f64) -> Self {
+        self.house_edge = Some(house_edge);
+        self
+    }
+
+    // Hitting to 17 and never doubling or splitting gives up about 5.5% at 3:2. Each unit
+    // cut from the natural's payout costs the player that much again on the 4.5% of hands
+    // that are a natural the dealer does not match, so 6:5 gives up about 6.9%.
+    pub fn house_edge(&self) -> f64 {
+        const AT_THREE_TO_TWO: f64 = 0.055;
+        const NATURALS: f64 = 0.045;
+        self.house_edge
+            .unwrap_or_else(|| AT_THREE_TO_TWO + NATURALS * (1.5 - self.blackjack_odds.to_f64()))
+    }
+
+    pub fn with_cashier(mut self, cashier: Cashier) -> Self {
+        self.cashier = cashier;
+        self
+    }
+
+    pub fn blackjack_odds(&self) -> Odds {
+        self.blackjack_odds
+    }
+
+    pub fn cashier(&self) -> &Cashier {
+        &self.cashier
+    }
+
+    pub fn player(&self, player_index: usize) -> Option<&Player> {
+        self.players.get(player_index).map(|seat| &seat.player)
+    }
+
     pub fn shuffle_shoe(&mut self) {
-        self.shoe.shuffle();
+        self.shoe.shuffle_with(&mut self.rng);
     }
 
     pub fn cards_remaining(&self) -> usize {
@@ -202,62 +309,77 @@ impl BlackjackGame {
     }
 
     pub fn place_initial_bet(&mut self, player_index: usize, bet: HashMap<Chip, u32>) -> Result<(), Error> {
-        if player_index >= self.players.len() {
-            return Err(Error::InsufficientChips);
+        let seats = self.players.len();
+        let added_cents: u64 = bet.iter().map(|(chip, &count)| chip.value_cents * count as u64).sum();
+        let (minimum_cents, maximum_cents) = (self.min_bet as u64 * 100, self.max_bet as u64 * 100);
+        let source = self.source();
+        let house_edge = self.house_edge();
+        let seat = self
+            .players
+            .get_mut(player_index)
+            .ok_or(Error::SeatOutOfRange { seat: player_index, seats })?;
+        // the limits apply to the whole stake on the hand, not just the chips added now
+        let bet_cents = seat.hands.first().map_or(0, |hand| hand.bet.total_cents()) + added_cents;
+        if bet_cents < minimum_cents {
+            return Err(Error::BelowTableMinimum { bet_cents, minimum_cents });
         }
-        let total_bet: u32 = bet.values().sum();
-        if total_bet < self.min_bet || total_bet > self.max_bet {
-            return Err(Error::InsufficientChips);
+        if bet_cents > maximum_cents {
+            return Err(Error::AboveTableMaximum { bet_cents, maximum_cents });
         }
-        // Here you would also check if the player has enough chips in their wallet
-        self.players[player_index].hands.push(Hand { cards: vec![], bet });
-        Ok(())
+        if seat.hands.first().is_some_and(|hand| !hand.cards.is_empty()) {
+            return Err(Error::IllegalAction { action: "place a bet", state: "cards are dealt".to_string() });
+        }
+        if seat.hands.is_empty() {
+            seat.hands.push(Hand { cards: vec![], bet: Bet::new() });
+        }
+        seat.hands[0].bet.house_edge = house_edge;
+        seat.hands[0].bet.escrow(&mut seat.player, bet, Some(source))
     }
-    pub fn deal_initial_cards(&mut self) {
-        // Ensure each player has a base hand
-        for player in &mut self.players {
+    // Deal two cards to every hand and the dealer. Fails with `ShoeExhausted` if the shoe runs
+    // out part way, leaving the cards dealt so far on the table.
+    pub fn deal_initial_cards(&mut self) -> Result<(), Error> {
+        // Ensure each player has a base hand, and clear any cards left from the last round
+        for player in self.players.iter_mut() {
             if player.hands.is_empty() {
-                player.hands.push(Hand { cards: vec![], bet: HashMap::new() });
-            } else {
-                // Clear the first hand for a fresh round (no splits support here)
-                if let Some(h) = player.hands.get_mut(0) {
-                    h.cards.clear();
-                }
+                player.hands.push(Hand { cards: vec![], bet: Bet::new() });
+            }
+            for h in &mut player.hands {
+                self.shoe.discard(h.cards.drain(..));
             }
         }
         // Clear dealer state
-        self.dealer.hand.clear();
+        self.shoe.discard(self.dealer.hand.drain(..));
         self.dealer.face_down_card = None;
 
-        // First card to each player
-        for player in &mut self.players {
-            if let Some(card1) = self.shoe.deal() {
-                if let Some(hand) = player.hands.get_mut(0) {
-                    hand.cards.push(card1);
-                }
+        // First card to each hand
+        for player in self.players.iter_mut() {
+            for hand in &mut player.hands {
+                hand.cards.push(Self::deal(&mut self.shoe)?);
             }
         }
         // Dealer gets one face down card
-        self.dealer.face_down_card = self.shoe.deal();
-        // Second card to each player
-        for player in &mut self.players {
-            if let Some(card2) = self.shoe.deal() {
-                if let Some(hand) = player.hands.get_mut(0) {
-                    hand.cards.push(card2);
-                }
+        self.dealer.face_down_card = Some(Self::deal(&mut self.shoe)?);
+        // Second card to each hand
+        for player in self.players.iter_mut() {
+            for hand in &mut player.hands {
+                hand.cards.push(Self::deal(&mut self.shoe)?);
             }
         }
         // Dealer gets one face up card
-        if let Some(card) = self.shoe.deal() {
-            self.dealer.hand.push(card);
-        }
+        self.dealer.hand.push(Self::deal(&mut self.shoe)?);
+        Ok(())
+    }
+
+    fn deal(shoe: &mut Shoe<BJRank>) -> Result<Card<BJRank>, Error> {
+        let remaining = shoe.shoe.len();
+        shoe.deal().ok_or(Error::ShoeExhausted { remaining })
     }
 
     // Ensure the shoe has enough cards; if not, reinitialize and shuffle
-    fn ensure_shoe_capacity(&mut self, min_remaining: usize) {
+    fn ensure_shoe_capacity<R: Rng + ?Sized>(&mut self, min_remaining: usize, rng: &mut R) {
         if self.cards_remaining() < min_remaining {
             self.shoe = Shoe::<BJRank>::new(self.num_decks);
-            self.shoe.shuffle();
+            self.shoe.shuffle_with(rng);
         }
     }
 
@@ -278,10 +400,21 @@ impl BlackjackGame {
         total
     }
 
-    pub fn play(&mut self) -> Vec<RoundResult> {
+    pub fn play(&mut self) -> Result<Vec<RoundResult>, Error> {
+        let mut rng = self.rng.clone();
+        let results = self.play_with(&mut rng);
+        self.rng = rng;
+        results
+    }
+
+    // Play a round, shuffling a fresh shoe with `rng` when fewer than 52 cards are left.
+    // Every hand a seat holds is played and settled, split hands included. If the shoe runs out
+    // part way the round fails with `ShoeExhausted` before any bet is settled; the stakes stay
+    // on their hands.
+    pub fn play_with<R: Rng + ?Sized>(&mut self, rng: &mut R) -> Result<Vec<RoundResult>, Error> {
         // Fresh round setup
-        self.ensure_shoe_capacity(52);
-        self.deal_initial_cards();
+        self.ensure_shoe_capacity(52, rng);
+        self.deal_initial_cards()?;
 
         // Check if dealer has blackjack (with hole card)
         let mut dealer_cards: Vec<Card<BJRank>> = self.dealer.hand.clone();
@@ -291,16 +424,12 @@ impl BlackjackGame {
         let dealer_has_blackjack = dealer_cards.len() == 2 && Self::score_cards(&dealer_cards) == 21;
 
         // Player turns (simple strategy: hit until 17 or more)
-        for player in &mut self.players {
-            if let Some(hand) = player.hands.get_mut(0) {
+        for player in self.players.iter_mut() {
+            for hand in &mut player.hands {
                 // Skip action if natural blackjack
                 if !hand.is_blackjack() {
                     while hand.value() < 17 {
-                        if let Some(card) = self.shoe.deal() {
-                            hand.cards.push(card);
-                        } else {
-                            break;
-                        }
+                        hand.cards.push(Self::deal(&mut self.shoe)?);
                         if hand.is_bust() {
                             break;
                         }
@@ -321,46 +450,27 @@ impl BlackjackGame {
                 if v >= 17 {
                     break;
                 }
-                if let Some(card) = self.shoe.deal() {
-                    self.dealer.hand.push(card);
-                } else {
-                    break;
-                }
+                self.dealer.hand.push(Self::deal(&mut self.shoe)?);
             }
         }
 
-        // Resolve outcomes per player (first hand only for now)
+        // Resolve every hand; a two-card 21 is only a blackjack on an unsplit hand
         let dealer_value = Self::score_cards(&self.dealer.hand);
+        let dealer_bust = dealer_value > 21;
+        let source = self.source();
         let mut results: Vec<RoundResult> = Vec::with_capacity(self.players.len());
-        for (idx, player) in self.players.iter().enumerate() {
-            let mut outcome = Outcome::Push;
-            let player_value = if let Some(hand) = player.hands.get(0) {
-                hand.value()
-            } else {
-                0
-            };
-
-            let player_blackjack = if let Some(hand) = player.hands.get(0) {
-                hand.is_blackjack()
-            } else {
-                false
-            };
-
-            if player_blackjack && dealer_has_blackjack {
-                outcome = Outcome::Push;
-            } else if player_blackjack {
-                outcome = Outcome::PlayerBlackjack;
-            } else if dealer_has_blackjack {
-                outcome = Outcome::DealerBlackjack;
-            } else {
-                let player_bust = if let Some(hand) = player.hands.get(0) {
-                    hand.is_bust()
-                } else {
-                    false
-                };
-                let dealer_bust = dealer_value > 21;
-
-                outcome = if player_bust {
+        for (idx, player) in self.players.iter_mut().enumerate() {
+            let split = player.hands.len() > 1;
+            for (hand_index, hand) in player.hands.iter_mut().enumerate() {
+                let player_value = hand.value();
+                let player_blackjack = !split && hand.is_blackjack();
+                let outcome = if player_blackjack && dealer_has_blackjack {
+                    Outcome::Push
+                } else if player_blackjack {
+                    Outcome::PlayerBlackjack
+                } else if dealer_has_blackjack {
+                    Outcome::DealerBlackjack
+                } else if hand.is_bust() {
                     Outcome::PlayerBust
                 } else if dealer_bust {
                     Outcome::DealerBust
@@ -371,41 +481,108 @@ impl BlackjackGame {
                 } else {
                     Outcome::Push
                 };
-            }
 
-            results.push(RoundResult {
-                player_index: idx,
-                player_value,
-                dealer_value,
-                outcome,
-            });
+                let resolution = match outcome {
+                    Outcome::PlayerBlackjack => Resolution::Win(self.blackjack_odds),
+                    Outcome::PlayerWin | Outcome::DealerBust => Resolution::Win(Odds::even()),
+                    Outcome::Push => Resolution::Push,
+                    Outcome::DealerBlackjack | Outcome::DealerWin | Outcome::PlayerBust => Resolution::Lose,
+                };
+                let payout = if hand.bet.chips.is_empty() {
+                    None
+                } else {
+                    let payout = hand.bet.settle(&mut player.player, &mut self.cashier, resolution, Some(source.clone()))?;
+                    hand.bet = Bet::new();
+                    payout
+                };
+
+                results.push(RoundResult {
+                    player_index: idx,
+                    hand: hand_index,
+                    player_value,
+                    dealer_value,
+                    outcome,
+                    payout,
+                });
+            }
         }
 
-        // Prepare players for the next round: keep hands but clear cards
-        for p in &mut self.players {
-            if let Some(h) = p.hands.get_mut(0) {
-                h.cards.clear();
+        // Prepare players for the next round: split hands end with the round, and the base
+        // hand keeps no cards (bets were settled)
+        for p in self.players.iter_mut() {
+            for h in &mut p.hands {
+                self.shoe.discard(h.cards.drain(..));
             }
+            p.hands.truncate(1);
         }
         // Dealer cleared next time in deal_initial_cards
+        self.rounds += 1;
 
-        results
+        Ok(results)
     }
 
-    pub fn play_n(&mut self, rounds: usize) -> Vec<RoundResult> {
+    // Play `rounds` rounds and collect every result. Stops at the first error.
+    pub fn play_n(&mut self, rounds: usize) -> Result<Vec<RoundResult>, Error> {
         let mut all = Vec::new();
         for _ in 0..rounds {
-            let res = self.play();
+            let res = self.play()?;
             all.extend(res);
         }
-        all
+        Ok(all)
     }
 
 }
 
+impl TableGame for BlackjackGame {
+    type Bet = BlackjackBet;
+    type Outcome = Vec<RoundResult>;
+
+    fn name(&self) -> &'static str {
+        "blackjack"
+    }
+
+    fn seating(&self) -> &dyn Seating {
+        &self.players
+    }
+
+    fn seating_mut(&mut self) -> &mut dyn Seating {
+        &mut self.players
+    }
+
+    fn place_bet(&mut self, seat: usize, _bet: BlackjackBet, chips: HashMap<Chip, u32>) -> Result<(), Error> {
+        self.place_initial_bet(seat, chips)
+    }
+
+    fn play_round(&mut self) -> Result<Round<Vec<RoundResult>, BlackjackBet>, Error> {
+        let mut rng = self.rng.clone();
+        let round = self.play_round_with(&mut rng);
+        self.rng = rng;
+        round
+    }
+
+    fn play_round_with(&mut self, rng: &mut dyn RngCore) -> Result<Round<Vec<RoundResult>, BlackjackBet>, Error> {
+        let number = self.rounds + 1;
+        let stakes: Vec<Vec<u64>> = self
+            .players
+            .iter()
+            .map(|seat| seat.hands.iter().map(|hand| hand.bet.total_cents()).collect())
+            .collect();
+        let outcome = self.play_with(rng)?;
+        let settlements = outcome
+            .iter()
+            .filter_map(|result| {
+                let stake = stakes[result.player_index].get(result.hand).copied().unwrap_or(0);
+                (stake > 0).then(|| Settlement::new(result.player_index, BlackjackBet::Hand, stake, result.payout))
+            })
+            .collect();
+        Ok(Round { number, outcome, settlements })
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::ledger::Reason;
 
     #[test]
     fn test_play_n_games() {
@@ -416,8 +593,135 @@ mod tests {
             Player::new("Dave"),
         ];
         let mut game = BlackjackGame::new(players, 1, 10, 100);
-        let results = game.play_n(10);
+        let results = game.play_n(10).unwrap();
         println!("{:?}", results);
         assert_eq!(results.len(), 10 * 4); // 4 players, 10 rounds each
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_bets_settle_through_cashier() {
+        let green = crate::bet::ChipSet::standard().chip(2_500).unwrap().clone();
+        let mut alice = Player::new("Alice");
+        alice.deposit(green.clone(), 40).unwrap();
+        let mut game = BlackjackGame::new(vec![alice], 6, 10, 2_000).with_blackjack_odds(Odds::new(6, 5).unwrap());
+        assert_eq!(game.blackjack_odds().to_string(), "6:5");
+        assert!(matches!(
+            game.place_initial_bet(0, HashMap::from([(green.clone(), 41)])),
+            Err(Error::InsufficientChips { wanted: 41, held: 40, .. })
+        ));
+        for _ in 0..30 {
+            let before = game.player(0).unwrap().total_cents();
+            game.place_initial_bet(0, HashMap::from([(green.clone(), 1)])).unwrap();
+            assert_eq!(game.player(0).unwrap().total_cents(), before - 2_500);
+            let result = game.play().unwrap()[0];
+            let returned = result.payout.map_or(0, |p| p.returned_cents());
+            assert_eq!(game.player(0).unwrap().total_cents(), before - 2_500 + returned);
+            match result.outcome {
+                // 6:5 on $25 is $30
+                Outcome::PlayerBlackjack => assert_eq!(returned, 5_500),
+                Outcome::PlayerWin | Outcome::DealerBust => assert_eq!(returned, 5_000),
+                Outcome::Push => assert_eq!(returned, 2_500),
+                _ => assert!(result.payout.is_none()),
+            }
+        }
+        let ledger = game.player(0).unwrap().ledger();
+        let wagers: Vec<u64> = ledger
+            .by_source("blackjack", "1")
+            .filter(|tx| tx.reason == Reason::Wager)
+            .map(|tx| tx.source.as_ref().unwrap().round)
+            .collect();
+        assert_eq!(wagers, (1..=30).collect::<Vec<_>>());
+        let stats = game.player(0).unwrap().session_stats();
+        assert_eq!((stats.bets_placed, stats.decisions), (30, 30));
+        // 6:5 gives up 0.3 of a unit on every natural
+        assert!((game.house_edge() - (0.055 + 0.045 * 0.3)).abs() < 1e-12);
+        assert!((stats.theoretical_loss_cents - 30.0 * 2_500.0 * game.house_edge()).abs() < 1e-6);
+        assert_eq!(BlackjackGame::new(vec![], 6, 10, 100).house_edge(), 0.055);
+        assert_eq!(BlackjackGame::new(vec![], 6, 10, 100).with_house_edge(0.01).house_edge(), 0.01);
+        assert_eq!(ledger.verify(), Ok(()));
+    }
+
+    #[test]
+    fn test_every_hand_settles() {
+        let green = crate::bet::ChipSet::standard().chip(2_500).unwrap().clone();
+        let mut alice = Player::new("Alice");
+        alice.deposit(green.clone(), 10).unwrap();
+        let mut game = BlackjackGame::new(vec![alice], 6, 10, 1_000);
+        game.place_initial_bet(0, HashMap::from([(green.clone(), 2)])).unwrap();
+        // a split: the second hand carries its own matching stake
+        let seat = &mut game.players[0];
+        let mut bet = Bet::new();
+        bet.escrow(&mut seat.player, HashMap::from([(green.clone(), 2)]), None).unwrap();
+        seat.hands.push(Hand { cards: vec![], bet });
+
+        let round = game.play_round_with(&mut rand::rng()).unwrap();
+        assert_eq!(round.outcome.iter().map(|r| r.hand).collect::<Vec<_>>(), vec![0, 1]);
+        assert!(round.outcome.iter().all(|r| r.outcome != Outcome::PlayerBlackjack));
+        assert_eq!(round.settlements.len(), 2);
+        assert_eq!(round.staked_cents(), 10_000);
+        assert_eq!(game.staked_cents(0), 0);
+        assert_eq!(game.player(0).unwrap().total_cents(), 15_000 + round.returned_cents());
+        assert_eq!(game.player(0).unwrap().session_stats().decisions, 2);
+    }
+
+    #[test]
+    fn test_bet_errors_carry_context() {
+        let green = crate::bet::ChipSet::standard().chip(2_500).unwrap().clone();
+        let mut alice = Player::new("Alice");
+        alice.deposit(green.clone(), 10).unwrap();
+        let mut game = BlackjackGame::new(vec![alice], 6, 50, 100);
+        let bet = |n| HashMap::from([(green.clone(), n)]);
+        assert_eq!(game.place_initial_bet(2, bet(2)).unwrap_err(), Error::SeatOutOfRange { seat: 2, seats: 1 });
+        assert_eq!(
+            game.place_initial_bet(0, bet(1)).unwrap_err(),
+            Error::BelowTableMinimum { bet_cents: 2_500, minimum_cents: 5_000 }
+        );
+        assert_eq!(
+            game.place_initial_bet(0, bet(5)).unwrap_err(),
+            Error::AboveTableMaximum { bet_cents: 12_500, maximum_cents: 10_000 }
+        );
+        game.deal_initial_cards().unwrap();
+        assert!(matches!(game.place_initial_bet(0, bet(2)), Err(Error::IllegalAction { .. })));
+        assert_eq!(game.player(0).unwrap().total_cents(), 25_000);
+    }
+
+    #[test]
+    fn test_exhausted_shoe_settles_nothing() {
+        use rand::SeedableRng;
+        let green = crate::bet::ChipSet::standard().chip(2_500).unwrap().clone();
+        let players = (0..20)
+            .map(|i| {
+                let mut player = Player::new(format!("Seat {i}"));
+                player.deposit(green.clone(), 1).unwrap();
+                player
+            })
+            .collect();
+        let mut game = BlackjackGame::new(players, 1, 10, 100);
+        for seat in 0..20 {
+            game.place_initial_bet(seat, HashMap::from([(green.clone(), 1)])).unwrap();
+        }
+        let err = game.play_with(&mut rand::rngs::StdRng::seed_from_u64(7)).unwrap_err();
+        assert!(matches!(err, Error::ShoeExhausted { remaining: 0 }));
+        for seat in 0..20 {
+            assert_eq!(game.staked_cents(seat), 2_500);
+            assert_eq!(game.player(seat).unwrap().session_stats().decisions, 0);
+        }
+    }
+
+    #[test]
+    fn test_repeated_bets_count_towards_the_maximum() {
+        let black = crate::bet::ChipSet::standard().chip(10_000).unwrap().clone();
+        let mut alice = Player::new("Alice");
+        alice.deposit(black.clone(), 8).unwrap();
+        let mut game = BlackjackGame::new(vec![alice], 6, 10, 100);
+        game.place_initial_bet(0, HashMap::from([(black.clone(), 1)])).unwrap();
+        for _ in 1..8 {
+            assert_eq!(
+                game.place_initial_bet(0, HashMap::from([(black.clone(), 1)])).unwrap_err(),
+                Error::AboveTableMaximum { bet_cents: 20_000, maximum_cents: 10_000 }
+            );
+        }
+        assert_eq!(game.staked_cents(0), 10_000);
+        assert_eq!(game.player(0).unwrap().total_cents(), 70_000);
+    }
+}
@@ -1,11 +1,24 @@
+use std::collections::HashMap;
+
+use rand::{Rng, RngCore};
+
+use crate::bet::Chip;
 use crate::dice;
+use crate::dice::loaded::DiceRoller;
+use crate::error::Error;
+use crate::games::{Round, Seating, Seats, TableGame};
+use crate::ledger::Source;
+use crate::odds::{Cashier, Odds, Resolution};
 use crate::player::Player;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum CrapsType {
     StandardCraps,
     CraplessCraps,
     EasyCraps,
 }
 
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum GameState {
     // Initial state before the point is set
     ComeOut,
@@ -21,11 +34,41 @@ pub enum GameState {
     NaturalWin,
 }
 
+// Line bets, made on the come-out and working until the shooter decides them.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum CrapsBet {
+    /// wins on a natural or the point, loses on craps or a seven-out
+    PassLine,
+    /// the reverse, with 12 on the come-out barred as a push; not offered at crapless tables
+    DontPass,
+}
+
+// A throw and the state it left the game in.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct CrapsRoll {
+    pub dice: [u8; 2],
+    pub state: GameState,
+}
+
+impl CrapsRoll {
+    pub fn total(&self) -> u8 {
+        self.dice[0].saturating_add(self.dice[1])
+    }
+}
+
+pub type Settlement = crate::games::Settlement<CrapsBet>;
+
+pub type PlayerSeat = crate::games::PlayerSeat<CrapsBet>;
+
 pub struct CrapsGame {
     pub game_type: CrapsType,
     pub game_state: GameState,
     pub shooter: u8,
-    pub players: Vec<Player>,
+    pub cashier: Cashier,
+    players: Seats<PlayerSeat>,
+    roller: Box<dyn DiceRoller>,
+    /// every pair of dice thrown, in order
+    roll_history: Vec<[u8; 2]>,
 }
 
 impl CrapsGame {
@@ -34,12 +77,118 @@ impl CrapsGame {
             game_type,
             game_state: GameState::ComeOut,
             shooter: 0,
-            players,
+            cashier: Cashier::default(),
+            players: Seats::new(players),
+            roller: Box::new(dice::Die::D6),
+            roll_history: Vec::new(),
+        }
+    }
+
+    pub fn players(&self) -> &[PlayerSeat] {
+        &self.players
+    }
+
+    // Ledger source for the throw now being bet on.
+    fn source(&self) -> Source {
+        self.players.source("craps", self.roll_history.len() as u64 + 1)
+    }
+
+    // Exact edges over a decision: 7/495 on the pass line, 3/220 on don't pass with 12
+    // barred, and 5.38% on the crapless pass line.
+    pub fn house_edge(&self, bet: CrapsBet) -> f64 {
+        match (self.game_type, bet) {
+            (CrapsType::CraplessCraps, _) => 0.053_843,
+            (_, CrapsBet::PassLine) => 7.0 / 495.0,
+            (_, CrapsBet::DontPass) => 3.0 / 220.0,
+        }
+    }
+
+    // Line bets go down on the come-out only, so a point must not be set.
+    pub fn place_bet(&mut self, seat: usize, bet: CrapsBet, chips: HashMap<Chip, u32>) -> Result<(), Error> {
+        if self.game_type == CrapsType::CraplessCraps && bet == CrapsBet::DontPass {
+            return Err(Error::InvalidBet(format!("{bet:?} at a crapless table")));
+        }
+        if let GameState::PointSet(_) = self.game_state {
+            return Err(Error::IllegalAction { action: "make a line bet", state: format!("{:?}", self.game_state) });
+        }
+        let source = self.source();
+        let seats = self.players.len();
+        let house_edge = self.house_edge(bet);
+        let seat = self.players.get_mut(seat).ok_or(Error::SeatOutOfRange { seat, seats })?;
+        seat.place(bet, house_edge, chips, source)
+    }
+
+    // How the game state just reached decides a line bet; None while it keeps working.
+    fn resolve(bet: CrapsBet, roll: &CrapsRoll) -> Option<Resolution> {
+        let win = |wins: bool| if wins { Resolution::Win(Odds::even()) } else { Resolution::Lose };
+        match (bet, roll.state) {
+            (_, GameState::ComeOut | GameState::PointSet(_)) => None,
+            (CrapsBet::PassLine, state) => Some(win(matches!(state, GameState::NaturalWin | GameState::PointWin))),
+            (CrapsBet::DontPass, GameState::Craps) if roll.total() == 12 => Some(Resolution::Push),
+            (CrapsBet::DontPass, state) => Some(win(matches!(state, GameState::Craps | GameState::Lose))),
+        }
+    }
+
+    // Pay or take every line bet the roll decided. Undecided bets stay on the layout, and if
+    // the cashier cannot pay one of the decided bets nothing moves.
+    pub fn settle(&mut self, roll: &CrapsRoll) -> Result<Vec<Settlement>, Error> {
+        let source = self.players.source("craps", self.roll_history.len() as u64);
+        self.players.settle(&mut self.cashier, source, |bet| Self::resolve(bet, roll))
+    }
+
+    pub fn play(&mut self) -> Result<(CrapsRoll, Vec<Settlement>), Error> {
+        let mut rng = rand::rng();
+        self.play_with(&mut rng)
+    }
+
+    // Throw once, as a come-out or for the point, and settle the line bets it decides. The
+    // dice pass to the next shooter on a seven-out.
+    pub fn play_with<R: Rng + ?Sized>(&mut self, rng: &mut R) -> Result<(CrapsRoll, Vec<Settlement>), Error> {
+        let dice = if let GameState::PointSet(_) = self.game_state {
+            let dice = self.roll_dice_with(rng);
+            self.point_roll(dice[0] + dice[1])?;
+            dice
+        } else {
+            self.come_out_roll_with(rng)?;
+            *self.roll_history.last().expect("a come-out roll was just thrown")
+        };
+        let roll = CrapsRoll { dice, state: self.game_state };
+        let settlements = self.settle(&roll)?;
+        if self.game_state == GameState::Lose {
+            self.advance_shooter();
+        }
+        Ok((roll, settlements))
+    }
+
+    // Throw with something other than a pair of fair dice, e.g. loaded dice or a dice setter.
+    // Fails unless the roller throws six-sided dice.
+    pub fn with_roller(mut self, roller: impl DiceRoller + 'static) -> Result<Self, Error> {
+        if roller.sides() != 6 {
+            return Err(Error::WrongDice { sides: roller.sides(), expected: 6 });
         }
+        self.roller = Box::new(roller);
+        Ok(self)
+    }
+
+    pub fn roll_history(&self) -> &[[u8; 2]] {
+        &self.roll_history
+    }
+
+    pub fn roll_dice(&mut self) -> [u8; 2] {
+        let mut rng = rand::rng();
+        self.roll_dice_with(&mut rng)
+    }
+
+    // Throw the dice and record them in the roll history.
+    pub fn roll_dice_with<R: Rng + ?Sized>(&mut self, mut rng: &mut R) -> [u8; 2] {
+        let faces = self.roller.roll_dice(2, &mut rng);
+        let dice = [0, 1].map(|i| u8::try_from(faces[i]).expect("roller throws six-sided dice"));
+        self.roll_history.push(dice);
+        dice
     }
 
     pub fn advance_shooter(&mut self) {
-        self.shooter = (self.shooter + 1) % self.players.len() as u8;
+        self.shooter = (self.shooter + 1) % self.players.len().max(1) as u8;
     }
 
     pub fn reset_game(&mut self) {
@@ -51,9 +200,17 @@ impl CrapsGame {
         (d1 == d2) && (d1 == total || total == 6 || total == 8 || total == 10)
     }
 
-    pub fn come_out_roll(&mut self ){
-        let d1 = dice::Die::new(6).unwrap().roll();
-        let d2 = dice::Die::new(6).unwrap().roll();
+    pub fn come_out_roll(&mut self) -> Result<(), Error> {
+        let mut rng = rand::rng();
+        self.come_out_roll_with(&mut rng)
+    }
+
+    // Fails without rolling once a point is set.
+    pub fn come_out_roll_with<R: Rng + ?Sized>(&mut self, rng: &mut R) -> Result<(), Error> {
+        if let GameState::PointSet(_) = self.game_state {
+            return Err(Error::IllegalAction { action: "make a come-out roll", state: format!("{:?}", self.game_state) });
+        }
+        let [d1, d2] = self.roll_dice_with(rng);
 
         // hard way check
         let total = d1+ d2;
@@ -66,34 +223,157 @@ impl CrapsGame {
         }
     }
 
-    pub fn standard_craps_come_out(&mut self, total: u8) {
-        match total {
-            7 | 11 => self.game_state = GameState::NaturalWin,
-            2 | 3 | 12 => self.game_state = GameState::Craps,
-            4 | 5 | 6 | 8 | 9 | 10 => self.game_state = GameState::PointSet(total),
-            _ => {}
+    // Decide a come-out total. The last decision is cleared first, so a total two dice
+    // cannot throw fails and leaves the game coming out.
+    pub fn standard_craps_come_out(&mut self, total: u8) -> Result<(), Error> {
+        self.game_state = GameState::ComeOut;
+        self.game_state = match total {
+            7 | 11 => GameState::NaturalWin,
+            2 | 3 | 12 => GameState::Craps,
+            4 | 5 | 6 | 8 | 9 | 10 => GameState::PointSet(total),
+            _ => return Err(Error::ImpossibleRoll { total }),
+        };
+        Ok(())
+    }
+
+    pub fn crapless_craps_come_out(&mut self, total: u8) -> Result<(), Error> {
+        self.game_state = GameState::ComeOut;
+        self.game_state = match total {
+            7 | 11 => GameState::NaturalWin,
+            2 | 3 | 4 | 5 | 6 | 8 | 9 | 10 | 12 => GameState::PointSet(total),
+            _ => return Err(Error::ImpossibleRoll { total }),
+        };
+        Ok(())
+    }
+
+    pub fn point_roll(&mut self, roll: u8) -> Result<(), Error> {
+        let GameState::PointSet(point) = self.game_state else {
+            return Err(Error::IllegalAction { action: "roll for the point", state: format!("{:?}", self.game_state) });
+        };
+        if !(2..=12).contains(&roll) {
+            return Err(Error::ImpossibleRoll { total: roll });
+        }
+        if roll == point {
+            self.game_state = GameState::PointWin;
+        } else if roll == 7 {
+            self.game_state = GameState::Lose;
         }
+        Ok(())
     }
+}
+
+impl TableGame for CrapsGame {
+    type Bet = CrapsBet;
+    type Outcome = CrapsRoll;
 
-    pub fn crapless_craps_come_out(&mut self, total: u8) {
-        match total {
-            7 | 11 => self.game_state = GameState::NaturalWin,
-            2 | 3 | 4 | 5 | 6 | 8 | 9 | 10 | 12 => self.game_state = GameState::PointSet(total),
-            _ => {}
+    fn name(&self) -> &'static str {
+        "craps"
+    }
+
+    fn seating(&self) -> &dyn Seating {
+        &self.players
+    }
+
+    fn seating_mut(&mut self) -> &mut dyn Seating {
+        &mut self.players
+    }
+
+    // The dice stay with the shooter's seat, or go back to the first seat if it is gone.
+    fn unseat_player(&mut self, seat: usize) -> Result<Player, Error> {
+        let player = self.players.unseat(seat)?;
+        if self.shooter as usize >= self.players.len() {
+            self.shooter = 0;
         }
+        Ok(player)
     }
 
+    fn place_bet(&mut self, seat: usize, bet: CrapsBet, chips: HashMap<Chip, u32>) -> Result<(), Error> {
+        CrapsGame::place_bet(self, seat, bet, chips)
+    }
 
-    pub fn point_roll(&mut self, roll: u8) {
-        if let GameState::PointSet(point) = self.game_state {
-            if roll == point {
-                self.game_state = GameState::PointWin;
-            } else if roll == 7 {
-                self.game_state = GameState::Lose;
-            } else if roll == 11 {
+    fn play_round_with(&mut self, rng: &mut dyn RngCore) -> Result<Round<CrapsRoll, CrapsBet>, Error> {
+        let number = self.roll_history.len() as u64 + 1;
+        let (outcome, settlements) = self.play_with(rng)?;
+        Ok(Round { number, outcome, settlements })
+    }
+}
 
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bet::ChipSet;
+    use crate::dice::loaded::{LoadedDice, WeightedDie};
+    use rand::SeedableRng;
+    use rand::rngs::StdRng;
+
+    #[test]
+    fn test_line_bets() {
+        let red = ChipSet::standard().chip(500).unwrap().clone();
+        let mut alice = Player::new("Alice");
+        alice.deposit(red.clone(), 10).unwrap();
+        let mut game = CrapsGame::new(CrapsType::StandardCraps, vec![alice]);
+        game.place_bet(0, CrapsBet::PassLine, HashMap::from([(red.clone(), 2)])).unwrap();
+        game.place_bet(0, CrapsBet::DontPass, HashMap::from([(red.clone(), 1)])).unwrap();
+        let mut rng = StdRng::seed_from_u64(7);
+        let mut decided = Vec::new();
+        while decided.len() < 2 {
+            let (roll, settlements) = game.play_with(&mut rng).unwrap();
+            if let GameState::PointSet(_) = roll.state {
+                assert!(game.place_bet(0, CrapsBet::PassLine, HashMap::from([(red.clone(), 1)])).is_err());
             }
+            decided.extend(settlements);
+        }
+        let pass = decided.iter().find(|s| s.bet == CrapsBet::PassLine).unwrap();
+        let dont = decided.iter().find(|s| s.bet == CrapsBet::DontPass).unwrap();
+        // one side wins even money and the other loses, unless the come-out was a barred 12
+        if dont.net_cents() == 0 {
+            assert_eq!(pass.net_cents(), -1_000);
+        } else {
+            assert_eq!(pass.net_cents(), -2 * dont.net_cents());
         }
+        assert_eq!(game.player(0).unwrap().total_cents() as i64, 5_000 + pass.net_cents() + dont.net_cents());
+    }
+
+    #[test]
+    fn test_barred_twelve_and_crapless() {
+        let sixes = LoadedDice::new(vec![WeightedDie::loaded(6, 6, 1e9).unwrap(); 2]).unwrap();
+        let red = ChipSet::standard().chip(500).unwrap().clone();
+        let mut bob = Player::new("Bob");
+        bob.deposit(red.clone(), 4).unwrap();
+        let mut game = CrapsGame::new(CrapsType::StandardCraps, vec![bob]).with_roller(sixes).unwrap();
+        game.place_bet(0, CrapsBet::DontPass, HashMap::from([(red.clone(), 1)])).unwrap();
+        game.place_bet(0, CrapsBet::PassLine, HashMap::from([(red.clone(), 1)])).unwrap();
+        let (roll, settlements) = game.play_with(&mut StdRng::seed_from_u64(1)).unwrap();
+        assert_eq!((roll.total(), roll.state), (12, GameState::Craps));
+        let dont = settlements.iter().find(|s| s.bet == CrapsBet::DontPass).unwrap();
+        assert_eq!(dont.returned_cents, 500);
+        assert_eq!(game.player(0).unwrap().total_cents(), 1_500);
+
+        let mut game = CrapsGame::new(CrapsType::CraplessCraps, vec![Player::new("Carol")]);
+        let refused = game.place_bet(0, CrapsBet::DontPass, HashMap::from([(red.clone(), 1)]));
+        assert!(matches!(refused, Err(Error::InvalidBet(_))));
     }
 
-}
\ No newline at end of file
+    #[test]
+    fn test_impossible_totals_clear_the_last_decision() {
+        let red = ChipSet::standard().chip(500).unwrap().clone();
+        let mut dave = Player::new("Dave");
+        dave.deposit(red.clone(), 2).unwrap();
+        let mut game = CrapsGame::new(CrapsType::StandardCraps, vec![dave]);
+        game.standard_craps_come_out(7).unwrap();
+        game.place_bet(0, CrapsBet::PassLine, HashMap::from([(red.clone(), 1)])).unwrap();
+        assert_eq!(game.standard_craps_come_out(16), Err(Error::ImpossibleRoll { total: 16 }));
+        assert_eq!(game.game_state, GameState::ComeOut);
+        let settlements = game.settle(&CrapsRoll { dice: [8, 8], state: game.game_state }).unwrap();
+        assert!(settlements.is_empty());
+        assert_eq!(game.staked_cents(0), 500);
+
+        let mut game = CrapsGame::new(CrapsType::CraplessCraps, vec![]);
+        game.crapless_craps_come_out(11).unwrap();
+        assert_eq!(game.crapless_craps_come_out(1), Err(Error::ImpossibleRoll { total: 1 }));
+        assert_eq!(game.game_state, GameState::ComeOut);
+        game.crapless_craps_come_out(4).unwrap();
+        assert_eq!(game.point_roll(13), Err(Error::ImpossibleRoll { total: 13 }));
+        assert_eq!(game.game_state, GameState::PointSet(4));
+    }
+}
@@ -1,3 +1,389 @@
+//! Table games and the `TableGame` trait they share.
+//!
+//! Every table seats players, takes bets from their wallets, and plays rounds that settle
+//! the layout and return a `Round` record. Simulators and front ends can be written once
+//! against the trait, or hold a `Box<dyn TableGame>`, and drive any game. Game-specific
+//! actions stay on each game's own type. The seating, and the table identifier recorded in
+//! players' ledgers, live in a `Seats` every game shares.
+
 pub mod craps;
 pub mod blackjack;
-mod baccarat;
\ No newline at end of file
+pub mod baccarat;
+pub mod sicbo;
+pub mod roulette;
+
+use std::collections::HashMap;
+use std::fmt;
+use std::hash::Hash;
+use std::ops::{Deref, DerefMut};
+
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+
+use crate::bet::{Bet, Chip};
+use crate::error::Error;
+use crate::ledger::Source;
+use crate::odds::{Cashier, Payout, Resolution};
+use crate::player::Player;
+
+// One bet after its round was decided.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Settlement<B> {
+    pub seat: usize,
+    pub bet: B,
+    pub staked_cents: u64,
+    /// chips handed back, stake included; 0 for a losing bet
+    pub returned_cents: u64,
+    /// None for a losing bet
+    pub payout: Option<Payout>,
+}
+
+impl<B> Settlement<B> {
+    pub fn new(seat: usize, bet: B, staked_cents: u64, payout: Option<Payout>) -> Self {
+        let returned_cents = payout.as_ref().map_or(0, Payout::returned_cents);
+        Self { seat, bet, staked_cents, returned_cents, payout }
+    }
+
+    // Player's result on the bet, positive when they won.
+    pub fn net_cents(&self) -> i64 {
+        self.returned_cents as i64 - self.staked_cents as i64
+    }
+}
+
+// What one round produced: the game's own result and every bet it settled.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Round<O, B> {
+    /// matches the round in the ledger sources of its wagers
+    pub number: u64,
+    pub outcome: O,
+    pub settlements: Vec<Settlement<B>>,
+}
+
+impl<O, B> Round<O, B> {
+    pub fn staked_cents(&self) -> u64 {
+        self.settlements.iter().map(|s| s.staked_cents).sum()
+    }
+
+    pub fn returned_cents(&self) -> u64 {
+        self.settlements.iter().map(|s| s.returned_cents).sum()
+    }
+}
+
+// What a table keeps for each player it seats.
+pub trait Seat {
+    fn new(player: Player) -> Self;
+
+    fn player(&self) -> &Player;
+
+    fn into_player(self) -> Player;
+
+    // Chips the seat has on the layout, waiting to be decided.
+    fn staked_cents(&self) -> u64;
+
+    fn get_player_name(&self) -> &str {
+        &self.player().name
+    }
+}
+
+// A seat whose bets are kept by kind, one `Bet` for each kind on the layout.
+#[derive(Serialize, Deserialize)]
+#[serde(bound(deserialize = "B: Deserialize<'de> + Eq + Hash"))]
+pub struct PlayerSeat<B> {
+    pub player: Player,
+    pub bets: HashMap<B, Bet>,
+}
+
+impl<B: Eq + Hash> PlayerSeat<B> {
+    // Move chips from the player's wallet onto `bet`, opening it at `house_edge` if it is
+    // not on the layout yet. Nothing is taken unless the player holds every chip.
+    pub(crate) fn place(
+        &mut self,
+        bet: B,
+        house_edge: f64,
+        chips: HashMap<Chip, u32>,
+        source: Source,
+    ) -> Result<(), Error> {
+        self.bets
+            .entry(bet)
+            .or_insert_with(|| Bet::new().with_house_edge(house_edge))
+            .escrow(&mut self.player, chips, Some(source))
+    }
+}
+
+impl<B: Eq + Hash> Seat for PlayerSeat<B> {
+    fn new(player: Player) -> Self {
+        Self { player, bets: HashMap::new() }
+    }
+
+    fn player(&self) -> &Player {
+        &self.player
+    }
+
+    fn into_player(self) -> Player {
+        self.player
+    }
+
+    fn staked_cents(&self) -> u64 {
+        self.bets.values().map(Bet::total_cents).sum()
+    }
+}
+
+// The players at a table in seat order, and the table's identifier. Derefs to the seats.
+#[derive(Serialize, Deserialize)]
+pub struct Seats<S> {
+    seats: Vec<S>,
+    /// table identifier recorded in players' ledgers
+    table: String,
+}
+
+impl<S: Seat> Seats<S> {
+    pub fn new(players: Vec<Player>) -> Self {
+        Self { seats: players.into_iter().map(S::new).collect(), table: "1".to_string() }
+    }
+
+    // Ledger source for round `round` of `game` at this table.
+    pub fn source(&self, game: &str, round: u64) -> Source {
+        Source::new(game, self.table.clone(), round)
+    }
+}
+
+impl<B: Copy + Eq + Hash> Seats<PlayerSeat<B>> {
+    // Pay or take every bet `resolve` decides and clear it; undecided bets stay on the layout.
+    // Every decided bet is quoted before any is paid, so if the cashier cannot pay one of them
+    // nothing moves and the whole layout is left as it was.
+    pub(crate) fn settle(
+        &mut self,
+        cashier: &mut Cashier,
+        source: Source,
+        resolve: impl Fn(B) -> Option<Resolution>,
+    ) -> Result<Vec<Settlement<B>>, Error> {
+        for seat in &self.seats {
+            for (&bet, chips) in &seat.bets {
+                if let Some(resolution) = resolve(bet) {
+                    chips.quote(&seat.player, cashier, resolution)?;
+                }
+            }
+        }
+        let mut settlements = Vec::new();
+        for (index, seat) in self.seats.iter_mut().enumerate() {
+            let decided: Vec<(B, Resolution)> =
+                seat.bets.keys().filter_map(|&bet| resolve(bet).map(|resolution| (bet, resolution))).collect();
+            for (bet, resolution) in decided {
+                let chips = &seat.bets[&bet];
+                let staked_cents = chips.total_cents();
+                let payout = chips.settle(&mut seat.player, cashier, resolution, Some(source.clone()))?;
+                seat.bets.remove(&bet);
+                settlements.push(Settlement::new(index, bet, staked_cents, payout));
+            }
+        }
+        Ok(settlements)
+    }
+}
+
+impl<S> Deref for Seats<S> {
+    type Target = [S];
+
+    fn deref(&self) -> &[S] {
+        &self.seats
+    }
+}
+
+impl<S> DerefMut for Seats<S> {
+    fn deref_mut(&mut self) -> &mut [S] {
+        &mut self.seats
+    }
+}
+
+// The seating a `TableGame` works through, whatever its seats hold.
+pub trait Seating {
+    fn seated(&self) -> usize;
+
+    fn player(&self, seat: usize) -> Option<&Player>;
+
+    // 0 for an empty or unknown seat.
+    fn staked_cents(&self, seat: usize) -> u64;
+
+    fn table(&self) -> &str;
+
+    fn set_table(&mut self, table: String);
+
+    // Sit `player` at the next free seat and return its index.
+    fn seat(&mut self, player: Player) -> usize;
+
+    // Refused while the player has bets on the layout. Later seats move down one.
+    fn unseat(&mut self, seat: usize) -> Result<Player, Error>;
+}
+
+impl<S: Seat> Seating for Seats<S> {
+    fn seated(&self) -> usize {
+        self.seats.len()
+    }
+
+    fn player(&self, seat: usize) -> Option<&Player> {
+        self.seats.get(seat).map(Seat::player)
+    }
+
+    fn staked_cents(&self, seat: usize) -> u64 {
+        self.seats.get(seat).map_or(0, Seat::staked_cents)
+    }
+
+    fn table(&self) -> &str {
+        &self.table
+    }
+
+    fn set_table(&mut self, table: String) {
+        self.table = table;
+    }
+
+    fn seat(&mut self, player: Player) -> usize {
+        self.seats.push(S::new(player));
+        self.seats.len() - 1
+    }
+
+    fn unseat(&mut self, seat: usize) -> Result<Player, Error> {
+        let seats = self.seats.len();
+        let held = self.seats.get(seat).ok_or(Error::SeatOutOfRange { seat, seats })?;
+        if held.staked_cents() > 0 {
+            return Err(Error::IllegalAction { action: "leave the table", state: "bets are on the layout".to_string() });
+        }
+        Ok(self.seats.remove(seat).into_player())
+    }
+}
+
+pub trait TableGame {
+    type Bet: Copy + fmt::Debug + PartialEq;
+    type Outcome: Clone + fmt::Debug;
+
+    // Game name as recorded in ledger sources, e.g. "baccarat".
+    fn name(&self) -> &'static str;
+
+    // The table's players; the seating methods below work through it.
+    fn seating(&self) -> &dyn Seating;
+
+    fn seating_mut(&mut self) -> &mut dyn Seating;
+
+    fn seats(&self) -> usize {
+        self.seating().seated()
+    }
+
+    fn player(&self, seat: usize) -> Option<&Player> {
+        self.seating().player(seat)
+    }
+
+    // Sit `player` at the next free seat and return its index.
+    fn seat_player(&mut self, player: Player) -> usize {
+        self.seating_mut().seat(player)
+    }
+
+    // Stand the player up with their wallet. Refused while they have bets on the layout.
+    // Later seats move down one.
+    fn unseat_player(&mut self, seat: usize) -> Result<Player, Error> {
+        self.seating_mut().unseat(seat)
+    }
+
+    // Chips the seat has on the layout, waiting to be decided. 0 for an empty or unknown seat.
+    fn staked_cents(&self, seat: usize) -> u64 {
+        self.seating().staked_cents(seat)
+    }
+
+    // Table identifier recorded in players' ledgers.
+    fn table(&self) -> &str {
+        self.seating().table()
+    }
+
+    fn with_table(mut self, table: impl Into<String>) -> Self
+    where
+        Self: Sized,
+    {
+        self.seating_mut().set_table(table.into());
+        self
+    }
+
+    // Move chips from the seat's wallet onto `bet`. Nothing is taken unless every chip is
+    // there and the bet is allowed.
+    fn place_bet(&mut self, seat: usize, bet: Self::Bet, chips: HashMap<Chip, u32>) -> Result<(), Error>;
+
+    // Play one round, settling every bet it decides. Every shuffle, throw and spin draws on
+    // `rng`, so a seeded generator replays the same rounds.
+    fn play_round_with(&mut self, rng: &mut dyn RngCore) -> Result<Round<Self::Outcome, Self::Bet>, Error>;
+
+    fn play_round(&mut self) -> Result<Round<Self::Outcome, Self::Bet>, Error> {
+        self.play_round_with(&mut rand::rng())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bet::ChipSet;
+    use crate::games::baccarat::{BaccaratBet, BaccaratGame};
+    use crate::games::blackjack::{BlackjackBet, BlackjackGame};
+    use crate::games::craps::{CrapsBet, CrapsGame, CrapsType};
+    use crate::games::roulette::{RouletteBet, RouletteGame};
+    use crate::games::sicbo::{PayTable, SicBoBet, SicBoGame};
+    use crate::roulette::{RouletteWheel, WheelType};
+    use rand::SeedableRng;
+    use rand::rngs::StdRng;
+
+    // Keep a $5 bet working and check every cent against the round records.
+    fn drive<G: TableGame + ?Sized>(game: &mut G, bet: G::Bet, rounds: usize) {
+        let red = ChipSet::standard().chip(500).unwrap().clone();
+        let mut player = Player::new("Sim");
+        player.deposit(red.clone(), 200).unwrap();
+        let seat = game.seat_player(player);
+        let mut rng = StdRng::seed_from_u64(48);
+        let (mut net, mut last) = (0, 0);
+        for _ in 0..rounds {
+            if game.staked_cents(seat) == 0 {
+                game.place_bet(seat, bet, HashMap::from([(red.clone(), 1)])).unwrap();
+            }
+            let round = game.play_round_with(&mut rng).unwrap();
+            assert!(round.number > last);
+            last = round.number;
+            assert!(round.settlements.iter().all(|s| s.seat == seat && s.bet == bet));
+            net += round.settlements.iter().map(Settlement::net_cents).sum::<i64>();
+            let held = game.player(seat).unwrap().total_cents() + game.staked_cents(seat);
+            assert_eq!(held as i64, 100_000 + net);
+        }
+        assert_eq!(game.player(seat).unwrap().ledger().verify(), Ok(()));
+    }
+
+    #[test]
+    fn test_every_game_drives_generically() {
+        let bystander = || vec![Player::new("Bystander")];
+        drive(&mut SicBoGame::new(bystander(), PayTable::macau()), SicBoBet::Big, 30);
+        drive(&mut BaccaratGame::new(bystander()), BaccaratBet::Banker, 30);
+        drive(&mut BlackjackGame::new(bystander(), 6, 5, 500), BlackjackBet::Hand, 30);
+        drive(&mut RouletteGame::new(bystander(), RouletteWheel::new(WheelType::European)), RouletteBet::Red, 30);
+        drive(&mut CrapsGame::new(CrapsType::StandardCraps, bystander()), CrapsBet::PassLine, 30);
+    }
+
+    #[test]
+    fn test_games_drive_as_trait_objects() {
+        let mut tables: Vec<Box<dyn TableGame<Bet = SicBoBet, Outcome = _>>> = vec![
+            Box::new(SicBoGame::new(vec![], PayTable::macau()).with_table("A")),
+            Box::new(SicBoGame::new(vec![], PayTable::atlantic_city()).with_table("B")),
+        ];
+        for table in &mut tables {
+            drive(table.as_mut(), SicBoBet::Small, 10);
+        }
+        assert_eq!(tables.iter().map(|table| table.table()).collect::<Vec<_>>(), ["A", "B"]);
+    }
+
+    #[test]
+    fn test_unseat_player() {
+        let red = ChipSet::standard().chip(500).unwrap().clone();
+        let mut alice = Player::new("Alice");
+        alice.deposit(red.clone(), 2).unwrap();
+        let mut game = SicBoGame::new(vec![Player::new("Bob")], PayTable::macau());
+        let seat = game.seat_player(alice);
+        assert_eq!((seat, game.seats()), (1, 2));
+        TableGame::place_bet(&mut game, seat, SicBoBet::Small, HashMap::from([(red.clone(), 1)])).unwrap();
+        assert!(matches!(game.unseat_player(seat), Err(Error::IllegalAction { .. })));
+        assert_eq!(game.unseat_player(5).unwrap_err(), Error::SeatOutOfRange { seat: 5, seats: 2 });
+        game.play_round().unwrap();
+        let alice = game.unseat_player(seat).unwrap();
+        assert_eq!(alice.name, "Alice");
+        assert_eq!(game.seats(), 1);
+    }
+}
@@ -0,0 +1,261 @@
+//! Roulette as a table game: inside and outside bets on a `RouletteWheel`.
+//!
+//! Every bet is paid to-one, and every zero pocket loses the outside bets. Each bet's house
+//! edge comes from the wheel's pocket probabilities, so a biased wheel rates play on what it
+//! actually gives up.
+
+use std::collections::HashMap;
+
+use rand::{Rng, RngCore};
+
+use crate::bet::Chip;
+use crate::error::Error;
+use crate::games::{Round, Seating, Seats, TableGame};
+use crate::ledger::Source;
+use crate::odds::{Cashier, Odds, Resolution};
+use crate::player::Player;
+use crate::roulette::{RouletteColor, RouletteNumber, RouletteWheel};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum RouletteBet {
+    /// a single pocket; 37 is 00 and 38 is 000
+    Straight(u16),
+    Red,
+    Black,
+    Odd,
+    Even,
+    /// 1 to 18
+    Low,
+    /// 19 to 36
+    High,
+    /// 1 to 3 for 1-12, 13-24 and 25-36
+    Dozen(u8),
+    /// 1 to 3, the column holding 1, 2 or 3
+    Column(u8),
+}
+
+impl RouletteBet {
+    // Winnings to one.
+    pub fn payout(&self) -> u64 {
+        match self {
+            RouletteBet::Straight(_) => 35,
+            RouletteBet::Dozen(_) | RouletteBet::Column(_) => 2,
+            _ => 1,
+        }
+    }
+
+    pub fn wins(&self, pocket: RouletteNumber) -> bool {
+        let n = pocket.number;
+        if let RouletteBet::Straight(number) = self {
+            return n == *number;
+        }
+        if pocket.color == RouletteColor::Green {
+            return false;
+        }
+        match self {
+            RouletteBet::Straight(_) => unreachable!("handled above"),
+            RouletteBet::Red => pocket.color == RouletteColor::Red,
+            RouletteBet::Black => pocket.color == RouletteColor::Black,
+            RouletteBet::Odd => !n.is_multiple_of(2),
+            RouletteBet::Even => n.is_multiple_of(2),
+            RouletteBet::Low => n <= 18,
+            RouletteBet::High => n >= 19,
+            RouletteBet::Dozen(dozen) => (n - 1) / 12 + 1 == *dozen as u16,
+            RouletteBet::Column(column) => (n - 1) % 3 + 1 == *column as u16,
+        }
+    }
+}
+
+pub type Settlement = crate::games::Settlement<RouletteBet>;
+
+pub type PlayerSeat = crate::games::PlayerSeat<RouletteBet>;
+
+pub struct RouletteGame {
+    pub wheel: RouletteWheel,
+    pub cashier: Cashier,
+    players: Seats<PlayerSeat>,
+    history: Vec<RouletteNumber>,
+}
+
+impl RouletteGame {
+    pub fn new(players: Vec<Player>, wheel: RouletteWheel) -> Self {
+        Self {
+            wheel,
+            cashier: Cashier::default(),
+            players: Seats::new(players),
+            history: Vec::new(),
+        }
+    }
+
+    // Ledger source for the spin now being bet on.
+    fn source(&self) -> Source {
+        self.players.source("roulette", self.history.len() as u64 + 1)
+    }
+
+    pub fn players(&self) -> &[PlayerSeat] {
+        &self.players
+    }
+
+    pub fn history(&self) -> &[RouletteNumber] {
+        &self.history
+    }
+
+    // False for a pocket the wheel does not have or a dozen or column outside 1 to 3.
+    pub fn is_valid(&self, bet: RouletteBet) -> bool {
+        match bet {
+            RouletteBet::Straight(number) => self.wheel.wheel.numbers().iter().any(|n| n.number == number),
+            RouletteBet::Dozen(k) | RouletteBet::Column(k) => (1..=3).contains(&k),
+            _ => true,
+        }
+    }
+
+    // Share of each stake the house keeps on this wheel, bias included.
+    pub fn house_edge(&self, bet: RouletteBet) -> f64 {
+        let returned: f64 = self
+            .wheel
+            .wheel
+            .numbers()
+            .into_iter()
+            .zip(self.wheel.pocket_probabilities())
+            .filter(|&(pocket, _)| bet.wins(pocket))
+            .map(|(_, p)| p * (bet.payout() + 1) as f64)
+            .sum();
+        1.0 - returned
+    }
+
+    pub fn place_bet(&mut self, seat: usize, bet: RouletteBet, chips: HashMap<Chip, u32>) -> Result<(), Error> {
+        if !self.is_valid(bet) {
+            return Err(Error::InvalidBet(format!("{bet:?}")));
+        }
+        let source = self.source();
+        let seats = self.players.len();
+        let house_edge = self.house_edge(bet);
+        let seat = self.players.get_mut(seat).ok_or(Error::SeatOutOfRange { seat, seats })?;
+        seat.place(bet, house_edge, chips, source)
+    }
+
+    // Pay or take every bet on the layout, clear it and record the pocket. If the cashier
+    // cannot pay one of them nothing moves and the pocket is not recorded.
+    pub fn settle(&mut self, pocket: RouletteNumber) -> Result<Vec<Settlement>, Error> {
+        let source = self.source();
+        let settlements = self.players.settle(&mut self.cashier, source, |bet| {
+            Some(if bet.wins(pocket) { Resolution::Win(Odds::to_one(bet.payout())) } else { Resolution::Lose })
+        })?;
+        self.history.push(pocket);
+        Ok(settlements)
+    }
+
+    pub fn play(&mut self) -> Result<(RouletteNumber, Vec<Settlement>), Error> {
+        let mut rng = rand::rng();
+        self.play_with(&mut rng)
+    }
+
+    pub fn play_with<R: Rng + ?Sized>(&mut self, rng: &mut R) -> Result<(RouletteNumber, Vec<Settlement>), Error> {
+        let pocket = self.wheel.spin_with(rng);
+        Ok((pocket, self.settle(pocket)?))
+    }
+}
+
+impl TableGame for RouletteGame {
+    type Bet = RouletteBet;
+    type Outcome = RouletteNumber;
+
+    fn name(&self) -> &'static str {
+        "roulette"
+    }
+
+    fn seating(&self) -> &dyn Seating {
+        &self.players
+    }
+
+    fn seating_mut(&mut self) -> &mut dyn Seating {
+        &mut self.players
+    }
+
+    fn place_bet(&mut self, seat: usize, bet: RouletteBet, chips: HashMap<Chip, u32>) -> Result<(), Error> {
+        RouletteGame::place_bet(self, seat, bet, chips)
+    }
+
+    fn play_round_with(&mut self, rng: &mut dyn RngCore) -> Result<Round<RouletteNumber, RouletteBet>, Error> {
+        let number = self.history.len() as u64 + 1;
+        let (outcome, settlements) = self.play_with(rng)?;
+        Ok(Round { number, outcome, settlements })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bet::{ChipSet, Denomination};
+    use crate::odds::Breakage;
+    use crate::roulette::WheelType;
+
+    #[test]
+    fn test_house_edge() {
+        let european = RouletteGame::new(vec![], RouletteWheel::new(WheelType::European));
+        let american = RouletteGame::new(vec![], RouletteWheel::new(WheelType::American));
+        for bet in [RouletteBet::Red, RouletteBet::Straight(17), RouletteBet::Dozen(2), RouletteBet::Column(3)] {
+            assert!((european.house_edge(bet) - 1.0 / 37.0).abs() < 1e-12);
+            assert!((american.house_edge(bet) - 2.0 / 38.0).abs() < 1e-12);
+        }
+        assert!(american.is_valid(RouletteBet::Straight(37)));
+        assert!(!european.is_valid(RouletteBet::Straight(37)));
+        assert!(!european.is_valid(RouletteBet::Dozen(4)));
+    }
+
+    #[test]
+    fn test_settle() {
+        let red = ChipSet::standard().chip(500).unwrap().clone();
+        let mut alice = Player::new("Alice");
+        alice.deposit(red.clone(), 10).unwrap();
+        let mut game = RouletteGame::new(vec![alice], RouletteWheel::new(WheelType::European));
+        for bet in [RouletteBet::Straight(14), RouletteBet::Red, RouletteBet::Odd, RouletteBet::Column(2)] {
+            game.place_bet(0, bet, HashMap::from([(red.clone(), 1)])).unwrap();
+        }
+        assert!(matches!(
+            game.place_bet(0, RouletteBet::Straight(38), HashMap::from([(red.clone(), 1)])),
+            Err(Error::InvalidBet(_))
+        ));
+        // 14 is red, even and in the second column
+        let settlements = game.settle(RouletteNumber::new(14).unwrap()).unwrap();
+        let returned = |bet| settlements.iter().find(|s| s.bet == bet).unwrap().returned_cents;
+        assert_eq!(returned(RouletteBet::Straight(14)), 18_000);
+        assert_eq!(returned(RouletteBet::Red), 1_000);
+        assert_eq!(returned(RouletteBet::Odd), 0);
+        assert_eq!(returned(RouletteBet::Column(2)), 1_500);
+        assert_eq!(game.players()[0].player.total_cents(), 3_000 + 18_000 + 1_000 + 1_500);
+        assert_eq!(game.history().len(), 1);
+
+        // a zero takes every outside bet
+        game.place_bet(0, RouletteBet::Black, HashMap::from([(red.clone(), 1)])).unwrap();
+        let settlements = game.settle(RouletteNumber::new(0).unwrap()).unwrap();
+        assert_eq!(settlements[0].payout, None);
+    }
+
+    #[test]
+    fn test_unpayable_spin_settles_nothing() {
+        let red = ChipSet::standard().chip(500).unwrap().clone();
+        let odd = Chip::new("odd", "$42,949,672.96", 1 << 32);
+        let (mut alice, mut bob) = (Player::new("Alice"), Player::new("Bob"));
+        alice.deposit(red.clone(), 1).unwrap();
+        bob.deposit(odd.clone(), 1).unwrap();
+        let mut game = RouletteGame::new(vec![alice, bob], RouletteWheel::new(WheelType::European));
+        game.place_bet(0, RouletteBet::Red, HashMap::from([(red.clone(), 1)])).unwrap();
+        game.place_bet(1, RouletteBet::Red, HashMap::from([(odd.clone(), 1)])).unwrap();
+        // paid to the cent with nothing smaller than a plaque, Bob's win needs more coins than
+        // a u32 holds
+        let plaque = Denomination { chip: Chip::new("plaque", "$100,000,000", 10_000_000_000), colour: "gold".into() };
+        game.cashier = Cashier::new(ChipSet::new(vec![plaque]).unwrap(), Breakage::Cents);
+        let pocket = RouletteNumber::new(14).unwrap();
+        assert_eq!(game.settle(pocket).unwrap_err(), Error::Unpayable { cents: 1 << 32 });
+        // Alice's bet, quoted first, was not paid either
+        assert_eq!((game.staked_cents(0), game.staked_cents(1)), (500, 1 << 32));
+        assert_eq!(game.players()[0].player.total_cents(), 0);
+        assert!(game.history().is_empty());
+
+        game.cashier = Cashier::default();
+        assert_eq!(game.settle(pocket).unwrap().len(), 2);
+        assert_eq!(game.players()[0].player.total_cents(), 1_000);
+        assert_eq!(game.history().len(), 1);
+    }
+}
@@ -0,0 +1,413 @@
+//! Sic Bo: three dice, bets on totals, doubles, triples and single numbers.
+//!
+//! Payouts are to-one and come from a `PayTable`, with the common Macau and Atlantic City
+//! tables built in. Chips are taken from the player's wallet when a bet is placed; a winning
+//! bet returns its chips and the table's `Cashier` pays the winnings, a losing bet's chips
+//! stay with the house.
+
+use std::collections::HashMap;
+
+use num_bigint::BigInt;
+use num_rational::BigRational;
+use num_traits::{One, ToPrimitive};
+use rand::{Rng, RngCore};
+
+use crate::bet::Chip;
+use crate::dice::Die;
+use crate::dice::loaded::DiceRoller;
+use crate::games::{Round, Seating, Seats, TableGame};
+use crate::ledger::Source;
+use crate::odds::{Cashier, Odds, Resolution};
+use crate::player::Player;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum SicBoBet {
+    /// totals 4 to 10, losing on any triple
+    Small,
+    /// totals 11 to 17, losing on any triple
+    Big,
+    Odd,
+    Even,
+    /// all three dice showing the number
+    Triple(u8),
+    AnyTriple,
+    /// at least two dice showing the number
+    Double(u8),
+    /// a total from 4 to 17
+    Total(u8),
+    /// two different numbers each on at least one die
+    Combination(u8, u8),
+    /// pays by how many dice show the number
+    Single(u8),
+}
+
+impl SicBoBet {
+    pub fn is_valid(&self) -> bool {
+        let face = |n: u8| (1..=6).contains(&n);
+        match *self {
+            SicBoBet::Triple(n) | SicBoBet::Double(n) | SicBoBet::Single(n) => face(n),
+            SicBoBet::Total(t) => (4..=17).contains(&t),
+            SicBoBet::Combination(a, b) => face(a) && face(b) && a != b,
+            _ => true,
+        }
+    }
+
+    // Every valid bet on the layout.
+    pub fn all() -> Vec<SicBoBet> {
+        let mut bets = vec![SicBoBet::Small, SicBoBet::Big, SicBoBet::Odd, SicBoBet::Even, SicBoBet::AnyTriple];
+        for n in 1..=6 {
+            bets.extend([SicBoBet::Triple(n), SicBoBet::Double(n), SicBoBet::Single(n)]);
+            bets.extend((n + 1..=6).map(|m| SicBoBet::Combination(n, m)));
+        }
+        bets.extend((4..=17).map(SicBoBet::Total));
+        bets
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SicBoRoll {
+    pub dice: [u8; 3],
+}
+
+impl SicBoRoll {
+    pub fn total(&self) -> u8 {
+        self.dice.iter().fold(0, |total, &d| total.saturating_add(d))
+    }
+
+    pub fn count(&self, face: u8) -> usize {
+        self.dice.iter().filter(|&&d| d == face).count()
+    }
+
+    pub fn is_triple(&self) -> bool {
+        self.dice[0] == self.dice[1] && self.dice[1] == self.dice[2]
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PayTable {
+    pub small_big: u32,
+    pub odd_even: u32,
+    pub specific_triple: u32,
+    pub any_triple: u32,
+    pub specific_double: u32,
+    /// payouts for totals 4 through 17
+    pub totals: [u32; 14],
+    pub combination: u32,
+    /// single-number payouts when the number shows on one, two or three dice
+    pub single: [u32; 3],
+}
+
+impl PayTable {
+    pub fn macau() -> Self {
+        Self {
+            small_big: 1,
+            odd_even: 1,
+            specific_triple: 150,
+            any_triple: 24,
+            specific_double: 8,
+            totals: [50, 18, 14, 12, 8, 6, 6, 6, 6, 8, 12, 14, 18, 50],
+            combination: 5,
+            single: [1, 2, 3],
+        }
+    }
+
+    pub fn atlantic_city() -> Self {
+        Self {
+            small_big: 1,
+            odd_even: 1,
+            specific_triple: 180,
+            any_triple: 30,
+            specific_double: 10,
+            totals: [60, 30, 17, 12, 8, 6, 6, 6, 6, 8, 12, 17, 30, 60],
+            combination: 6,
+            single: [1, 2, 3],
+        }
+    }
+
+    // The to-one payout for `bet` on `roll`, or None if the bet loses.
+    pub fn payout(&self, bet: SicBoBet, roll: &SicBoRoll) -> Option<u32> {
+        let total = roll.total();
+        let wins = match bet {
+            SicBoBet::Small => !roll.is_triple() && total <= 10,
+            SicBoBet::Big => !roll.is_triple() && total >= 11,
+            SicBoBet::Odd => !roll.is_triple() && !total.is_multiple_of(2),
+            SicBoBet::Even => !roll.is_triple() && total.is_multiple_of(2),
+            SicBoBet::Triple(n) => roll.count(n) == 3,
+            SicBoBet::AnyTriple => roll.is_triple(),
+            SicBoBet::Double(n) => roll.count(n) >= 2,
+            SicBoBet::Total(t) => total == t,
+            SicBoBet::Combination(a, b) => a != b && roll.count(a) > 0 && roll.count(b) > 0,
+            SicBoBet::Single(n) => roll.count(n) > 0,
+        };
+        if !wins || !bet.is_valid() {
+            return None;
+        }
+        Some(match bet {
+            SicBoBet::Small | SicBoBet::Big => self.small_big,
+            SicBoBet::Odd | SicBoBet::Even => self.odd_even,
+            SicBoBet::Triple(_) => self.specific_triple,
+            SicBoBet::AnyTriple => self.any_triple,
+            SicBoBet::Double(_) => self.specific_double,
+            SicBoBet::Total(t) => self.totals[t as usize - 4],
+            SicBoBet::Combination(..) => self.combination,
+            SicBoBet::Single(n) => self.single[roll.count(n) - 1],
+        })
+    }
+
+    // Exact expected return per unit staked, over all 216 rolls.
+    pub fn expected_return(&self, bet: SicBoBet) -> BigRational {
+        let mut returned = 0u64;
+        for a in 1..=6 {
+            for b in 1..=6 {
+                for c in 1..=6 {
+                    if let Some(payout) = self.payout(bet, &SicBoRoll { dice: [a, b, c] }) {
+                        returned += payout as u64 + 1;
+                    }
+                }
+            }
+        }
+        BigRational::new(BigInt::from(returned), BigInt::from(216))
+    }
+
+    pub fn house_edge(&self, bet: SicBoBet) -> f64 {
+        (BigRational::one() - self.expected_return(bet)).to_f64().unwrap_or(0.0)
+    }
+}
+
+pub use crate::error::Error;
+
+pub type Settlement = crate::games::Settlement<SicBoBet>;
+
+pub type PlayerSeat = crate::games::PlayerSeat<SicBoBet>;
+
+pub struct SicBoGame {
+    pub pay_table: PayTable,
+    pub cashier: Cashier,
+    players: Seats<PlayerSeat>,
+    history: Vec<SicBoRoll>,
+    roller: Box<dyn DiceRoller>,
+}
+
+impl SicBoGame {
+    pub fn new(players: Vec<Player>, pay_table: PayTable) -> Self {
+        Self {
+            pay_table,
+            cashier: Cashier::default(),
+            players: Seats::new(players),
+            history: Vec::new(),
+            roller: Box::new(Die::D6),
+        }
+    }
+
+    // Ledger source for the round now being bet on.
+    fn source(&self) -> Source {
+        self.players.source("sicbo", self.history.len() as u64 + 1)
+    }
+
+    // Shake with something other than three fair dice. Fails unless the roller throws
+    // six-sided dice.
+    pub fn with_roller(mut self, roller: impl DiceRoller + 'static) -> Result<Self, Error> {
+        if roller.sides() != 6 {
+            return Err(Error::WrongDice { sides: roller.sides(), expected: 6 });
+        }
+        self.roller = Box::new(roller);
+        Ok(self)
+    }
+
+    pub fn players(&self) -> &[PlayerSeat] {
+        &self.players
+    }
+
+    pub fn history(&self) -> &[SicBoRoll] {
+        &self.history
+    }
+
+    pub fn place_bet(&mut self, seat: usize, bet: SicBoBet, chips: HashMap<Chip, u32>) -> Result<(), Error> {
+        if !bet.is_valid() {
+            return Err(Error::InvalidBet(format!("{bet:?}")));
+        }
+        let source = self.source();
+        let seats = self.players.len();
+        let seat = self.players.get_mut(seat).ok_or(Error::SeatOutOfRange { seat, seats })?;
+        let house_edge = self.pay_table.house_edge(bet);
+        seat.place(bet, house_edge, chips, source)
+    }
+
+    pub fn roll(&mut self) -> SicBoRoll {
+        let mut rng = rand::rng();
+        self.roll_with(&mut rng)
+    }
+
+    pub fn roll_with<R: Rng + ?Sized>(&mut self, mut rng: &mut R) -> SicBoRoll {
+        let faces = self.roller.roll_dice(3, &mut rng);
+        SicBoRoll { dice: [0, 1, 2].map(|i| u8::try_from(faces[i]).expect("roller throws six-sided dice")) }
+    }
+
+    // Pay winning bets into each player's wallet, clear the layout and record the roll. If the
+    // cashier cannot pay one of them nothing moves and the roll is not recorded.
+    pub fn settle(&mut self, roll: SicBoRoll) -> Result<Vec<Settlement>, Error> {
+        let source = self.source();
+        let pay_table = &self.pay_table;
+        let settlements = self.players.settle(&mut self.cashier, source, |bet| {
+            Some(match pay_table.payout(bet, &roll) {
+                Some(p) => Resolution::Win(Odds::to_one(p as u64)),
+                None => Resolution::Lose,
+            })
+        })?;
+        self.history.push(roll);
+        Ok(settlements)
+    }
+
+    pub fn play(&mut self) -> Result<(SicBoRoll, Vec<Settlement>), Error> {
+        let mut rng = rand::rng();
+        self.play_with(&mut rng)
+    }
+
+    pub fn play_with<R: Rng + ?Sized>(&mut self, rng: &mut R) -> Result<(SicBoRoll, Vec<Settlement>), Error> {
+        let roll = self.roll_with(rng);
+        Ok((roll, self.settle(roll)?))
+    }
+}
+
+impl TableGame for SicBoGame {
+    type Bet = SicBoBet;
+    type Outcome = SicBoRoll;
+
+    fn name(&self) -> &'static str {
+        "sicbo"
+    }
+
+    fn seating(&self) -> &dyn Seating {
+        &self.players
+    }
+
+    fn seating_mut(&mut self) -> &mut dyn Seating {
+        &mut self.players
+    }
+
+    fn place_bet(&mut self, seat: usize, bet: SicBoBet, chips: HashMap<Chip, u32>) -> Result<(), Error> {
+        SicBoGame::place_bet(self, seat, bet, chips)
+    }
+
+    fn play_round_with(&mut self, rng: &mut dyn RngCore) -> Result<Round<SicBoRoll, SicBoBet>, Error> {
+        let number = self.history.len() as u64 + 1;
+        let (outcome, settlements) = self.play_with(rng)?;
+        Ok(Round { number, outcome, settlements })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dice::loaded::WeightedDie;
+    use rand::SeedableRng;
+    use rand::rngs::StdRng;
+
+    fn dollar() -> Chip {
+        Chip { name: "one dollar".to_string(), display: "$1".to_string(), value_cents: 100 }
+    }
+
+    fn roll(a: u8, b: u8, c: u8) -> SicBoRoll {
+        SicBoRoll { dice: [a, b, c] }
+    }
+
+    fn ratio(n: i64, d: i64) -> BigRational {
+        BigRational::new(BigInt::from(n), BigInt::from(d))
+    }
+
+    #[test]
+    fn test_payouts() {
+        let table = PayTable::macau();
+        assert_eq!(table.payout(SicBoBet::Big, &roll(6, 5, 1)), Some(1));
+        assert_eq!(table.payout(SicBoBet::Big, &roll(5, 5, 5)), None);
+        assert_eq!(table.payout(SicBoBet::Small, &roll(1, 1, 1)), None);
+        assert_eq!(table.payout(SicBoBet::Even, &roll(2, 2, 4)), Some(1));
+        assert_eq!(table.payout(SicBoBet::Triple(5), &roll(5, 5, 5)), Some(150));
+        assert_eq!(table.payout(SicBoBet::AnyTriple, &roll(2, 2, 2)), Some(24));
+        assert_eq!(table.payout(SicBoBet::Double(3), &roll(3, 3, 3)), Some(8));
+        assert_eq!(table.payout(SicBoBet::Total(4), &roll(1, 1, 2)), Some(50));
+        assert_eq!(table.payout(SicBoBet::Combination(2, 6), &roll(6, 2, 2)), Some(5));
+        assert_eq!(table.payout(SicBoBet::Single(4), &roll(4, 1, 4)), Some(2));
+        assert_eq!(table.payout(SicBoBet::Single(4), &roll(3, 1, 2)), None);
+        assert_eq!(PayTable::atlantic_city().payout(SicBoBet::Total(17), &roll(6, 6, 5)), Some(60));
+        assert!(!SicBoBet::Total(3).is_valid());
+        assert!(!SicBoBet::Combination(4, 4).is_valid());
+        assert_eq!(SicBoBet::all().len(), 5 + 18 + 15 + 14);
+    }
+
+    #[test]
+    fn test_house_edges() {
+        let macau = PayTable::macau();
+        // big/small: 105 winning rolls of 216
+        assert_eq!(macau.expected_return(SicBoBet::Big), ratio(210, 216));
+        // single number: 75 one-dice, 15 two-dice and 1 three-dice rolls
+        assert_eq!(macau.expected_return(SicBoBet::Single(3)), ratio(75 * 2 + 15 * 3 + 4, 216));
+        assert_eq!(macau.expected_return(SicBoBet::Triple(1)), ratio(151, 216));
+        assert_eq!(PayTable::atlantic_city().expected_return(SicBoBet::AnyTriple), ratio(6 * 31, 216));
+    }
+
+    #[test]
+    fn test_place_and_settle() {
+        let mut alice = Player::new("Alice");
+        alice.deposit(dollar(), 20).unwrap();
+        let mut game = SicBoGame::new(vec![alice], PayTable::macau());
+        game.place_bet(0, SicBoBet::Big, HashMap::from([(dollar(), 5)])).unwrap();
+        game.place_bet(0, SicBoBet::Single(6), HashMap::from([(dollar(), 2)])).unwrap();
+        game.place_bet(0, SicBoBet::Triple(1), HashMap::from([(dollar(), 1)])).unwrap();
+        assert_eq!(game.players()[0].player.total_cents(), 1_200);
+
+        let settlements = game.settle(roll(6, 6, 2)).unwrap();
+        assert_eq!(settlements.len(), 3);
+        // big returns 5 x 2, single six twice returns 2 x 3, the triple loses
+        assert_eq!(game.players()[0].player.total_cents(), 1_200 + 1_000 + 600);
+        let single = settlements.iter().find(|s| s.bet == SicBoBet::Single(6)).unwrap();
+        assert_eq!((single.staked_cents, single.returned_cents), (200, 600));
+        assert!(game.players()[0].bets.is_empty());
+        assert_eq!(game.history(), &[roll(6, 6, 2)]);
+        // three wagers and two wins, all in round 1
+        let ledger = game.players()[0].player.ledger();
+        assert_eq!(ledger.by_source("sicbo", "1").count(), 5);
+        assert!(ledger.by_source("sicbo", "1").all(|tx| tx.source.as_ref().unwrap().round == 1));
+        assert_eq!(ledger.verify(), Ok(()));
+    }
+
+    #[test]
+    fn test_bet_errors() {
+        let mut bob = Player::new("Bob");
+        bob.deposit(dollar(), 3).unwrap();
+        let mut game = SicBoGame::new(vec![bob], PayTable::atlantic_city());
+        let chips = |n| HashMap::from([(dollar(), n)]);
+        assert_eq!(game.place_bet(1, SicBoBet::Big, chips(1)), Err(Error::SeatOutOfRange { seat: 1, seats: 1 }));
+        assert_eq!(game.place_bet(0, SicBoBet::Total(18), chips(1)), Err(Error::InvalidBet("Total(18)".to_string())));
+        assert_eq!(
+            game.place_bet(0, SicBoBet::Big, chips(4)),
+            Err(Error::InsufficientChips { chip: "one dollar".to_string(), wanted: 4, held: 3 })
+        );
+        assert_eq!(game.players()[0].player.total_cents(), 300);
+    }
+
+    #[test]
+    fn test_play_rolls_three_dice() {
+        let mut game = SicBoGame::new(vec![Player::new("Carol")], PayTable::macau());
+        let mut rng = StdRng::seed_from_u64(39);
+        for _ in 0..100 {
+            let (roll, settlements) = game.play_with(&mut rng).unwrap();
+            assert!(roll.dice.iter().all(|d| (1..=6).contains(d)));
+            assert!(settlements.is_empty());
+        }
+        assert_eq!(game.history().len(), 100);
+    }
+
+    #[test]
+    fn test_loaded_dice() {
+        let sixes = WeightedDie::new(vec![0.0, 0.0, 0.0, 0.0, 0.0, 1.0]).unwrap();
+        let mut game = SicBoGame::new(vec![], PayTable::macau()).with_roller(sixes).unwrap();
+        let mut rng = StdRng::seed_from_u64(40);
+        assert_eq!(game.roll_with(&mut rng), roll(6, 6, 6));
+        assert_eq!(
+            SicBoGame::new(vec![], PayTable::macau()).with_roller(Die::D20).err(),
+            Some(Error::WrongDice { sides: 20, expected: 6 })
+        );
+        assert_eq!(roll(200, 100, 1).total(), u8::MAX);
+    }
+}
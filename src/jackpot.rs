@@ -0,0 +1,292 @@
+//! Progressive jackpot pools.
+//!
+//! A `JackpotPool` takes a share of every wager fed to it, from slot machines or from table
+//! side bets, and pays out when a game awards it. Clones share the same pool, so one pool can
+//! be fed from several machines or threads at once. Each pool starts at its seed value and
+//! goes back to its reset value after a hit. With a must-hit-by ceiling, a hidden hit point is
+//! drawn uniformly between the reset value and the ceiling, and the wager that lifts the pool
+//! past it wins.
+
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+use std::time::SystemTime;
+
+use rand::Rng;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AwardReason {
+    /// a game paid the jackpot, e.g. a royal flush on a side bet
+    Won,
+    /// the pool reached its must-hit-by hit point
+    MustHitBy,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct JackpotAward {
+    /// sequence number within the pool, starting at 1
+    pub id: u64,
+    pub pool: String,
+    pub winner: String,
+    pub reason: AwardReason,
+    pub amount_cents: u64,
+    /// pool value before and after the award
+    pub pool_before_cents: u64,
+    pub pool_after_cents: u64,
+    pub wagers_since_last: u64,
+    pub contributed_since_last: u64,
+    pub awarded_at: SystemTime,
+}
+
+struct PoolState {
+    amount_cents: u64,
+    /// contributions smaller than a cent, in 1/10000ths of a cent
+    remainder: u64,
+    hit_point: Option<u64>,
+    wagers_since_last: u64,
+    contributed_since_last: u64,
+    total_contributed: u64,
+    history: Vec<JackpotAward>,
+}
+
+#[derive(Clone)]
+pub struct JackpotPool {
+    name: String,
+    reset_cents: u64,
+    /// share of each wager added to the pool, in basis points (1% = 100)
+    rate_basis_points: u32,
+    must_hit_by_cents: Option<u64>,
+    state: Arc<Mutex<PoolState>>,
+}
+
+impl JackpotPool {
+    // Returns None if the rate is over 100%.
+    pub fn new(name: impl Into<String>, seed_cents: u64, reset_cents: u64, rate_basis_points: u32) -> Option<Self> {
+        if rate_basis_points > 10_000 {
+            return None;
+        }
+        Some(Self {
+            name: name.into(),
+            reset_cents,
+            rate_basis_points,
+            must_hit_by_cents: None,
+            state: Arc::new(Mutex::new(PoolState {
+                amount_cents: seed_cents,
+                remainder: 0,
+                hit_point: None,
+                wagers_since_last: 0,
+                contributed_since_last: 0,
+                total_contributed: 0,
+                history: Vec::new(),
+            })),
+        })
+    }
+
+    // Returns None unless the ceiling is above both the reset value and the current pool.
+    pub fn with_must_hit_by(mut self, ceiling_cents: u64) -> Option<Self> {
+        if ceiling_cents <= self.reset_cents || ceiling_cents <= self.amount_cents() {
+            return None;
+        }
+        self.must_hit_by_cents = Some(ceiling_cents);
+        Some(self)
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn reset_cents(&self) -> u64 {
+        self.reset_cents
+    }
+
+    pub fn rate_basis_points(&self) -> u32 {
+        self.rate_basis_points
+    }
+
+    pub fn must_hit_by_cents(&self) -> Option<u64> {
+        self.must_hit_by_cents
+    }
+
+    pub fn amount_cents(&self) -> u64 {
+        self.lock().amount_cents
+    }
+
+    pub fn total_contributed(&self) -> u64 {
+        self.lock().total_contributed
+    }
+
+    // Every award so far, oldest first.
+    pub fn history(&self) -> Vec<JackpotAward> {
+        self.lock().history.clone()
+    }
+
+    // True if `other` is a handle to the same pool.
+    pub fn same_pool(&self, other: &JackpotPool) -> bool {
+        Arc::ptr_eq(&self.state, &other.state)
+    }
+
+    pub fn contribute(&self, source: &str, wager_cents: u64) -> Option<JackpotAward> {
+        let mut rng = rand::rng();
+        self.contribute_with(source, wager_cents, &mut rng)
+    }
+
+    // Add the pool's share of a wager. Returns the award if this wager hit a must-hit-by
+    // jackpot, in which case `source` is the winner.
+    pub fn contribute_with<R: Rng + ?Sized>(&self, source: &str, wager_cents: u64, rng: &mut R) -> Option<JackpotAward> {
+        let mut state = self.lock();
+        let scaled = state.remainder + wager_cents.saturating_mul(self.rate_basis_points as u64);
+        let cents = scaled / 10_000;
+        state.remainder = scaled % 10_000;
+        state.amount_cents += cents;
+        state.wagers_since_last += 1;
+        state.contributed_since_last += cents;
+        state.total_contributed += cents;
+
+        let ceiling = self.must_hit_by_cents?;
+        let hit_point = match state.hit_point {
+            Some(point) => point,
+            None => {
+                let low = self.reset_cents.max(state.amount_cents - cents).min(ceiling);
+                let point = rng.random_range(low..=ceiling);
+                state.hit_point = Some(point);
+                point
+            }
+        };
+        if state.amount_cents < hit_point {
+            return None;
+        }
+        // anything past the ceiling carries over into the next pool
+        let amount = state.amount_cents.min(ceiling);
+        Some(self.pay(&mut state, source, AwardReason::MustHitBy, amount))
+    }
+
+    // Pay the whole pool to `winner` and reset it.
+    pub fn award(&self, winner: &str) -> JackpotAward {
+        self.award_share(winner, 10_000)
+    }
+
+    // Pay a share of the pool in basis points, e.g. 1000 for the 10% a straight flush takes
+    // on a Caribbean-style progressive. The pool never drops below its reset value.
+    pub fn award_share(&self, winner: &str, basis_points: u32) -> JackpotAward {
+        let mut state = self.lock();
+        let amount = (state.amount_cents as u128 * basis_points.min(10_000) as u128 / 10_000) as u64;
+        self.pay(&mut state, winner, AwardReason::Won, amount)
+    }
+
+    fn pay(&self, state: &mut PoolState, winner: &str, reason: AwardReason, amount: u64) -> JackpotAward {
+        let before = state.amount_cents;
+        state.amount_cents = match reason {
+            AwardReason::Won => (before - amount).max(self.reset_cents),
+            AwardReason::MustHitBy => self.reset_cents + (before - amount),
+        };
+        let award = JackpotAward {
+            id: state.history.len() as u64 + 1,
+            pool: self.name.clone(),
+            winner: winner.to_string(),
+            reason,
+            amount_cents: amount,
+            pool_before_cents: before,
+            pool_after_cents: state.amount_cents,
+            wagers_since_last: state.wagers_since_last,
+            contributed_since_last: state.contributed_since_last,
+            awarded_at: SystemTime::now(),
+        };
+        state.hit_point = None;
+        state.wagers_since_last = 0;
+        state.contributed_since_last = 0;
+        state.history.push(award.clone());
+        award
+    }
+
+    // A panic while holding the lock cannot leave the pool half-updated, so a poisoned lock
+    // is still safe to use.
+    fn lock(&self) -> MutexGuard<'_, PoolState> {
+        self.state.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+    use rand::rngs::StdRng;
+
+    #[test]
+    fn test_validation() {
+        assert!(JackpotPool::new("mega", 100_000, 100_000, 10_001).is_none());
+        let pool = JackpotPool::new("mega", 100_000, 100_000, 100).unwrap();
+        assert!(pool.clone().with_must_hit_by(100_000).is_none());
+        assert!(pool.with_must_hit_by(200_000).is_some());
+    }
+
+    #[test]
+    fn test_contributions_keep_fractions() {
+        let pool = JackpotPool::new("mega", 1_000, 500, 150).unwrap();
+        // 1.5% of 25c is 0.375c; eight such wagers add exactly 3c
+        for _ in 0..8 {
+            assert!(pool.contribute("slot-1", 25).is_none());
+        }
+        assert_eq!(pool.amount_cents(), 1_003);
+        assert_eq!(pool.total_contributed(), 3);
+    }
+
+    #[test]
+    fn test_award_and_reset() {
+        let pool = JackpotPool::new("caribbean", 1_000_000, 500_000, 7_000).unwrap();
+        let table = pool.clone();
+        table.contribute("table-3", 100);
+        assert_eq!(pool.amount_cents(), 1_000_070);
+
+        // straight flush takes 10% of the pool
+        let award = table.award_share("seat 2", 1_000);
+        assert_eq!(award.amount_cents, 100_007);
+        assert_eq!(pool.amount_cents(), 900_063);
+
+        // royal flush takes it all and the pool resets
+        let award = pool.award("seat 5");
+        assert_eq!(award.amount_cents, 900_063);
+        assert_eq!(award.pool_after_cents, 500_000);
+        assert_eq!(award.reason, AwardReason::Won);
+        assert_eq!(pool.amount_cents(), 500_000);
+        let history = pool.history();
+        assert_eq!(history.iter().map(|a| a.id).collect::<Vec<_>>(), vec![1, 2]);
+        assert_eq!(history[0].wagers_since_last, 1);
+        assert_eq!(history[1].wagers_since_last, 0);
+    }
+
+    #[test]
+    fn test_must_hit_by() {
+        let pool = JackpotPool::new("mystery", 10_000, 10_000, 1_000).unwrap().with_must_hit_by(20_000).unwrap();
+        let mut rng = StdRng::seed_from_u64(36);
+        let mut awards = Vec::new();
+        for i in 0..100_000 {
+            if let Some(award) = pool.contribute_with(&format!("slot-{}", i % 4), 100, &mut rng) {
+                awards.push(award);
+            }
+        }
+        assert!(awards.len() > 5);
+        for award in &awards {
+            assert_eq!(award.reason, AwardReason::MustHitBy);
+            assert!((10_000..=20_000).contains(&award.amount_cents));
+        }
+        assert!(pool.amount_cents() < 20_000);
+        // every contribution and every reset is either paid out or still in the pool
+        let paid: u64 = awards.iter().map(|a| a.amount_cents).sum();
+        let seeded = 10_000 * (awards.len() as u64 + 1);
+        assert_eq!(paid + pool.amount_cents(), seeded + pool.total_contributed());
+    }
+
+    #[test]
+    fn test_shared_across_threads() {
+        let pool = JackpotPool::new("linked", 0, 0, 100).unwrap();
+        std::thread::scope(|s| {
+            for t in 0..4 {
+                let pool = pool.clone();
+                s.spawn(move || {
+                    for _ in 0..1_000 {
+                        pool.contribute(&format!("table-{t}"), 100);
+                    }
+                });
+            }
+        });
+        assert_eq!(pool.amount_cents(), 4_000);
+    }
+}
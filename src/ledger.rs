@@ -0,0 +1,213 @@
+//! Player transaction ledger.
+//!
+//! Every chip that enters or leaves a `Player`'s wallet is recorded as a `Transaction`: the
+//! chips moved, their signed value, why they moved and, for table play, which game, table and
+//! round it was. Entries are grouped into sessions, and the balance after each entry can be
+//! rebuilt from the amounts alone, which is what a dispute or audit works from.
+
+use std::collections::HashMap;
+use std::time::SystemTime;
+
+use serde::{Deserialize, Serialize};
+
+use crate::bet::Chip;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Reason {
+    /// chips bought at the cage or the table
+    BuyIn,
+    CashOut,
+    /// chips put on a bet
+    Wager,
+    /// a winning bet's stake and winnings
+    Win,
+    /// a stake handed back on a push
+    Push,
+    /// a fee taken separately from a win
+    Commission,
+    /// chips exchanged for other denominations of the same value
+    ColourUp,
+}
+
+// Where a table transaction happened.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct Source {
+    pub game: String,
+    pub table: String,
+    pub round: u64,
+}
+
+impl Source {
+    pub fn new(game: impl Into<String>, table: impl Into<String>, round: u64) -> Self {
+        Self { game: game.into(), table: table.into(), round }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Transaction {
+    /// sequence number within the ledger, starting at 1
+    pub id: u64,
+    pub session: u64,
+    pub reason: Reason,
+    /// positive into the wallet, negative out of it
+    pub amount_cents: i64,
+    #[serde(with = "crate::bet::chip_map")]
+    pub chips: HashMap<Chip, u32>,
+    /// None for movements away from a table, such as a buy-in at the cage
+    pub source: Option<Source>,
+    /// wallet value once this entry was applied
+    pub balance_after_cents: u64,
+    pub at: SystemTime,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Ledger {
+    entries: Vec<Transaction>,
+    session: u64,
+}
+
+impl Ledger {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn entries(&self) -> &[Transaction] {
+        &self.entries
+    }
+
+    pub fn current_session(&self) -> u64 {
+        self.session
+    }
+
+    // Start a new session; later entries are tagged with its number.
+    pub fn start_session(&mut self) -> u64 {
+        self.session += 1;
+        self.session
+    }
+
+    pub fn session(&self, session: u64) -> impl Iterator<Item = &Transaction> + '_ {
+        self.entries.iter().filter(move |tx| tx.session == session)
+    }
+
+    // Net win or loss over a session, counting only wagers and what came back from them.
+    pub fn session_result_cents(&self, session: u64) -> i64 {
+        self.session(session)
+            .filter(|tx| matches!(tx.reason, Reason::Wager | Reason::Win | Reason::Push | Reason::Commission))
+            .map(|tx| tx.amount_cents)
+            .sum()
+    }
+
+    pub fn by_source<'a>(&'a self, game: &'a str, table: &'a str) -> impl Iterator<Item = &'a Transaction> + 'a {
+        self.entries
+            .iter()
+            .filter(move |tx| tx.source.as_ref().is_some_and(|s| s.game == game && s.table == table))
+    }
+
+    // Record a movement that has just been applied to a wallet now worth `balance_after_cents`.
+    pub fn record(
+        &mut self,
+        reason: Reason,
+        amount_cents: i64,
+        chips: HashMap<Chip, u32>,
+        source: Option<Source>,
+        balance_after_cents: u64,
+    ) -> &Transaction {
+        self.entries.push(Transaction {
+            id: self.entries.len() as u64 + 1,
+            session: self.session,
+            reason,
+            amount_cents,
+            chips,
+            source,
+            balance_after_cents,
+            at: SystemTime::now(),
+        });
+        self.entries.last().expect("just pushed")
+    }
+
+    // The balance after every entry, rebuilt from the amounts alone.
+    pub fn running_balances(&self) -> Vec<(u64, i64)> {
+        self.entries
+            .iter()
+            .scan(0i64, |balance, tx| {
+                *balance += tx.amount_cents;
+                Some((tx.id, *balance))
+            })
+            .collect()
+    }
+
+    // Checks the rebuilt balances against the ones recorded at the time. Returns the id of the
+    // first entry that disagrees.
+    pub fn verify(&self) -> Result<(), u64> {
+        for (tx, (_, balance)) in self.entries.iter().zip(self.running_balances()) {
+            if balance != tx.balance_after_cents as i64 {
+                return Err(tx.id);
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bet::ChipSet;
+    use crate::player::Player;
+
+    #[test]
+    fn test_ledger_records_every_movement() {
+        let set = ChipSet::standard();
+        let (red, white) = (set.chip(500).unwrap().clone(), set.chip(100).unwrap().clone());
+        let mut player = Player::new("Alice");
+        let session = player.start_session().unwrap();
+        player.deposit(red.clone(), 4).unwrap();
+        let table = Source::new("sicbo", "3", 17);
+        player.debit(&HashMap::from([(red.clone(), 1)]), Reason::Wager, Some(table.clone())).unwrap();
+        player.credit(HashMap::from([(red.clone(), 2)]), Reason::Win, Some(table.clone()));
+        assert!(player.debit(&HashMap::from([(white.clone(), 1)]), Reason::Wager, None).is_err());
+        player.withdraw(&red, 5).unwrap();
+
+        let ledger = player.ledger();
+        assert_eq!(ledger.entries().len(), 4);
+        let reasons: Vec<Reason> = ledger.entries().iter().map(|tx| tx.reason).collect();
+        assert_eq!(reasons, [Reason::BuyIn, Reason::Wager, Reason::Win, Reason::CashOut]);
+        assert_eq!(ledger.entries()[1].amount_cents, -500);
+        assert_eq!(ledger.entries()[2].source.as_ref(), Some(&table));
+        assert_eq!(ledger.session_result_cents(session), 500);
+        assert_eq!(ledger.by_source("sicbo", "3").count(), 2);
+        assert_eq!(
+            ledger.running_balances().iter().map(|&(_, b)| b).collect::<Vec<_>>(),
+            [2_000, 1_500, 2_500, 0]
+        );
+        assert_eq!(ledger.verify(), Ok(()));
+    }
+
+    #[test]
+    fn test_sessions_and_tampering() {
+        let mut player = Player::new("Bob");
+        let chip = Chip::new("white", "$1", 100);
+        player.deposit(chip.clone(), 10).unwrap();
+        let second = player.start_session().unwrap();
+        player.debit(&HashMap::from([(chip.clone(), 3)]), Reason::Wager, None).unwrap();
+        assert_eq!(player.ledger().session(0).count(), 1);
+        assert_eq!(player.ledger().session(second).count(), 1);
+        assert_eq!(player.ledger().session_result_cents(second), -300);
+
+        let mut ledger = player.ledger().clone();
+        ledger.entries[0].amount_cents = 900;
+        assert_eq!(ledger.verify(), Err(1));
+    }
+
+    #[test]
+    fn test_colour_up_is_recorded() {
+        let set = ChipSet::standard();
+        let mut player = Player::new("Carol");
+        player.deposit(set.chip(100).unwrap().clone(), 25).unwrap();
+        set.colour_up(&mut player);
+        let ledger = player.ledger();
+        let colour_ups: Vec<&Transaction> = ledger.entries().iter().filter(|tx| tx.reason == Reason::ColourUp).collect();
+        assert_eq!(colour_ups.len(), 2);
+        assert_eq!(colour_ups.iter().map(|tx| tx.amount_cents).sum::<i64>(), 0);
+        assert_eq!(ledger.verify(), Ok(()));
+    }
+}
@@ -4,4 +4,15 @@ pub mod roulette;
 pub mod slots;
 pub mod games;
 pub mod player;
-pub mod bet;
\ No newline at end of file
+pub mod bet;
+pub mod stats;
+pub mod jackpot;
+pub mod odds;
+pub mod ledger;
+pub mod session;
+pub mod limits;
+pub mod error;
+pub mod snapshot;
+pub mod simulation;
+
+pub use error::{Error, Result};
\ No newline at end of file
@@ -0,0 +1,533 @@
+//! Responsible-gaming limits.
+//!
+//! A `Player` carries `Limits` that are checked on every buy-in, every wager and every new
+//! session, so no front end can skip them. Day limits cover the last 24 hours of the
+//! player's ledger and session limits the current ledger session, timed from its first
+//! wager. Losses count every chip wagered that has not come back, so a bet still on the
+//! layout counts against the limit until it is settled. A session that ran out or hit its
+//! loss limit starts the cool-off when the next one opens. Tighter limits apply at once;
+//! looser ones wait out a cool-off, and neither can lift a self-exclusion or a cool-off.
+//! Money owed to the player (wins, pushes and cash-outs) is never refused.
+
+use std::fmt;
+use std::time::{Duration, SystemTime};
+
+use serde::{Deserialize, Serialize};
+
+use crate::ledger::{Ledger, Reason, Transaction};
+use crate::session::SessionStats;
+
+const DAY: Duration = Duration::from_secs(24 * 60 * 60);
+
+// How long `Limits::update` holds back a limit that loosens the current one.
+pub const LOOSENING_DELAY: Duration = DAY;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Period {
+    Session,
+    /// the last 24 hours
+    Day,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Cap {
+    pub cents: u64,
+    pub period: Period,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SelfExclusion {
+    Until(SystemTime),
+    Indefinite,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Refusal {
+    SelfExcluded(SelfExclusion),
+    CoolingOff { until: SystemTime },
+    SessionTimeLimit { played: Duration, limit: Duration },
+    DepositCap { period: Period, limit_cents: u64, deposited_cents: u64 },
+    LossLimit { period: Period, limit_cents: u64, lost_cents: u64 },
+    MaxWager { limit_cents: u64, wager_cents: u64 },
+}
+
+impl fmt::Display for Refusal {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Refusal::SelfExcluded(SelfExclusion::Indefinite) => write!(f, "player is self-excluded"),
+            Refusal::SelfExcluded(SelfExclusion::Until(until)) => write!(f, "player is self-excluded until {until:?}"),
+            Refusal::CoolingOff { until } => write!(f, "player is cooling off until {until:?}"),
+            Refusal::SessionTimeLimit { played, limit } => {
+                write!(f, "session has run {}s, over the {}s limit", played.as_secs(), limit.as_secs())
+            }
+            Refusal::DepositCap { period, limit_cents, deposited_cents } => write!(
+                f,
+                "deposit would exceed the {period:?} cap of {limit_cents} cents ({deposited_cents} deposited)"
+            ),
+            Refusal::LossLimit { period, limit_cents, lost_cents } => {
+                write!(f, "wager could exceed the {period:?} loss limit of {limit_cents} cents ({lost_cents} lost)")
+            }
+            Refusal::MaxWager { limit_cents, wager_cents } => {
+                write!(f, "wager of {wager_cents} cents is over the {limit_cents} cent maximum")
+            }
+        }
+    }
+}
+
+impl std::error::Error for Refusal {}
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(try_from = "LimitsRaw")]
+pub struct Limits {
+    pub deposit_cap: Option<Cap>,
+    pub loss_limit: Option<Cap>,
+    pub max_wager_cents: Option<u64>,
+    pub max_session: Option<Duration>,
+    /// enforced break after a session runs past `max_session`
+    pub cool_off: Duration,
+    pub self_exclusion: Option<SelfExclusion>,
+    /// set when a session runs out or is left over its loss limit
+    cooling_off_until: Option<SystemTime>,
+    /// looser limits asked for, and when they take effect
+    pending: Option<(Box<Limits>, SystemTime)>,
+}
+
+// Limits as saved. Read back only if a cool-off can still be timed from now and pending
+// limits do not have pending limits of their own.
+#[derive(Deserialize)]
+struct LimitsRaw {
+    deposit_cap: Option<Cap>,
+    loss_limit: Option<Cap>,
+    max_wager_cents: Option<u64>,
+    max_session: Option<Duration>,
+    cool_off: Duration,
+    self_exclusion: Option<SelfExclusion>,
+    cooling_off_until: Option<SystemTime>,
+    pending: Option<(Box<Limits>, SystemTime)>,
+}
+
+impl TryFrom<LimitsRaw> for Limits {
+    type Error = String;
+
+    fn try_from(raw: LimitsRaw) -> Result<Self, String> {
+        let pending_cool_off = raw.pending.as_ref().map_or(Duration::ZERO, |(limits, _)| limits.cool_off);
+        if SystemTime::now().checked_add(raw.cool_off.max(pending_cool_off)).is_none() {
+            return Err("cool-off is too long to time".to_string());
+        }
+        if raw.pending.as_ref().is_some_and(|(limits, _)| limits.pending.is_some()) {
+            return Err("pending limits have pending limits of their own".to_string());
+        }
+        Ok(Self {
+            deposit_cap: raw.deposit_cap,
+            loss_limit: raw.loss_limit,
+            max_wager_cents: raw.max_wager_cents,
+            max_session: raw.max_session,
+            cool_off: raw.cool_off,
+            self_exclusion: raw.self_exclusion,
+            cooling_off_until: raw.cooling_off_until,
+            pending: raw.pending,
+        })
+    }
+}
+
+impl Limits {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_deposit_cap(mut self, cents: u64, period: Period) -> Self {
+        self.deposit_cap = Some(Cap { cents, period });
+        self
+    }
+
+    pub fn with_loss_limit(mut self, cents: u64, period: Period) -> Self {
+        self.loss_limit = Some(Cap { cents, period });
+        self
+    }
+
+    pub fn with_max_wager(mut self, cents: u64) -> Self {
+        self.max_wager_cents = Some(cents);
+        self
+    }
+
+    pub fn with_max_session(mut self, max_session: Duration, cool_off: Duration) -> Self {
+        self.max_session = Some(max_session);
+        self.cool_off = cool_off;
+        self
+    }
+
+    pub fn with_self_exclusion(mut self, exclusion: SelfExclusion) -> Self {
+        self.self_exclusion = Some(exclusion);
+        self
+    }
+
+    pub fn cooling_off_until(&self) -> Option<SystemTime> {
+        self.cooling_off_until
+    }
+
+    // Limits asked for by `update` that loosen the current ones, and when they take effect.
+    pub fn pending(&self) -> Option<(&Limits, SystemTime)> {
+        self.pending.as_ref().map(|(limits, from)| (limits.as_ref(), *from))
+    }
+
+    // Move to `limits`. Each limit that is at least as strict as the current one applies
+    // now; the rest wait `LOOSENING_DELAY`, whatever the cool-off. A self-exclusion only ever
+    // lengthens, and a cool-off under way is kept.
+    pub fn update(&mut self, limits: Limits, now: SystemTime) {
+        self.apply_pending(now);
+        if stricter_cap(limits.deposit_cap, self.deposit_cap) {
+            self.deposit_cap = limits.deposit_cap;
+        }
+        if stricter_cap(limits.loss_limit, self.loss_limit) {
+            self.loss_limit = limits.loss_limit;
+        }
+        if stricter(limits.max_wager_cents, self.max_wager_cents) {
+            self.max_wager_cents = limits.max_wager_cents;
+        }
+        if stricter(limits.max_session, self.max_session) {
+            self.max_session = limits.max_session;
+        }
+        self.cool_off = self.cool_off.max(limits.cool_off);
+        if let Some(exclusion) = limits.self_exclusion {
+            self.exclude(exclusion);
+        }
+        let loosened = limits.deposit_cap != self.deposit_cap
+            || limits.loss_limit != self.loss_limit
+            || limits.max_wager_cents != self.max_wager_cents
+            || limits.max_session != self.max_session
+            || limits.cool_off != self.cool_off;
+        self.pending = loosened.then(|| (Box::new(limits), now + LOOSENING_DELAY));
+        self.apply_pending(now);
+    }
+
+    // Self-exclude, keeping whichever of this and any current exclusion lasts longer.
+    pub fn exclude(&mut self, exclusion: SelfExclusion) {
+        self.self_exclusion = match (self.self_exclusion, exclusion) {
+            (Some(SelfExclusion::Indefinite), _) | (_, SelfExclusion::Indefinite) => Some(SelfExclusion::Indefinite),
+            (Some(SelfExclusion::Until(current)), SelfExclusion::Until(until)) => {
+                Some(SelfExclusion::Until(current.max(until)))
+            }
+            (None, exclusion) => Some(exclusion),
+        };
+    }
+
+    // Refuses a new session while the player is excluded or cooling off. If the session
+    // being closed ran out of time or reached its loss limit, the cool-off starts now.
+    pub fn check_new_session(
+        &mut self,
+        ledger: &Ledger,
+        session: &SessionStats,
+        now: SystemTime,
+    ) -> Result<(), Refusal> {
+        self.apply_pending(now);
+        self.check_excluded(now)?;
+        let ran_out = self.max_session.is_some_and(|limit| played(session, now) >= limit);
+        let lost_out = self
+            .loss_limit
+            .is_some_and(|cap| cap.period == Period::Session && lost_cents(ledger, cap.period, now) >= cap.cents);
+        if ran_out || lost_out {
+            self.cooling_off_until = Some(now + self.cool_off);
+        }
+        Ok(())
+    }
+
+    // Refuses any buy-in that would take the player's deposits over the cap.
+    pub fn check_deposit(&mut self, ledger: &Ledger, amount_cents: u64, now: SystemTime) -> Result<(), Refusal> {
+        self.apply_pending(now);
+        self.check_excluded(now)?;
+        if let Some(cap) = self.deposit_cap {
+            let deposited_cents = in_period(ledger, cap.period, now)
+                .filter(|tx| tx.reason == Reason::BuyIn)
+                .map(|tx| tx.amount_cents.unsigned_abs())
+                .sum::<u64>();
+            if deposited_cents + amount_cents > cap.cents {
+                return Err(Refusal::DepositCap { period: cap.period, limit_cents: cap.cents, deposited_cents });
+            }
+        }
+        Ok(())
+    }
+
+    // Refuses a wager that is too large, that could take losses past the limit, or that
+    // comes after the session has run out. Running out starts the cool-off.
+    pub fn check_wager(
+        &mut self,
+        ledger: &Ledger,
+        session: &SessionStats,
+        amount_cents: u64,
+        now: SystemTime,
+    ) -> Result<(), Refusal> {
+        self.apply_pending(now);
+        self.check_excluded(now)?;
+        if let Some(limit) = self.max_session {
+            let played = played(session, now);
+            if played >= limit {
+                self.cooling_off_until = Some(now + self.cool_off);
+                return Err(Refusal::SessionTimeLimit { played, limit });
+            }
+        }
+        if let Some(limit_cents) = self.max_wager_cents
+            && amount_cents > limit_cents
+        {
+            return Err(Refusal::MaxWager { limit_cents, wager_cents: amount_cents });
+        }
+        if let Some(cap) = self.loss_limit {
+            let lost_cents = lost_cents(ledger, cap.period, now);
+            if lost_cents + amount_cents > cap.cents {
+                return Err(Refusal::LossLimit { period: cap.period, limit_cents: cap.cents, lost_cents });
+            }
+        }
+        Ok(())
+    }
+
+    fn apply_pending(&mut self, now: SystemTime) {
+        let Some((limits, _)) = self.pending.take_if(|(_, from)| *from <= now) else {
+            return;
+        };
+        self.deposit_cap = limits.deposit_cap;
+        self.loss_limit = limits.loss_limit;
+        self.max_wager_cents = limits.max_wager_cents;
+        self.max_session = limits.max_session;
+        self.cool_off = limits.cool_off;
+    }
+
+    fn check_excluded(&self, now: SystemTime) -> Result<(), Refusal> {
+        match self.self_exclusion {
+            Some(SelfExclusion::Indefinite) => return Err(Refusal::SelfExcluded(SelfExclusion::Indefinite)),
+            Some(SelfExclusion::Until(until)) if now < until => {
+                return Err(Refusal::SelfExcluded(SelfExclusion::Until(until)));
+            }
+            _ => {}
+        }
+        match self.cooling_off_until {
+            Some(until) if now < until => Err(Refusal::CoolingOff { until }),
+            _ => Ok(()),
+        }
+    }
+}
+
+// Whether `new` is at least as strict as `current`; None is no limit at all.
+fn stricter<T: PartialOrd>(new: Option<T>, current: Option<T>) -> bool {
+    match (new, current) {
+        (Some(new), Some(current)) => new <= current,
+        (_, None) => true,
+        (None, Some(_)) => false,
+    }
+}
+
+// Caps over different periods cannot be compared, so a change of period counts as looser.
+fn stricter_cap(new: Option<Cap>, current: Option<Cap>) -> bool {
+    match (new, current) {
+        (Some(new), Some(current)) => new.period == current.period && new.cents <= current.cents,
+        (new, current) => stricter(new.map(|c| c.cents), current.map(|c| c.cents)),
+    }
+}
+
+// Time since the session's first wager; nothing before then counts.
+fn played(session: &SessionStats, now: SystemTime) -> Duration {
+    session.first_wager.map_or(Duration::ZERO, |first| now.duration_since(first).unwrap_or_default())
+}
+
+// Chips wagered over the period that have not come back.
+fn lost_cents(ledger: &Ledger, period: Period, now: SystemTime) -> u64 {
+    let net: i64 = in_period(ledger, period, now)
+        .filter(|tx| matches!(tx.reason, Reason::Wager | Reason::Win | Reason::Push | Reason::Commission))
+        .map(|tx| tx.amount_cents)
+        .sum();
+    (-net).max(0) as u64
+}
+
+fn in_period(ledger: &Ledger, period: Period, now: SystemTime) -> impl Iterator<Item = &Transaction> + '_ {
+    let session = ledger.current_session();
+    let since = now.checked_sub(DAY).unwrap_or(SystemTime::UNIX_EPOCH);
+    ledger.entries().iter().filter(move |tx| match period {
+        Period::Session => tx.session == session,
+        Period::Day => tx.at >= since,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::*;
+    use crate::bet::{Bet, ChipSet};
+    use crate::games::sicbo::{PayTable, SicBoBet, SicBoGame};
+    use crate::odds::{Cashier, Resolution};
+    use crate::error::Error;
+    use crate::player::Player;
+
+    fn red() -> crate::bet::Chip {
+        ChipSet::standard().chip(500).unwrap().clone()
+    }
+
+    #[test]
+    fn test_deposit_cap() {
+        let mut player = Player::new("Alice").with_limits(Limits::new().with_deposit_cap(5_000, Period::Day));
+        player.deposit(red(), 8).unwrap();
+        let refused = player.deposit(red(), 3);
+        assert_eq!(
+            refused,
+            Err(Error::Refused(Refusal::DepositCap { period: Period::Day, limit_cents: 5_000, deposited_cents: 4_000 }))
+        );
+        assert_eq!(player.total_cents(), 4_000);
+        player.deposit(red(), 2).unwrap();
+    }
+
+    #[test]
+    fn test_loss_limit_and_max_wager() {
+        let limits = Limits::new().with_loss_limit(2_000, Period::Session).with_max_wager(1_500);
+        let mut player = Player::new("Bob").with_limits(limits);
+        player.deposit(red(), 20).unwrap();
+        let mut cashier = Cashier::default();
+        let wager = |player: &mut Player, count| {
+            let mut bet = Bet::new();
+            bet.escrow(player, HashMap::from([(red(), count)]), None).map(|_| bet)
+        };
+        assert_eq!(
+            wager(&mut player, 4).err(),
+            Some(Error::Refused(Refusal::MaxWager { limit_cents: 1_500, wager_cents: 2_000 }))
+        );
+        let bet = wager(&mut player, 3).unwrap();
+        bet.settle(&mut player, &mut cashier, Resolution::Lose, None).unwrap();
+        // $15 lost, so only $5 more can be risked this session
+        assert!(matches!(wager(&mut player, 2), Err(Error::Refused(Refusal::LossLimit { lost_cents: 1_500, .. }))));
+        wager(&mut player, 1).unwrap();
+        // a fresh session starts a fresh limit
+        player.start_session().unwrap();
+        wager(&mut player, 3).unwrap();
+    }
+
+    #[test]
+    fn test_session_time_and_cool_off() {
+        let limits = Limits::new().with_max_session(Duration::ZERO, Duration::from_secs(3_600));
+        let mut player = Player::new("Carol").with_limits(limits);
+        player.deposit(red(), 4).unwrap();
+        let mut game = SicBoGame::new(vec![player], PayTable::macau());
+        let refused = game.place_bet(0, SicBoBet::Big, HashMap::from([(red(), 1)]));
+        assert!(matches!(refused, Err(Error::Refused(Refusal::SessionTimeLimit { .. }))));
+        let player = &game.players()[0].player;
+        assert!(player.limits().cooling_off_until().is_some());
+        assert_eq!(player.total_cents(), 2_000);
+    }
+
+    #[test]
+    fn test_self_exclusion() {
+        let mut player = Player::new("Dave");
+        player.deposit(red(), 2).unwrap();
+        player.self_exclude(SelfExclusion::Indefinite);
+        assert_eq!(player.deposit(red(), 1), Err(Error::Refused(Refusal::SelfExcluded(SelfExclusion::Indefinite))));
+        assert!(Bet::new().escrow(&mut player, HashMap::from([(red(), 1)]), None).is_err());
+        // cashing out is still allowed
+        player.withdraw(&red(), 2).unwrap();
+
+        let past = SystemTime::now() - Duration::from_secs(60);
+        let mut player = Player::new("Erin").with_limits(Limits::new().with_self_exclusion(SelfExclusion::Until(past)));
+        player.deposit(red(), 1).unwrap();
+    }
+
+    #[test]
+    fn test_new_session_refused_while_excluded_or_cooling_off() {
+        let mut player = Player::new("Fay");
+        player.self_exclude(SelfExclusion::Indefinite);
+        assert_eq!(player.start_session(), Err(Error::Refused(Refusal::SelfExcluded(SelfExclusion::Indefinite))));
+
+        let limits = Limits::new().with_max_session(Duration::ZERO, Duration::from_secs(3_600));
+        let mut player = Player::new("Gus").with_limits(limits);
+        player.deposit(red(), 1).unwrap();
+        assert!(Bet::new().escrow(&mut player, HashMap::from([(red(), 1)]), None).is_err());
+        assert!(matches!(player.start_session(), Err(Error::Refused(Refusal::CoolingOff { .. }))));
+        assert_eq!(player.sessions().count(), 1);
+    }
+
+    #[test]
+    fn test_leaving_a_lost_out_session_starts_the_cool_off() {
+        let limits = Limits::new()
+            .with_loss_limit(1_000, Period::Session)
+            .with_max_session(Duration::from_secs(36_000), Duration::from_secs(3_600));
+        let mut player = Player::new("Hal").with_limits(limits);
+        player.deposit(red(), 4).unwrap();
+        let mut bet = Bet::new();
+        bet.escrow(&mut player, HashMap::from([(red(), 2)]), None).unwrap();
+        bet.settle(&mut player, &mut Cashier::default(), Resolution::Lose, None).unwrap();
+
+        // a new session would otherwise reset the loss limit
+        player.start_session().unwrap();
+        assert!(player.limits().cooling_off_until().is_some());
+        assert!(matches!(
+            Bet::new().escrow(&mut player, HashMap::from([(red(), 1)]), None),
+            Err(Error::Refused(Refusal::CoolingOff { .. }))
+        ));
+        assert!(matches!(player.start_session(), Err(Error::Refused(Refusal::CoolingOff { .. }))));
+    }
+
+    #[test]
+    fn test_leaving_a_timed_out_session_starts_the_cool_off() {
+        let start = SystemTime::UNIX_EPOCH + DAY;
+        let mut limits = Limits::new().with_max_session(Duration::from_secs(60), Duration::from_secs(3_600));
+        let mut session = SessionStats::new(0);
+        session.first_wager = Some(start);
+        let left = start + Duration::from_secs(120);
+        limits.check_new_session(&Ledger::new(), &session, left).unwrap();
+        assert_eq!(limits.cooling_off_until(), Some(left + Duration::from_secs(3_600)));
+        let refused = limits.check_new_session(&Ledger::new(), &SessionStats::new(1), left + Duration::from_secs(60));
+        assert!(matches!(refused, Err(Refusal::CoolingOff { .. })));
+    }
+
+    #[test]
+    fn test_session_time_runs_from_the_first_wager() {
+        let mut limits = Limits::new().with_max_session(Duration::from_secs(60), Duration::from_secs(3_600));
+        let session = SessionStats::new(0);
+        let later = session.started + Duration::from_secs(7_200);
+        limits.check_wager(&Ledger::new(), &session, 500, later).unwrap();
+        assert_eq!(limits.cooling_off_until(), None);
+    }
+
+    #[test]
+    fn test_set_limits_keeps_the_exclusion_and_cool_off() {
+        let mut player = Player::new("Ivy").with_limits(Limits::new().with_max_session(Duration::ZERO, DAY));
+        player.deposit(red(), 1).unwrap();
+        assert!(Bet::new().escrow(&mut player, HashMap::from([(red(), 1)]), None).is_err());
+        player.self_exclude(SelfExclusion::Until(SystemTime::now() + DAY));
+        player.self_exclude(SelfExclusion::Until(SystemTime::now()));
+
+        player.set_limits(Limits::new());
+        let limits = player.limits();
+        assert!(matches!(limits.self_exclusion, Some(SelfExclusion::Until(until)) if until > SystemTime::now()));
+        assert!(limits.cooling_off_until().is_some_and(|until| until > SystemTime::now()));
+        assert!(player.start_session().is_err());
+    }
+
+    #[test]
+    fn test_tighter_limits_apply_now_and_looser_after_a_day() {
+        let now = SystemTime::UNIX_EPOCH + DAY;
+        let cool_off = Duration::from_secs(3_600);
+        let mut limits = Limits::new().with_max_wager(1_000).with_max_session(Duration::from_secs(7_200), cool_off);
+        limits.update(limits.clone().with_max_wager(500), now);
+        assert_eq!((limits.max_wager_cents, limits.pending()), (Some(500), None));
+
+        limits.update(limits.clone().with_max_wager(5_000), now);
+        assert_eq!(limits.max_wager_cents, Some(500));
+        assert_eq!(
+            limits.pending().map(|(pending, from)| (pending.max_wager_cents, from)),
+            Some((Some(5_000), now + LOOSENING_DELAY))
+        );
+        let session = SessionStats::new(0);
+        let refused = limits.check_wager(&Ledger::new(), &session, 2_000, now + cool_off);
+        assert_eq!(refused, Err(Refusal::MaxWager { limit_cents: 500, wager_cents: 2_000 }));
+        limits.check_wager(&Ledger::new(), &session, 2_000, now + LOOSENING_DELAY).unwrap();
+        assert_eq!((limits.max_wager_cents, limits.pending()), (Some(5_000), None));
+    }
+
+    #[test]
+    fn test_raising_a_deposit_cap_waits_without_a_cool_off() {
+        let now = SystemTime::UNIX_EPOCH + DAY;
+        let mut limits = Limits::new().with_deposit_cap(5_000, Period::Day);
+        limits.update(Limits::new().with_deposit_cap(50_000, Period::Day), now);
+        assert_eq!(limits.deposit_cap.map(|cap| cap.cents), Some(5_000));
+        assert_eq!(limits.pending().map(|(_, from)| from), Some(now + LOOSENING_DELAY));
+        let refused = limits.check_deposit(&Ledger::new(), 10_000, now + Duration::from_secs(3_600));
+        assert!(matches!(refused, Err(Refusal::DepositCap { limit_cents: 5_000, .. })));
+        limits.check_deposit(&Ledger::new(), 10_000, now + LOOSENING_DELAY).unwrap();
+        assert_eq!(limits.deposit_cap.map(|cap| cap.cents), Some(50_000));
+    }
+}
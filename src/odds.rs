@@ -0,0 +1,501 @@
+//! Exact payout odds and breakage.
+//!
+//! `Odds` are kept as a reduced ratio of winnings to stake, so 3:2 on a $25 bet is exactly
+//! $37.50 and 0.95:1 on $5 is exactly $4.75. Whatever cannot be paid is breakage, and a
+//! `Cashier` settles each bet under one of three policies: round down to what the chip set
+//! can pay, pay to the cent with coins, or carry the unpaid part over to the player's next
+//! win. Every payout records its breakage, so the exact winnings always reconcile with
+//! what the player was paid plus what the house kept or still owes.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::str::FromStr;
+
+use num_rational::Ratio;
+use num_traits::Zero;
+use serde::{Deserialize, Serialize};
+
+use crate::bet::{Chip, ChipSet};
+use crate::error::Error;
+use crate::ledger::{Reason, Source};
+use crate::player::Player;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(try_from = "OddsRaw")]
+pub struct Odds {
+    /// winnings per unit staked, always reduced
+    ratio: Ratio<u64>,
+}
+
+// Odds as saved. Read back only if still reduced, so equal odds stay equal.
+#[derive(Deserialize)]
+struct OddsRaw {
+    ratio: Ratio<u64>,
+}
+
+impl TryFrom<OddsRaw> for Odds {
+    type Error = String;
+
+    fn try_from(raw: OddsRaw) -> Result<Self, String> {
+        let (numer, denom) = (*raw.ratio.numer(), *raw.ratio.denom());
+        match Odds::new(numer, denom) {
+            Some(odds) if odds.winnings_per_stake() == (numer, denom) => Ok(odds),
+            _ => Err(format!("odds of {numer}:{denom} are not a reduced ratio")),
+        }
+    }
+}
+
+impl Odds {
+    // `winnings:stake`, e.g. `Odds::new(3, 2)` for a blackjack. Returns None if stake is 0.
+    pub fn new(winnings: u64, stake: u64) -> Option<Self> {
+        (stake != 0).then(|| Self { ratio: Ratio::new(winnings, stake) })
+    }
+
+    pub fn to_one(winnings: u64) -> Self {
+        Self { ratio: Ratio::from_integer(winnings) }
+    }
+
+    pub fn even() -> Self {
+        Self::to_one(1)
+    }
+
+    pub fn winnings_per_stake(&self) -> (u64, u64) {
+        (*self.ratio.numer(), *self.ratio.denom())
+    }
+
+    pub fn to_f64(&self) -> f64 {
+        let (numer, denom) = self.winnings_per_stake();
+        numer as f64 / denom as f64
+    }
+
+    pub fn is_whole(&self) -> bool {
+        self.ratio.is_integer()
+    }
+
+    // These odds less `other`, e.g. even money less a 1:20 commission is 19:20. Returns None
+    // if `other` is larger.
+    pub fn less(&self, other: Odds) -> Option<Odds> {
+        (self.ratio >= other.ratio).then(|| Self { ratio: self.ratio - other.ratio })
+    }
+
+    // Exact winnings on `stake_cents`, in cents. Fails if they do not fit in a u64.
+    pub fn winnings(&self, stake_cents: u64) -> Result<Ratio<u64>, Error> {
+        let (numer, denom) = self.winnings_per_stake();
+        let product = stake_cents as u128 * numer as u128;
+        let whole = u64::try_from(product / denom as u128)
+            .map_err(|_| Error::PayoutOverflow { stake_cents, odds: *self })?;
+        Ok(Ratio::from_integer(whole) + Ratio::new((product % denom as u128) as u64, denom))
+    }
+}
+
+impl fmt::Display for Odds {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}", self.ratio.numer(), self.ratio.denom())
+    }
+}
+
+impl FromStr for Odds {
+    type Err = String;
+
+    // Accepts `3:2`, `6 to 5` and decimals such as `0.95:1`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (winnings, stake) = s
+            .split_once(':')
+            .or_else(|| s.split_once(" to "))
+            .ok_or_else(|| format!("expected odds like 3:2, got {s:?}"))?;
+        let stake = parse_decimal(stake.trim())?;
+        if stake.is_zero() {
+            return Err(format!("zero stake in odds {s:?}"));
+        }
+        let ratio = parse_decimal(winnings.trim())? / stake;
+        Ok(Self { ratio })
+    }
+}
+
+fn parse_decimal(s: &str) -> Result<Ratio<u64>, String> {
+    let invalid = || format!("invalid number {s:?} in odds");
+    let (whole, fraction) = s.split_once('.').unwrap_or((s, ""));
+    if whole.is_empty() && fraction.is_empty() || fraction.len() > 9 {
+        return Err(invalid());
+    }
+    let digits = |part: &str| -> Result<u64, String> {
+        if part.is_empty() {
+            return Ok(0);
+        }
+        if !part.bytes().all(|b| b.is_ascii_digit()) {
+            return Err(invalid());
+        }
+        part.parse().map_err(|_| invalid())
+    };
+    let scale = 10u64.pow(fraction.len() as u32);
+    let (whole, fraction) = (digits(whole)?, digits(fraction)?);
+    let value = whole.checked_mul(scale).and_then(|w| w.checked_add(fraction)).ok_or_else(invalid)?;
+    Ok(Ratio::new(value, scale))
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Breakage {
+    /// round winnings down to an amount the chip set can pay; the house keeps the rest
+    Chips,
+    /// pay to the cent, with coins for anything below the smallest chip
+    Cents,
+    /// pay what the chips allow and owe the rest to the player's next win
+    Carry,
+}
+
+// How a single bet came out.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Resolution {
+    Win(Odds),
+    /// a win at `odds` less `commission` per unit staked, taken as its own ledger entry
+    WinLessCommission { odds: Odds, commission: Odds },
+    Push,
+    Lose,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Payout {
+    pub stake_cents: u64,
+    /// winnings at the exact odds, before breakage
+    pub exact_cents: Ratio<u64>,
+    /// winnings actually paid, stake not included
+    pub paid_cents: u64,
+    /// kept by the house
+    pub breakage_cents: Ratio<u64>,
+    /// still owed to the player after this payout (only under `Breakage::Carry`)
+    pub carried_cents: Ratio<u64>,
+    /// fee collected from the win as its own entry; `paid_cents` does not include it
+    pub commission_cents: u64,
+}
+
+impl Payout {
+    // Stake plus winnings handed back, less any commission taken from them.
+    pub fn returned_cents(&self) -> u64 {
+        self.stake_cents + self.paid_cents - self.commission_cents
+    }
+}
+
+// Pays winning bets into players' wallets. Stakes are handed back as the chips that were bet,
+// winnings as the fewest chips from `chips`.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Cashier {
+    pub chips: ChipSet,
+    pub breakage: Breakage,
+    /// unpaid winnings per player id under `Breakage::Carry`
+    carried: HashMap<u64, Ratio<u64>>,
+    /// chips from losing bets
+    #[serde(with = "crate::bet::chip_map")]
+    collected: HashMap<Chip, u32>,
+    /// winnings paid out, stakes not included
+    paid_cents: u64,
+}
+
+impl Default for Cashier {
+    fn default() -> Self {
+        Self::new(ChipSet::standard(), Breakage::Chips)
+    }
+}
+
+impl Cashier {
+    pub fn new(chips: ChipSet, breakage: Breakage) -> Self {
+        Self { chips, breakage, carried: HashMap::new(), collected: HashMap::new(), paid_cents: 0 }
+    }
+
+    // The coin used to pay cents below the smallest chip.
+    pub fn coin() -> Chip {
+        Chip::new("cent", "1¢", 1)
+    }
+
+    pub fn collected(&self) -> &HashMap<Chip, u32> {
+        &self.collected
+    }
+
+    // What the house has taken from losing bets less the winnings it has paid.
+    pub fn house_net_cents(&self) -> i64 {
+        let collected: u64 = self.collected.iter().map(|(chip, &count)| chip.value_cents * count as u64).sum();
+        collected as i64 - self.paid_cents as i64
+    }
+
+    // Winnings still owed to the player with id `player`.
+    pub fn carried_cents(&self, player: u64) -> Ratio<u64> {
+        self.carried.get(&player).copied().unwrap_or_else(Ratio::zero)
+    }
+
+    // Work out what a win at `odds` on `stake_cents` pays the player with id `player`,
+    // without moving any chips.
+    pub fn quote(&self, player: u64, odds: Odds, stake_cents: u64) -> Result<Payout, Error> {
+        let exact_cents = odds.winnings(stake_cents)?;
+        let (paid_cents, carried_cents) = match self.breakage {
+            Breakage::Chips => (self.chips.round_down(exact_cents.to_integer()), Ratio::zero()),
+            Breakage::Cents => (exact_cents.to_integer(), Ratio::zero()),
+            Breakage::Carry => {
+                let owed = exact_cents + self.carried_cents(player);
+                let paid = self.chips.round_down(owed.to_integer());
+                (paid, owed - paid)
+            }
+        };
+        let carried_in = if self.breakage == Breakage::Carry { self.carried_cents(player) } else { Ratio::zero() };
+        let breakage_cents = exact_cents + carried_in - paid_cents - carried_cents;
+        Ok(Payout { stake_cents, exact_cents, paid_cents, breakage_cents, carried_cents, commission_cents: 0 })
+    }
+
+    // Hand back `stake` and pay the winnings at `odds` to `player`, recorded in their ledger
+    // as one win.
+    pub fn pay(
+        &mut self,
+        player: &mut Player,
+        stake: &HashMap<Chip, u32>,
+        odds: Odds,
+        source: Option<Source>,
+    ) -> Result<Payout, Error> {
+        self.pay_less_commission(player, stake, odds, Odds::to_one(0), source)
+    }
+
+    // Like `pay`, but `commission` per unit staked is collected from the win as its own ledger
+    // entry. The win is quoted and broken at `odds`, so the Win entry is the stake plus the win
+    // at `odds`; the part of it that covers the commission is paid in coins so the commission can
+    // always be taken back. The commission is never more than the win actually paid.
+    pub fn pay_less_commission(
+        &mut self,
+        player: &mut Player,
+        stake: &HashMap<Chip, u32>,
+        odds: Odds,
+        commission: Odds,
+        source: Option<Source>,
+    ) -> Result<Payout, Error> {
+        let (payout, chips) = self.plan_win(player.id(), stake, odds, commission)?;
+        player.credit(chips, Reason::Win, source.clone());
+        if payout.commission_cents > 0 {
+            // at most the coins just paid, so this fits and the player holds them
+            let fee = HashMap::from([(Self::coin(), payout.commission_cents as u32)]);
+            player.debit(&fee, Reason::Commission, source)?;
+            *self.collected.entry(Self::coin()).or_insert(0) += payout.commission_cents as u32;
+        }
+        self.paid_cents += payout.paid_cents;
+        if self.breakage == Breakage::Carry {
+            self.carried.insert(player.id(), payout.carried_cents);
+        }
+        Ok(payout)
+    }
+
+    // The payout for a win and the chips that hand it back with the stake. Everything that
+    // can fail in paying a win fails here, before any chips move.
+    fn plan_win(
+        &self,
+        player: u64,
+        stake: &HashMap<Chip, u32>,
+        odds: Odds,
+        commission: Odds,
+    ) -> Result<(Payout, HashMap<Chip, u32>), Error> {
+        if odds.less(commission).is_none() {
+            return Err(Error::InvalidBet(format!("commission of {commission} on a win at {odds}")));
+        }
+        let stake_cents = stake.iter().map(|(chip, &count)| chip.value_cents * count as u64).sum();
+        let mut payout = self.quote(player, odds, stake_cents)?;
+        payout.commission_cents = commission.winnings(stake_cents)?.to_integer().min(payout.paid_cents);
+        let in_chips = self.chips.round_down(payout.paid_cents - payout.commission_cents);
+        let in_coins = u32::try_from(payout.paid_cents - in_chips)
+            .map_err(|_| Error::Unpayable { cents: payout.paid_cents - in_chips })?;
+        let mut chips = stake.clone();
+        let paid_in_chips = self.chips.break_amount(in_chips).ok_or(Error::Unpayable { cents: in_chips })?;
+        for (chip, count) in paid_in_chips {
+            *chips.entry(chip).or_insert(0) += count;
+        }
+        if in_coins > 0 {
+            *chips.entry(Self::coin()).or_insert(0) += in_coins;
+        }
+        Ok((payout, chips))
+    }
+
+    // What `settle` would hand back to the player with id `player`, without moving any chips.
+    // Fails exactly when `settle` would, so a table can check every bet before paying any.
+    pub fn quote_settlement(
+        &self,
+        player: u64,
+        stake: &HashMap<Chip, u32>,
+        resolution: Resolution,
+    ) -> Result<Option<Payout>, Error> {
+        Ok(match resolution {
+            Resolution::Win(odds) => Some(self.plan_win(player, stake, odds, Odds::to_one(0))?.0),
+            Resolution::WinLessCommission { odds, commission } => {
+                Some(self.plan_win(player, stake, odds, commission)?.0)
+            }
+            Resolution::Push => Some(self.push_payout(player, stake)),
+            Resolution::Lose => None,
+        })
+    }
+
+    fn push_payout(&self, player: u64, stake: &HashMap<Chip, u32>) -> Payout {
+        Payout {
+            stake_cents: stake.iter().map(|(chip, &count)| chip.value_cents * count as u64).sum(),
+            exact_cents: Ratio::zero(),
+            paid_cents: 0,
+            breakage_cents: Ratio::zero(),
+            carried_cents: self.carried_cents(player),
+            commission_cents: 0,
+        }
+    }
+
+    // Settle a stake that has already been taken from the player, usually through
+    // `Bet::settle`. A push hands the stake back with nothing won; a losing bet returns None
+    // and its chips stay with the house.
+    pub fn settle(
+        &mut self,
+        player: &mut Player,
+        stake: &HashMap<Chip, u32>,
+        resolution: Resolution,
+        source: Option<Source>,
+    ) -> Result<Option<Payout>, Error> {
+        Ok(match resolution {
+            Resolution::Win(odds) => Some(self.pay(player, stake, odds, source)?),
+            Resolution::WinLessCommission { odds, commission } => {
+                Some(self.pay_less_commission(player, stake, odds, commission, source)?)
+            }
+            Resolution::Push => {
+                player.credit(stake.clone(), Reason::Push, source);
+                Some(self.push_payout(player.id(), stake))
+            }
+            Resolution::Lose => {
+                for (chip, &count) in stake {
+                    *self.collected.entry(chip.clone()).or_insert(0) += count;
+                }
+                None
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cents(whole: u64, numer: u64, denom: u64) -> Ratio<u64> {
+        Ratio::from_integer(whole) + Ratio::new(numer, denom)
+    }
+
+    #[test]
+    fn test_odds_arithmetic() {
+        let blackjack = Odds::new(3, 2).unwrap();
+        assert_eq!(blackjack.winnings(2_500), Ok(cents(3_750, 0, 1)));
+        assert_eq!(Odds::new(6, 5).unwrap().winnings(1_000), Ok(cents(1_200, 0, 1)));
+        assert_eq!(Odds::new(6, 4).unwrap(), blackjack);
+        assert!(Odds::new(1, 0).is_none());
+        // 3:2 on $5.25 is $7.875
+        assert_eq!(blackjack.winnings(525), Ok(cents(787, 1, 2)));
+        assert_eq!(Odds::to_one(35).winnings(100), Ok(cents(3_500, 0, 1)));
+        assert_eq!(
+            Odds::to_one(35).winnings(u64::MAX),
+            Err(Error::PayoutOverflow { stake_cents: u64::MAX, odds: Odds::to_one(35) })
+        );
+        assert!(Odds::to_one(8).is_whole() && !blackjack.is_whole());
+    }
+
+    #[test]
+    fn test_parse_and_display() {
+        assert_eq!("3:2".parse::<Odds>().unwrap(), Odds::new(3, 2).unwrap());
+        assert_eq!("6 to 5".parse::<Odds>().unwrap(), Odds::new(6, 5).unwrap());
+        assert_eq!("0.95:1".parse::<Odds>().unwrap(), Odds::new(19, 20).unwrap());
+        assert_eq!(Odds::new(19, 20).unwrap().to_string(), "19:20");
+        assert_eq!(Odds::to_one(35).to_string(), "35:1");
+        assert!("3-2".parse::<Odds>().is_err());
+        assert!("3:0".parse::<Odds>().is_err());
+        assert!("a:1".parse::<Odds>().is_err());
+        assert!(".:1".parse::<Odds>().is_err());
+    }
+
+    #[test]
+    fn test_breakage_policies() {
+        let commission = Odds::new(19, 20).unwrap();
+        let mut player = Player::new("Alice");
+        let stake = HashMap::from([(ChipSet::standard().chip(500).unwrap().clone(), 1)]);
+
+        // $4.75 rounds down to $4.50, a pink and two whites
+        let mut cashier = Cashier::default();
+        let payout = cashier.pay(&mut player, &stake, commission, None).unwrap();
+        assert_eq!(payout.paid_cents, 450);
+        assert_eq!(payout.breakage_cents, cents(25, 0, 1));
+        assert_eq!(player.total_cents(), 950);
+
+        // to the cent: $4.50 in chips and 25 coins
+        let mut cashier = Cashier::new(ChipSet::standard(), Breakage::Cents);
+        let mut player = Player::new("Bob");
+        let payout = cashier.pay(&mut player, &stake, Odds::new(3, 2).unwrap(), None).unwrap();
+        assert_eq!(payout.paid_cents, 750);
+        assert_eq!(payout.returned_cents(), 1_250);
+        assert_eq!(player.token_count(&Cashier::coin()), 0);
+        let payout = cashier.pay(&mut player, &stake, commission, None).unwrap();
+        assert_eq!(payout.paid_cents, 475);
+        assert_eq!(player.token_count(&Cashier::coin()), 25);
+        assert!(payout.breakage_cents.is_zero());
+    }
+
+    #[test]
+    fn test_carry_reconciles() {
+        let mut cashier = Cashier::new(ChipSet::standard(), Breakage::Carry);
+        let mut player = Player::new("Carol");
+        let stake = HashMap::from([(ChipSet::standard().chip(500).unwrap().clone(), 1)]);
+        let commission = Odds::new(19, 20).unwrap();
+        let mut exact = Ratio::zero();
+        let mut paid = 0;
+        for _ in 0..4 {
+            let payout = cashier.pay(&mut player, &stake, commission, None).unwrap();
+            exact += payout.exact_cents;
+            paid += payout.paid_cents;
+            assert!(payout.breakage_cents.is_zero());
+            assert_eq!(payout.carried_cents, cashier.carried_cents(player.id()));
+        }
+        // four wins of $4.75 pay $19 in all
+        assert_eq!(exact, cents(1_900, 0, 1));
+        assert_eq!(Ratio::from_integer(paid) + cashier.carried_cents(player.id()), exact);
+        assert_eq!(paid, 1_900);
+        assert!(cashier.carried_cents(player.id()).is_zero());
+    }
+
+    #[test]
+    fn test_carry_is_kept_per_player() {
+        let mut cashier = Cashier::new(ChipSet::standard(), Breakage::Carry);
+        let (mut first, mut second) = (Player::new("Sam"), Player::new("Sam"));
+        let stake = HashMap::from([(ChipSet::standard().chip(500).unwrap().clone(), 1)]);
+        let commission = Odds::new(19, 20).unwrap();
+        cashier.pay(&mut first, &stake, commission, None).unwrap();
+        assert_eq!(cashier.carried_cents(first.id()), cents(25, 0, 1));
+        assert!(cashier.carried_cents(second.id()).is_zero());
+        // the second Sam's win does not collect the first Sam's carry
+        let payout = cashier.pay(&mut second, &stake, commission, None).unwrap();
+        assert_eq!(payout.paid_cents, 450);
+        assert_eq!(cashier.carried_cents(first.id()), cents(25, 0, 1));
+    }
+
+    #[test]
+    fn test_banker_commission_under_default_cashier() {
+        let banker = Resolution::WinLessCommission { odds: Odds::even(), commission: Odds::new(1, 20).unwrap() };
+        for (chip_cents, paid, commission) in [(500, 500, 25), (2_500, 2_500, 125)] {
+            let mut cashier = Cashier::default();
+            let mut player = Player::new("Punto");
+            let stake = HashMap::from([(ChipSet::standard().chip(chip_cents).unwrap().clone(), 1)]);
+            let payout = cashier.settle(&mut player, &stake, banker, None).unwrap().unwrap();
+            // the win is broken at even money and the commission collected on its own
+            assert_eq!((payout.paid_cents, payout.commission_cents), (paid, commission));
+            assert!(payout.breakage_cents.is_zero());
+            assert_eq!(payout.returned_cents(), chip_cents + paid - commission);
+            assert_eq!(player.total_cents(), chip_cents + paid - commission);
+            let amounts: Vec<(Reason, i64)> =
+                player.ledger().entries().iter().map(|tx| (tx.reason, tx.amount_cents)).collect();
+            let win = (chip_cents + paid) as i64;
+            assert_eq!(amounts, vec![(Reason::Win, win), (Reason::Commission, -(commission as i64))]);
+            assert_eq!(cashier.house_net_cents(), commission as i64 - paid as i64);
+        }
+    }
+
+    #[test]
+    fn test_settle_push_and_lose() {
+        let mut cashier = Cashier::default();
+        let mut player = Player::new("Dave");
+        let stake = HashMap::from([(ChipSet::standard().chip(2_500).unwrap().clone(), 2)]);
+        let push = cashier.settle(&mut player, &stake, Resolution::Push, None).unwrap().unwrap();
+        assert_eq!((push.returned_cents(), push.paid_cents), (5_000, 0));
+        assert_eq!(player.total_cents(), 5_000);
+        assert_eq!(cashier.settle(&mut player, &stake, Resolution::Lose, None), Ok(None));
+        assert_eq!(player.total_cents(), 5_000);
+    }
+}
@@ -1,42 +1,214 @@
 use std::collections::HashMap;
+use std::time::SystemTime;
+use serde::{Deserialize, Serialize};
 use crate::bet::Chip;
+use crate::error::Error;
+use crate::ledger::{Ledger, Reason, Source};
+use crate::limits::{Limits, SelfExclusion};
+use crate::session::SessionStats;
 
-#[derive(Clone)]
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(try_from = "PlayerRaw")]
 pub struct Player {
     pub name: String,
+    /// tells apart players who share a name
+    id: u64,
     /// map token -> count
+    #[serde(with = "crate::bet::chip_map")]
+    wallet: HashMap<Chip, u32>,
+    /// every wallet movement
+    ledger: Ledger,
+    /// one per earlier ledger session, oldest first
+    closed_sessions: Vec<SessionStats>,
+    /// the session under way
+    session: SessionStats,
+    limits: Limits,
+}
+
+// A player as saved. Read back only if the ledger adds up to the wallet and the current
+// session is the ledger's.
+#[derive(Deserialize)]
+struct PlayerRaw {
+    name: String,
+    id: u64,
+    #[serde(with = "crate::bet::chip_map")]
     wallet: HashMap<Chip, u32>,
+    ledger: Ledger,
+    closed_sessions: Vec<SessionStats>,
+    session: SessionStats,
+    limits: Limits,
+}
+
+impl TryFrom<PlayerRaw> for Player {
+    type Error = String;
+
+    fn try_from(raw: PlayerRaw) -> Result<Self, String> {
+        let player = Player {
+            name: raw.name,
+            id: raw.id,
+            wallet: raw.wallet,
+            ledger: raw.ledger,
+            closed_sessions: raw.closed_sessions,
+            session: raw.session,
+            limits: raw.limits,
+        };
+        if let Err(id) = player.ledger.verify() {
+            return Err(format!("{}'s ledger entry {id} does not match its balance", player.name));
+        }
+        let balance = player.ledger.entries().last().map_or(0, |tx| tx.balance_after_cents);
+        let held = player.total_cents();
+        if balance != held {
+            return Err(format!("{}'s ledger ends at {balance} cents but the wallet holds {held}", player.name));
+        }
+        if player.session.session != player.ledger.current_session() {
+            return Err(format!("{}'s session stats are not for the ledger's current session", player.name));
+        }
+        Ok(player)
+    }
 }
 
 impl Player {
     pub fn new(name: impl Into<String>) -> Self {
         Self {
             name: name.into(),
+            id: rand::random(),
             wallet: HashMap::new(),
+            ledger: Ledger::new(),
+            closed_sessions: Vec::new(),
+            session: SessionStats::new(0),
+            limits: Limits::default(),
         }
     }
 
-    pub fn deposit(&mut self, token: Chip, count: u32) {
-        *self.wallet.entry(token).or_insert(0) += count;
+    pub fn id(&self) -> u64 {
+        self.id
+    }
+
+    pub fn with_limits(mut self, limits: Limits) -> Self {
+        self.set_limits(limits);
+        self
+    }
+
+    pub fn limits(&self) -> &Limits {
+        &self.limits
+    }
+
+    // Tighter limits apply at once and looser ones after a day; see `Limits::update`.
+    pub fn set_limits(&mut self, limits: Limits) {
+        self.limits.update(limits, SystemTime::now());
+    }
+
+    pub fn self_exclude(&mut self, exclusion: SelfExclusion) {
+        self.limits.exclude(exclusion);
+    }
+
+    // Deposits and withdrawals are recorded as buy-ins and cash-outs; table movements go
+    // through `credit` and `debit` with their own reason and source. Buy-ins are checked
+    // against the player's limits.
+    pub fn deposit(&mut self, token: Chip, count: u32) -> Result<(), Error> {
+        self.deposit_multiple(HashMap::from([(token, count)]))
+    }
+
+    pub fn deposit_multiple(&mut self, tokens: HashMap<Chip, u32>) -> Result<(), Error> {
+        let amount = tokens.iter().map(|(tok, &count)| tok.value_cents * count as u64).sum();
+        self.limits.check_deposit(&self.ledger, amount, SystemTime::now()).map_err(Error::Refused)?;
+        self.credit(tokens, Reason::BuyIn, None);
+        Ok(())
+    }
+
+    pub fn withdraw(&mut self, token: &Chip, count: u32) -> Result<(), Error> {
+        self.debit(&HashMap::from([(token.clone(), count)]), Reason::CashOut, None)
     }
 
-    pub fn deposit_multiple(&mut self, tokens: HashMap<Chip, u32>) {
-        tokens.iter().for_each(|(token, count)| {
-            self.deposit(token.clone(), *count);
-        });
+    // Money owed to the player, such as a win or a push. Never refused.
+    pub fn credit(&mut self, tokens: HashMap<Chip, u32>, reason: Reason, source: Option<Source>) {
+        for (token, &count) in &tokens {
+            *self.wallet.entry(token.clone()).or_insert(0) += count;
+        }
+        let amount = tokens.iter().map(|(tok, &count)| tok.value_cents * count as u64).sum::<u64>() as i64;
+        let balance = self.total_cents();
+        self.ledger.record(reason, amount, tokens, source, balance);
+        self.update_stats(reason, amount);
     }
 
-    pub fn withdraw(&mut self, token: &Chip, count: u32) -> Result<(), &'static str> {
-        match self.wallet.get_mut(token) {
-            Some(n) if *n >= count => {
+    // Take every chip in `tokens` or, if any is short, none of them. Wagers are checked
+    // against the player's limits first.
+    pub fn debit(&mut self, tokens: &HashMap<Chip, u32>, reason: Reason, source: Option<Source>) -> Result<(), Error> {
+        if let Some((token, &wanted)) = tokens.iter().find(|&(token, &count)| self.token_count(token) < count) {
+            return Err(Error::InsufficientChips { chip: token.name.clone(), wanted, held: self.token_count(token) });
+        }
+        if reason == Reason::Wager {
+            let amount = tokens.iter().map(|(tok, &count)| tok.value_cents * count as u64).sum();
+            self.limits
+                .check_wager(&self.ledger, &self.session, amount, SystemTime::now())
+                .map_err(Error::Refused)?;
+        }
+        for (token, &count) in tokens {
+            if let Some(n) = self.wallet.get_mut(token) {
                 *n -= count;
                 if *n == 0 {
                     self.wallet.remove(token);
                 }
-                Ok(())
             }
-            _ => Err("insufficient tokens"),
         }
+        let amount = tokens.iter().map(|(tok, &count)| tok.value_cents * count as u64).sum::<u64>() as i64;
+        let balance = self.total_cents();
+        self.ledger.record(reason, -amount, tokens.clone(), source, balance);
+        self.update_stats(reason, -amount);
+        Ok(())
+    }
+
+    fn update_stats(&mut self, reason: Reason, amount: i64) {
+        let stats = &mut self.session;
+        let cents = amount.unsigned_abs();
+        match reason {
+            Reason::BuyIn => stats.buy_in_cents += cents,
+            Reason::CashOut => stats.cash_out_cents += cents,
+            Reason::Wager => {
+                stats.wagered_cents += cents;
+                stats.first_wager.get_or_insert_with(SystemTime::now);
+            }
+            Reason::Win | Reason::Push => stats.returned_cents += cents,
+            Reason::Commission => stats.returned_cents = stats.returned_cents.saturating_sub(cents),
+            Reason::ColourUp => {}
+        }
+        stats.touch();
+    }
+
+    // Called as each bet is settled: one more decision and the stake's theoretical loss.
+    pub(crate) fn record_decision(&mut self, stake_cents: u64, house_edge: f64) {
+        let stats = &mut self.session;
+        stats.bets_placed += 1;
+        stats.decisions += 1;
+        stats.theoretical_loss_cents += stake_cents as f64 * house_edge;
+        stats.touch();
+    }
+
+    pub fn session_stats(&self) -> &SessionStats {
+        &self.session
+    }
+
+    // Every session's stats, oldest first and the current one last.
+    pub fn sessions(&self) -> impl Iterator<Item = &SessionStats> {
+        self.closed_sessions.iter().chain([&self.session])
+    }
+
+    pub fn ledger(&self) -> &Ledger {
+        &self.ledger
+    }
+
+    // Refused while the player is excluded or cooling off. Closing a session that ran out
+    // of time or reached its loss limit starts the cool-off.
+    pub fn start_session(&mut self) -> Result<u64, Error> {
+        self.limits.check_new_session(&self.ledger, &self.session, SystemTime::now())?;
+        let session = self.ledger.start_session();
+        self.closed_sessions.push(std::mem::replace(&mut self.session, SessionStats::new(session)));
+        Ok(session)
+    }
+
+    pub fn wallet(&self) -> &HashMap<Chip, u32> {
+        &self.wallet
     }
 
     pub fn token_count(&self, token: &Chip) -> u32 {
@@ -76,7 +248,7 @@ mod tests {
         let (quarter, _ones_chip, _fives_chip, _tens_chip) = sample_tokens();
         assert_eq!(player.total_cents(), 0);
         assert_eq!(player.total_balance(), 0.0);
-        player.deposit(quarter.clone(), 4);
+        player.deposit(quarter.clone(), 4).unwrap();
         assert_eq!(player.token_count(&quarter), 4);
         assert_eq!(player.total_cents(), 100);
     }
@@ -89,16 +261,16 @@ mod tests {
         map.insert(quarter.clone(), 4);
         map.insert(ones_chip.clone(), 2);
         map.insert(fives_chip.clone(), 1);
-        player.deposit_multiple(map);
-        assert_eq!(player.total_cents(), 4 * 25 + 2 * 100 + 1 * 500);
+        player.deposit_multiple(map).unwrap();
+        assert_eq!(player.total_cents(), 4 * 25 + 2 * 100 + 500);
     }
 
     #[test]
     fn test_withdraw() {
         let mut player = Player::new("Charlie");
         let (quarter, ones_chip, _fives_chip, _tens_chip) = sample_tokens();
-        player.deposit(quarter.clone(), 4);
-        player.deposit(ones_chip.clone(), 2);
+        player.deposit(quarter.clone(), 4).unwrap();
+        player.deposit(ones_chip.clone(), 2).unwrap();
         assert_eq!(player.total_cents(), 4 * 25 + 2 * 100);
         assert!(player.withdraw(&quarter, 2).is_ok());
         assert_eq!(player.token_count(&quarter), 2);
@@ -0,0 +1,204 @@
+//! Statistical bias detection over a roulette spin history.
+//!
+//! The detector runs a chi-square goodness-of-fit test over all pockets, plus an exact
+//! binomial test per pocket. Per-pocket tests are Bonferroni-corrected so that the
+//! chance of flagging any pocket on a fair wheel stays at `1 - confidence`. Repeated looks
+//! at a growing history in `spins_to_detect` are corrected the same way.
+
+use rand::Rng;
+
+use crate::roulette::{RouletteNumber, RouletteWheel, WheelType};
+use crate::stats;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct PocketDeviation {
+    pub number: RouletteNumber,
+    pub observed: u64,
+    pub expected: f64,
+    /// Exact two-sided binomial p-value for this pocket alone.
+    pub p_value: f64,
+    /// Confidence that this pocket deviates, after correcting for the number of pockets.
+    pub confidence: f64,
+}
+
+impl PocketDeviation {
+    pub fn is_hot(&self) -> bool {
+        self.observed as f64 > self.expected
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct BiasReport {
+    pub spins: u64,
+    pub chi_square: f64,
+    pub degrees_of_freedom: usize,
+    pub p_value: f64,
+    /// True when the chi-square test rejects a fair wheel at the detector's confidence.
+    pub biased: bool,
+    /// Every pocket, in `WheelType::numbers()` order.
+    pub pockets: Vec<PocketDeviation>,
+}
+
+impl BiasReport {
+    // Pockets whose individual test is significant at the given confidence.
+    pub fn deviating(&self, confidence: f64) -> Vec<PocketDeviation> {
+        self.pockets
+            .iter()
+            .filter(|p| p.confidence >= confidence)
+            .copied()
+            .collect()
+    }
+}
+
+pub struct BiasDetector {
+    pub wheel: WheelType,
+    /// e.g. 0.99 to reject a fair wheel at the 1% level
+    pub confidence: f64,
+}
+
+impl BiasDetector {
+    pub fn new(wheel: WheelType, confidence: f64) -> Self {
+        BiasDetector { wheel, confidence }
+    }
+
+    // Spins of numbers that are not on this wheel are ignored.
+    pub fn analyze(&self, history: &[RouletteNumber]) -> BiasReport {
+        let numbers = self.wheel.numbers();
+        let mut observed = vec![0u64; numbers.len()];
+        for spin in history {
+            if let Some(i) = numbers.iter().position(|n| n.number == spin.number) {
+                observed[i] += 1;
+            }
+        }
+        let spins: u64 = observed.iter().sum();
+        let p = 1.0 / numbers.len() as f64;
+        let expected = vec![spins as f64 * p; numbers.len()];
+
+        let chi_square = stats::chi_square_statistic(&observed, &expected);
+        let degrees_of_freedom = numbers.len() - 1;
+        let p_value = if spins == 0 { 1.0 } else { stats::chi_square_sf(chi_square, degrees_of_freedom) };
+
+        let pockets = numbers
+            .iter()
+            .zip(&observed)
+            .map(|(&number, &count)| {
+                let p_value = stats::binomial_two_sided(count, spins, p);
+                let corrected = (p_value * numbers.len() as f64).min(1.0);
+                PocketDeviation {
+                    number,
+                    observed: count,
+                    expected: spins as f64 * p,
+                    p_value,
+                    confidence: 1.0 - corrected,
+                }
+            })
+            .collect();
+
+        BiasReport {
+            spins,
+            chi_square,
+            degrees_of_freedom,
+            p_value,
+            biased: spins > 0 && p_value < 1.0 - self.confidence,
+            pockets,
+        }
+    }
+
+    // Spin `wheel` until the detector flags it, re-testing every `check_every` spins.
+    // Returns the number of spins needed, or None if `max_spins` was reached first.
+    //
+    // Testing the same growing history again and again gives a fair wheel many chances to
+    // look biased, so each look is Bonferroni-corrected: with k looks planned up to
+    // `max_spins`, a look flags the wheel only at 1/k of the detector's false-positive rate.
+    // The chance of ever flagging a fair wheel stays at or below `1 - confidence`.
+    pub fn spins_to_detect<R: Rng + ?Sized>(
+        &self,
+        wheel: &RouletteWheel,
+        check_every: usize,
+        max_spins: usize,
+        rng: &mut R,
+    ) -> Option<usize> {
+        let check_every = check_every.max(1);
+        let looks = max_spins.div_ceil(check_every).max(1);
+        let alpha = (1.0 - self.confidence) / looks as f64;
+        let mut history = Vec::with_capacity(max_spins);
+        while history.len() < max_spins {
+            for _ in 0..check_every.min(max_spins - history.len()) {
+                history.push(wheel.spin_with(rng));
+            }
+            if self.analyze(&history).p_value < alpha {
+                return Some(history.len());
+            }
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::roulette::WheelBias;
+    use rand::SeedableRng;
+    use rand::rngs::StdRng;
+
+    #[test]
+    fn test_empty_history() {
+        let report = BiasDetector::new(WheelType::European, 0.99).analyze(&[]);
+        assert_eq!(report.spins, 0);
+        assert!(!report.biased);
+        assert!(report.deviating(0.99).is_empty());
+    }
+
+    #[test]
+    fn test_fair_wheel_not_flagged() {
+        let mut rng = StdRng::seed_from_u64(7);
+        let wheel = RouletteWheel::new(WheelType::European);
+        let history: Vec<RouletteNumber> = (0..20_000).map(|_| wheel.spin_with(&mut rng)).collect();
+        let report = BiasDetector::new(WheelType::European, 0.999).analyze(&history);
+        assert_eq!(report.spins, 20_000);
+        assert_eq!(report.degrees_of_freedom, 36);
+        assert!(!report.biased);
+        assert!(report.deviating(0.999).is_empty());
+    }
+
+    #[test]
+    fn test_biased_pocket_flagged() {
+        let mut weights = vec![1.0; 38];
+        weights[17] = 2.0;
+        let wheel = RouletteWheel::with_bias(WheelType::American, WheelBias::Weights(weights)).unwrap();
+        let mut rng = StdRng::seed_from_u64(11);
+        let history: Vec<RouletteNumber> = (0..20_000).map(|_| wheel.spin_with(&mut rng)).collect();
+        let report = BiasDetector::new(WheelType::American, 0.99).analyze(&history);
+        assert!(report.biased);
+        let deviating = report.deviating(0.99);
+        assert_eq!(deviating.len(), 1);
+        assert_eq!(deviating[0].number.number, 17);
+        assert!(deviating[0].is_hot());
+    }
+
+    #[test]
+    fn test_spins_to_detect() {
+        let detector = BiasDetector::new(WheelType::European, 0.99);
+        let mut rng = StdRng::seed_from_u64(3);
+        let biased = RouletteWheel::with_bias(
+            WheelType::European,
+            WheelBias::Sector { start: 0, len: 5, factor: 3.0 },
+        )
+        .unwrap();
+        let spins = detector.spins_to_detect(&biased, 500, 50_000, &mut rng);
+        assert!(spins.is_some());
+        assert_eq!(spins.unwrap() % 500, 0);
+    }
+
+    #[test]
+    fn test_spins_to_detect_fair_wheel_false_alarms() {
+        // 20 looks each at the full 1% would flag several times more than 1% of fair wheels
+        let detector = BiasDetector::new(WheelType::European, 0.99);
+        let wheel = RouletteWheel::new(WheelType::European);
+        let mut rng = StdRng::seed_from_u64(26);
+        let flagged = (0..100)
+            .filter(|_| detector.spins_to_detect(&wheel, 100, 2_000, &mut rng).is_some())
+            .count();
+        assert!(flagged <= 4, "{flagged} of 100 fair wheels flagged");
+    }
+}
@@ -1,7 +1,11 @@
 #[cfg(feature = "python")]
 pub mod python_bindings;
+pub mod bias;
+pub mod variants;
 
 use rand::{Rng};
+use rand::distr::Distribution;
+use rand::distr::weighted::WeightedIndex;
 
 #[derive(Clone, Copy, Debug, PartialEq)]
 pub enum RouletteColor {
@@ -60,6 +64,7 @@ impl RouletteNumber {
     }
 }
 
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum WheelType {
     American, // 0, 00
     European, // 0
@@ -74,31 +79,126 @@ impl WheelType {
     pub fn numbers(&self) -> Vec<RouletteNumber> {
         match self {
             WheelType::American => (0..38)
-                .filter_map(|n| RouletteNumber::new(n))
+                .filter_map(RouletteNumber::new)
                 .collect(),
             WheelType::European => (0..=36)
-                .filter_map(|n| RouletteNumber::new(n))
+                .filter_map(RouletteNumber::new)
                 .collect(),
             WheelType::TripleZero => (0..40)
-                .filter_map(|n| RouletteNumber::new(n))
+                .filter_map(RouletteNumber::new)
                 .collect(),
         }
     }
+
+    // Pocket numbers in the order they sit around the wheel (37 = 00, 38 = 000).
+    pub fn pocket_order(&self) -> &'static [u16] {
+        match self {
+            WheelType::American => &[
+                0, 28, 9, 26, 30, 11, 7, 20, 32, 17, 5, 22, 34, 15, 3, 24, 36, 13, 1, 37, 27, 10,
+                25, 29, 12, 8, 19, 31, 18, 6, 21, 33, 16, 4, 23, 35, 14, 2,
+            ],
+            WheelType::European => &[
+                0, 32, 15, 19, 4, 21, 2, 25, 17, 34, 6, 27, 13, 36, 11, 30, 8, 23, 10, 5, 24, 16,
+                33, 1, 20, 14, 31, 9, 22, 18, 29, 7, 28, 12, 35, 3, 26,
+            ],
+            // Triple-zero layouts vary by maker; this is the American sequence with 000 after 00.
+            WheelType::TripleZero => &[
+                0, 28, 9, 26, 30, 11, 7, 20, 32, 17, 5, 22, 34, 15, 3, 24, 36, 13, 1, 37, 38, 27,
+                10, 25, 29, 12, 8, 19, 31, 18, 6, 21, 33, 16, 4, 23, 35, 14, 2,
+            ],
+        }
+    }
+}
+
+// How a wheel deviates from a fair one.
+#[derive(Clone, Debug, PartialEq)]
+pub enum WheelBias {
+    Fair,
+    /// Relative weight for each pocket, in the same order as `WheelType::numbers()`.
+    Weights(Vec<f64>),
+    /// `len` neighbouring pockets (in wheel order) starting at pocket `start` are each
+    /// `factor` times as likely as the rest, e.g. a worn or tilted section of the wheel.
+    Sector { start: u16, len: usize, factor: f64 },
 }
 
 pub struct RouletteWheel {
     pub wheel: WheelType,
+    bias: WheelBias,
+    /// pocket sampler for a biased wheel, built once with the wheel
+    index: Option<WeightedIndex<f64>>,
 }
 
 impl RouletteWheel {
     pub fn new(wheel: WheelType) -> Self {
-        RouletteWheel { wheel }
+        RouletteWheel { wheel, bias: WheelBias::Fair, index: None }
     }
 
-    pub fn spin(&self) -> RouletteNumber {
+    // Returns None when the bias does not describe a valid distribution for this wheel.
+    pub fn with_bias(wheel: WheelType, bias: WheelBias) -> Option<Self> {
+        let valid = match &bias {
+            WheelBias::Fair => true,
+            WheelBias::Weights(weights) => {
+                weights.len() == wheel.numbers().len()
+                    && weights.iter().all(|w| w.is_finite() && *w >= 0.0)
+                    && weights.iter().sum::<f64>() > 0.0
+            }
+            WheelBias::Sector { start, len, factor } => {
+                wheel.pocket_order().contains(start)
+                    && *len <= wheel.pocket_order().len()
+                    && factor.is_finite()
+                    && *factor >= 0.0
+            }
+        };
+        if !valid {
+            return None;
+        }
+        let mut wheel = RouletteWheel { wheel, bias, index: None };
+        if wheel.bias != WheelBias::Fair {
+            wheel.index = Some(WeightedIndex::new(wheel.pocket_weights()).ok()?);
+        }
+        Some(wheel)
+    }
+
+    pub fn bias(&self) -> &WheelBias {
+        &self.bias
+    }
+
+    // Relative weight of each pocket, aligned with `self.wheel.numbers()`.
+    pub fn pocket_weights(&self) -> Vec<f64> {
         let numbers = self.wheel.numbers();
+        match &self.bias {
+            WheelBias::Fair => vec![1.0; numbers.len()],
+            WheelBias::Weights(weights) => weights.clone(),
+            WheelBias::Sector { start, len, factor } => {
+                let order = self.wheel.pocket_order();
+                let first = order.iter().position(|n| n == start).unwrap_or(0);
+                let sector: Vec<u16> = (0..*len).map(|i| order[(first + i) % order.len()]).collect();
+                numbers
+                    .iter()
+                    .map(|n| if sector.contains(&n.number) { *factor } else { 1.0 })
+                    .collect()
+            }
+        }
+    }
+
+    // Probability of each pocket, aligned with `self.wheel.numbers()`.
+    pub fn pocket_probabilities(&self) -> Vec<f64> {
+        let weights = self.pocket_weights();
+        let total: f64 = weights.iter().sum();
+        weights.iter().map(|w| w / total).collect()
+    }
+
+    pub fn spin(&self) -> RouletteNumber {
         let mut rng = rand::rng();
-        let index = rng.random_range(0..numbers.len());
+        self.spin_with(&mut rng)
+    }
+
+    pub fn spin_with<R: Rng + ?Sized>(&self, rng: &mut R) -> RouletteNumber {
+        let numbers = self.wheel.numbers();
+        let index = match &self.index {
+            Some(index) => index.sample(rng),
+            None => rng.random_range(0..numbers.len()),
+        };
         numbers[index]
     }
 }
@@ -186,4 +286,48 @@ mod tests {
             assert!(result.number <= 38);
         }
     }
+
+    #[test]
+    fn test_pocket_order_covers_wheel() {
+        for wheel in WheelType::all() {
+            let mut order = wheel.pocket_order().to_vec();
+            order.sort();
+            let numbers: Vec<u16> = wheel.numbers().iter().map(|n| n.number).collect();
+            assert_eq!(order, numbers);
+        }
+    }
+
+    #[test]
+    fn test_with_bias_validation() {
+        assert!(RouletteWheel::with_bias(WheelType::European, WheelBias::Weights(vec![1.0; 36])).is_none());
+        assert!(RouletteWheel::with_bias(WheelType::European, WheelBias::Weights(vec![0.0; 37])).is_none());
+        assert!(RouletteWheel::with_bias(WheelType::European, WheelBias::Sector { start: 37, len: 3, factor: 2.0 }).is_none());
+        assert!(RouletteWheel::with_bias(WheelType::American, WheelBias::Sector { start: 37, len: 3, factor: 2.0 }).is_some());
+    }
+
+    #[test]
+    fn test_sector_bias_weights() {
+        // 0 followed by 32 and 15 on a European wheel
+        let wheel = RouletteWheel::with_bias(
+            WheelType::European,
+            WheelBias::Sector { start: 0, len: 3, factor: 2.0 },
+        )
+        .unwrap();
+        let weights = wheel.pocket_weights();
+        assert_eq!(weights[0], 2.0);
+        assert_eq!(weights[32], 2.0);
+        assert_eq!(weights[15], 2.0);
+        assert_eq!(weights[19], 1.0);
+        assert!((wheel.pocket_probabilities()[0] - 2.0 / 40.0).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_weighted_spin_only_hits_weighted_pockets() {
+        let mut weights = vec![0.0; 37];
+        weights[17] = 1.0;
+        let wheel = RouletteWheel::with_bias(WheelType::European, WheelBias::Weights(weights)).unwrap();
+        for _ in 0..100 {
+            assert_eq!(wheel.spin().number, 17);
+        }
+    }
 }
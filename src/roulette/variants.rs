@@ -0,0 +1,294 @@
+//! Roulette variants built on top of `RouletteWheel`.
+//!
+//! - `MultiBallRoulette`: several balls per spin, straight-up payouts depend on how many
+//!   balls land on the chosen number.
+//! - `LightningRoulette`: a few random straight-up numbers are struck with a multiplier
+//!   every spin, in exchange for a reduced base straight-up payout.
+
+use rand::Rng;
+use rand::distr::Distribution;
+use rand::distr::weighted::WeightedIndex;
+use rand::seq::index;
+
+use crate::error::Error;
+use crate::odds::Odds;
+use crate::roulette::{RouletteNumber, RouletteWheel};
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct MultiBallSpin {
+    pub balls: Vec<RouletteNumber>,
+}
+
+impl MultiBallSpin {
+    pub fn hits(&self, number: u16) -> usize {
+        self.balls.iter().filter(|b| b.number == number).count()
+    }
+}
+
+pub struct MultiBallRoulette {
+    pub wheel: RouletteWheel,
+    pub balls: usize,
+    /// `straight_up_payouts[k]` is the to-one payout when `k` balls land on the number.
+    /// Index 0 is ignored (the bet loses). Each ball is independent, so several balls may
+    /// share a pocket.
+    pub straight_up_payouts: Vec<u32>,
+}
+
+impl MultiBallRoulette {
+    // Returns None unless there is at least one ball and a payout for every hit count.
+    pub fn new(wheel: RouletteWheel, balls: usize, straight_up_payouts: Vec<u32>) -> Option<Self> {
+        if balls == 0 || straight_up_payouts.len() != balls + 1 {
+            return None;
+        }
+        Some(MultiBallRoulette { wheel, balls, straight_up_payouts })
+    }
+
+    pub fn spin(&self) -> MultiBallSpin {
+        let mut rng = rand::rng();
+        self.spin_with(&mut rng)
+    }
+
+    pub fn spin_with<R: Rng + ?Sized>(&self, rng: &mut R) -> MultiBallSpin {
+        MultiBallSpin {
+            balls: (0..self.balls).map(|_| self.wheel.spin_with(rng)).collect(),
+        }
+    }
+
+    pub fn straight_up_payout(&self, spin: &MultiBallSpin, number: u16) -> u32 {
+        match spin.hits(number) {
+            0 => 0,
+            k => self.straight_up_payouts[k],
+        }
+    }
+
+    // Stake plus winnings returned for a straight-up bet, or 0 if it lost.
+    pub fn straight_up_return(&self, spin: &MultiBallSpin, number: u16, stake_cents: u64) -> Result<u64, Error> {
+        match spin.hits(number) {
+            0 => Ok(0),
+            k => Ok(stake_cents + Odds::to_one(self.straight_up_payouts[k] as u64).winnings(stake_cents)?.to_integer()),
+        }
+    }
+
+    // Expected return per unit staked on a straight-up bet on `number`.
+    pub fn straight_up_rtp(&self, number: u16) -> f64 {
+        let p = pocket_probability(&self.wheel, number);
+        let n = self.balls as i32;
+        (1..=self.balls)
+            .map(|k| {
+                let ways = binomial_coefficient(self.balls, k);
+                let prob = ways * p.powi(k as i32) * (1.0 - p).powi(n - k as i32);
+                prob * (self.straight_up_payouts[k] as f64 + 1.0)
+            })
+            .sum()
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct LightningSpin {
+    pub result: RouletteNumber,
+    /// Struck numbers and their multipliers for this spin.
+    pub lucky: Vec<(RouletteNumber, u32)>,
+}
+
+impl LightningSpin {
+    pub fn multiplier(&self, number: u16) -> Option<u32> {
+        self.lucky.iter().find(|(n, _)| n.number == number).map(|&(_, m)| m)
+    }
+}
+
+pub struct LightningRoulette {
+    pub wheel: RouletteWheel,
+    /// To-one straight-up payout on numbers that were not struck, e.g. 29.
+    pub base_payout: u32,
+    /// Weighted distribution of how many numbers are struck each spin: (count, weight).
+    strike_counts: Vec<(usize, u32)>,
+    /// Weighted distribution of multipliers: (to-one payout, weight).
+    multipliers: Vec<(u32, u32)>,
+    /// samplers for the two distributions, built once with the game
+    strike_index: WeightedIndex<u32>,
+    multiplier_index: WeightedIndex<u32>,
+}
+
+impl LightningRoulette {
+    // Returns None if either distribution is empty, has no weight or weights adding up to more
+    // than `u32::MAX`, or strikes more numbers than the wheel has.
+    pub fn new(
+        wheel: RouletteWheel,
+        base_payout: u32,
+        strike_counts: Vec<(usize, u32)>,
+        multipliers: Vec<(u32, u32)>,
+    ) -> Option<Self> {
+        let pockets = wheel.wheel.numbers().len();
+        if strike_counts.iter().any(|&(c, _)| c > pockets) {
+            return None;
+        }
+        let strike_index = weighted_index(&strike_counts)?;
+        let multiplier_index = weighted_index(&multipliers)?;
+        Some(LightningRoulette { wheel, base_payout, strike_counts, multipliers, strike_index, multiplier_index })
+    }
+
+    pub fn strike_counts(&self) -> &[(usize, u32)] {
+        &self.strike_counts
+    }
+
+    pub fn multipliers(&self) -> &[(u32, u32)] {
+        &self.multipliers
+    }
+
+    pub fn spin(&self) -> LightningSpin {
+        let mut rng = rand::rng();
+        self.spin_with(&mut rng)
+    }
+
+    pub fn spin_with<R: Rng + ?Sized>(&self, rng: &mut R) -> LightningSpin {
+        let numbers = self.wheel.wheel.numbers();
+        let count = self.strike_counts[self.strike_index.sample(rng)].0;
+        let lucky = index::sample(rng, numbers.len(), count)
+            .into_iter()
+            .map(|i| (numbers[i], self.multipliers[self.multiplier_index.sample(rng)].0))
+            .collect();
+        LightningSpin { result: self.wheel.spin_with(rng), lucky }
+    }
+
+    pub fn straight_up_payout(&self, spin: &LightningSpin, number: u16) -> u32 {
+        if spin.result.number != number {
+            return 0;
+        }
+        spin.multiplier(number).unwrap_or(self.base_payout)
+    }
+
+    // Stake plus winnings returned for a straight-up bet, or 0 if it lost.
+    pub fn straight_up_return(&self, spin: &LightningSpin, number: u16, stake_cents: u64) -> Result<u64, Error> {
+        if spin.result.number != number {
+            return Ok(0);
+        }
+        Ok(stake_cents + Odds::to_one(self.straight_up_payout(spin, number) as u64).winnings(stake_cents)?.to_integer())
+    }
+
+    // Expected return per unit staked on a straight-up bet on `number`.
+    pub fn straight_up_rtp(&self, number: u16) -> f64 {
+        let pockets = self.wheel.wheel.numbers().len() as f64;
+        let strike_chance = weighted_mean(&self.strike_counts, |&c| c as f64) / pockets;
+        let mean_multiplier = weighted_mean(&self.multipliers, |&m| m as f64);
+        let p = pocket_probability(&self.wheel, number);
+        p * ((1.0 - strike_chance) * (self.base_payout as f64 + 1.0) + strike_chance * (mean_multiplier + 1.0))
+    }
+}
+
+fn pocket_probability(wheel: &RouletteWheel, number: u16) -> f64 {
+    wheel
+        .wheel
+        .numbers()
+        .iter()
+        .zip(wheel.pocket_probabilities())
+        .find(|(n, _)| n.number == number)
+        .map(|(_, p)| p)
+        .unwrap_or(0.0)
+}
+
+fn binomial_coefficient(n: usize, k: usize) -> f64 {
+    (0..k).fold(1.0, |acc, i| acc * (n - i) as f64 / (i + 1) as f64)
+}
+
+// None for choices with no weight, or weights adding up to more than `u32::MAX`.
+fn weighted_index<T>(choices: &[(T, u32)]) -> Option<WeightedIndex<u32>> {
+    let total: u64 = choices.iter().map(|&(_, w)| w as u64).sum();
+    if total == 0 || total > u32::MAX as u64 {
+        return None;
+    }
+    WeightedIndex::new(choices.iter().map(|&(_, w)| w)).ok()
+}
+
+fn weighted_mean<T>(choices: &[(T, u32)], value: impl Fn(&T) -> f64) -> f64 {
+    let total: f64 = choices.iter().map(|(_, w)| *w as f64).sum();
+    choices.iter().map(|(v, w)| value(v) * *w as f64).sum::<f64>() / total
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::roulette::WheelType;
+    use rand::SeedableRng;
+    use rand::rngs::StdRng;
+
+    #[test]
+    fn test_multi_ball_validation() {
+        assert!(MultiBallRoulette::new(RouletteWheel::new(WheelType::European), 0, vec![0]).is_none());
+        assert!(MultiBallRoulette::new(RouletteWheel::new(WheelType::European), 2, vec![0, 17]).is_none());
+        assert!(MultiBallRoulette::new(RouletteWheel::new(WheelType::European), 2, vec![0, 17, 200]).is_some());
+    }
+
+    #[test]
+    fn test_multi_ball_spin_and_payout() {
+        let game = MultiBallRoulette::new(RouletteWheel::new(WheelType::European), 3, vec![0, 11, 100, 1000]).unwrap();
+        let mut rng = StdRng::seed_from_u64(1);
+        let spin = game.spin_with(&mut rng);
+        assert_eq!(spin.balls.len(), 3);
+        let number = spin.balls[0].number;
+        let hits = spin.hits(number);
+        assert!(hits >= 1);
+        assert_eq!(game.straight_up_payout(&spin, number), game.straight_up_payouts[hits]);
+        assert_eq!(
+            game.straight_up_return(&spin, number, 100),
+            Ok(100 * (game.straight_up_payouts[hits] as u64 + 1))
+        );
+    }
+
+    #[test]
+    fn test_multi_ball_rtp() {
+        // A single ball paying 35:1 is plain European roulette: 36/37
+        let single = MultiBallRoulette::new(RouletteWheel::new(WheelType::European), 1, vec![0, 35]).unwrap();
+        assert!((single.straight_up_rtp(7) - 36.0 / 37.0).abs() < 1e-12);
+
+        // Two balls: P(one hit) = 2 * 36 / 37^2, P(two hits) = 1 / 37^2
+        let double = MultiBallRoulette::new(RouletteWheel::new(WheelType::European), 2, vec![0, 17, 35]).unwrap();
+        let expected = (2.0 * 36.0 * 18.0 + 36.0) / (37.0 * 37.0);
+        assert!((double.straight_up_rtp(7) - expected).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_lightning_validation() {
+        let wheel = || RouletteWheel::new(WheelType::European);
+        assert!(LightningRoulette::new(wheel(), 29, vec![], vec![(50, 1)]).is_none());
+        assert!(LightningRoulette::new(wheel(), 29, vec![(1, 1)], vec![(50, 0)]).is_none());
+        assert!(LightningRoulette::new(wheel(), 29, vec![(38, 1)], vec![(50, 1)]).is_none());
+        assert!(LightningRoulette::new(wheel(), 29, vec![(1, u32::MAX), (2, 1)], vec![(50, 1)]).is_none());
+        assert!(LightningRoulette::new(wheel(), 29, vec![(1, 1)], vec![(50, u32::MAX), (500, 1)]).is_none());
+        assert!(LightningRoulette::new(wheel(), 29, vec![(1, 1), (5, 1)], vec![(50, 3), (500, 1)]).is_some());
+    }
+
+    #[test]
+    fn test_lightning_spin() {
+        let game = LightningRoulette::new(
+            RouletteWheel::new(WheelType::European),
+            29,
+            vec![(1, 1), (2, 1), (3, 1), (4, 1), (5, 1)],
+            vec![(50, 4), (100, 3), (200, 2), (500, 1)],
+        )
+        .unwrap();
+        let mut rng = StdRng::seed_from_u64(5);
+        for _ in 0..200 {
+            let spin = game.spin_with(&mut rng);
+            assert!((1..=5).contains(&spin.lucky.len()));
+            let mut struck: Vec<u16> = spin.lucky.iter().map(|(n, _)| n.number).collect();
+            struck.sort();
+            struck.dedup();
+            assert_eq!(struck.len(), spin.lucky.len());
+            let number = spin.result.number;
+            let payout = game.straight_up_payout(&spin, number);
+            match spin.multiplier(number) {
+                Some(m) => assert_eq!(payout, m),
+                None => assert_eq!(payout, 29),
+            }
+            assert_eq!(game.straight_up_payout(&spin, (number + 1) % 37), 0);
+        }
+    }
+
+    #[test]
+    fn test_lightning_rtp() {
+        // Always one struck number at 100x: (36 * 30 + 101) / 37^2
+        let game = LightningRoulette::new(RouletteWheel::new(WheelType::European), 29, vec![(1, 1)], vec![(100, 1)]).unwrap();
+        let expected = (36.0 * 30.0 + 101.0) / (37.0 * 37.0);
+        assert!((game.straight_up_rtp(0) - expected).abs() < 1e-12);
+    }
+}
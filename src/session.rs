@@ -0,0 +1,156 @@
+//! Per-player session statistics for comps.
+//!
+//! A `Player` keeps one `SessionStats` per ledger session. The figures fill in as chips move:
+//! buy-ins and cash-outs from the cage, wagers as bets are escrowed, and bets, decisions and
+//! theoretical loss as each bet is settled. Theoretical loss is the stake times the house
+//! edge of the bet, which is what a casino rates play on regardless of luck.
+
+use std::time::{Duration, SystemTime};
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct SessionStats {
+    pub session: u64,
+    pub started: SystemTime,
+    /// when the first chip was wagered; session time limits run from here
+    pub first_wager: Option<SystemTime>,
+    pub last_activity: SystemTime,
+    pub buy_in_cents: u64,
+    pub cash_out_cents: u64,
+    pub wagered_cents: u64,
+    /// stakes and winnings handed back by winning and pushed bets
+    pub returned_cents: u64,
+    /// bets made, counted once each as they settle however many times chips were added
+    pub bets_placed: u64,
+    /// bets settled, whether won, lost or pushed
+    pub decisions: u64,
+    /// sum of stake times house edge over settled bets, in cents
+    pub theoretical_loss_cents: f64,
+}
+
+impl SessionStats {
+    pub fn new(session: u64) -> Self {
+        let now = SystemTime::now();
+        Self {
+            session,
+            started: now,
+            first_wager: None,
+            last_activity: now,
+            buy_in_cents: 0,
+            cash_out_cents: 0,
+            wagered_cents: 0,
+            returned_cents: 0,
+            bets_placed: 0,
+            decisions: 0,
+            theoretical_loss_cents: 0.0,
+        }
+    }
+
+    // Player's result from play, positive when they are ahead.
+    pub fn actual_win_cents(&self) -> i64 {
+        self.returned_cents as i64 - self.wagered_cents as i64
+    }
+
+    pub fn average_bet_cents(&self) -> f64 {
+        if self.bets_placed == 0 {
+            return 0.0;
+        }
+        self.wagered_cents as f64 / self.bets_placed as f64
+    }
+
+    pub fn time_played(&self) -> Duration {
+        self.last_activity.duration_since(self.started).unwrap_or_default()
+    }
+
+    // Theoretical loss as a share of the amount wagered.
+    pub fn average_house_edge(&self) -> f64 {
+        if self.wagered_cents == 0 {
+            return 0.0;
+        }
+        self.theoretical_loss_cents / self.wagered_cents as f64
+    }
+
+    pub(crate) fn touch(&mut self) {
+        self.last_activity = SystemTime::now();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use crate::bet::{Bet, ChipSet};
+    use crate::games::sicbo::{PayTable, SicBoBet, SicBoGame, SicBoRoll};
+    use crate::odds::{Cashier, Odds, Resolution};
+    use crate::player::Player;
+
+    #[test]
+    fn test_stats_follow_the_money() {
+        let red = ChipSet::standard().chip(500).unwrap().clone();
+        let mut player = Player::new("Alice");
+        player.deposit(red.clone(), 10).unwrap();
+        let mut cashier = Cashier::default();
+        for resolution in [Resolution::Win(Odds::even()), Resolution::Lose, Resolution::Push] {
+            let mut bet = Bet::new().with_house_edge(0.02);
+            bet.escrow(&mut player, HashMap::from([(red.clone(), 2)]), None).unwrap();
+            bet.settle(&mut player, &mut cashier, resolution, None).unwrap();
+        }
+        player.withdraw(&red, 2).unwrap();
+
+        let stats = player.session_stats();
+        assert_eq!(stats.buy_in_cents, 5_000);
+        assert_eq!(stats.cash_out_cents, 1_000);
+        assert_eq!((stats.bets_placed, stats.decisions), (3, 3));
+        assert_eq!(stats.wagered_cents, 3_000);
+        assert_eq!(stats.returned_cents, 3_000);
+        assert_eq!(stats.actual_win_cents(), 0);
+        assert_eq!(stats.average_bet_cents(), 1_000.0);
+        assert!((stats.theoretical_loss_cents - 60.0).abs() < 1e-9);
+        assert!((stats.average_house_edge() - 0.02).abs() < 1e-12);
+        assert!(stats.last_activity >= stats.started);
+    }
+
+    #[test]
+    fn test_sessions_are_separate() {
+        let mut player = Player::new("Bob");
+        player.deposit(ChipSet::standard().chip(100).unwrap().clone(), 5).unwrap();
+        let second = player.start_session().unwrap();
+        assert_eq!(player.session_stats().session, second);
+        assert_eq!(player.session_stats().buy_in_cents, 0);
+        assert_eq!(player.sessions().count(), 2);
+        assert_eq!(player.sessions().next().unwrap().buy_in_cents, 500);
+    }
+
+    #[test]
+    fn test_game_bets_carry_their_house_edge() {
+        let white = ChipSet::standard().chip(100).unwrap().clone();
+        let mut player = Player::new("Carol");
+        player.deposit(white.clone(), 100).unwrap();
+        let mut game = SicBoGame::new(vec![player], PayTable::macau());
+        game.place_bet(0, SicBoBet::Big, HashMap::from([(white.clone(), 10)])).unwrap();
+        game.place_bet(0, SicBoBet::Triple(1), HashMap::from([(white.clone(), 1)])).unwrap();
+        game.settle(SicBoRoll { dice: [1, 2, 3] }).unwrap();
+        let stats = game.players()[0].player.session_stats();
+        // big gives up 6/216 and a specific triple 65/216
+        let expected = 1_000.0 * 6.0 / 216.0 + 100.0 * 65.0 / 216.0;
+        assert!((stats.theoretical_loss_cents - expected).abs() < 1e-9);
+        assert_eq!(stats.decisions, 2);
+        assert_eq!(stats.actual_win_cents(), -1_100);
+    }
+
+    #[test]
+    fn test_adding_to_a_bet_counts_once() {
+        let white = ChipSet::standard().chip(100).unwrap().clone();
+        let mut player = Player::new("Dan");
+        player.deposit(white.clone(), 10).unwrap();
+        let mut game = SicBoGame::new(vec![player], PayTable::macau());
+        game.place_bet(0, SicBoBet::Big, HashMap::from([(white.clone(), 2)])).unwrap();
+        game.place_bet(0, SicBoBet::Big, HashMap::from([(white.clone(), 3)])).unwrap();
+        assert_eq!(game.players()[0].player.session_stats().bets_placed, 0);
+        game.settle(SicBoRoll { dice: [1, 2, 3] }).unwrap();
+        let stats = game.players()[0].player.session_stats();
+        assert_eq!((stats.bets_placed, stats.decisions), (1, 1));
+        assert_eq!(stats.average_bet_cents(), 500.0);
+    }
+}
@@ -0,0 +1,435 @@
+//! Monte Carlo simulation of table games across threads.
+//!
+//! A run is split into fixed-size shards. Each shard is played on a fresh table from the
+//! `Scenario`, with its own generator keyed by the run's seed and the shard number, so the
+//! streams never overlap. Threads take shards as they free up and the results are merged in
+//! shard order, which makes a report depend on the seed alone and not on the thread count.
+//! A fresh table per shard also stops the players' ledgers growing with the run.
+
+use std::collections::BTreeMap;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use rand::SeedableRng;
+use rand::rngs::StdRng;
+
+use crate::error::Error;
+use crate::games::{Round, TableGame};
+
+/// z-score for a two-sided 95% interval
+const Z_95: f64 = 1.959_964;
+
+pub type RoundOf<G> = Round<<G as TableGame>::Outcome, <G as TableGame>::Bet>;
+
+// What to simulate: the table, how it is bet, and how rounds are tallied.
+pub trait Scenario: Sync {
+    type Game: TableGame;
+
+    // A fresh table for one shard, with its players seated and bought in.
+    fn table(&self) -> Self::Game;
+
+    // Put down the bets for the next round, topping up or reloading as needed.
+    fn bet(&self, game: &mut Self::Game) -> Result<(), Error>;
+
+    // Name of the round's outcome in the report's frequencies. By default whether the bets
+    // it decided won, lost or pushed overall.
+    fn outcome(&self, round: &RoundOf<Self::Game>) -> &'static str {
+        if round.settlements.is_empty() {
+            return "undecided";
+        }
+        match round.returned_cents().cmp(&round.staked_cents()) {
+            std::cmp::Ordering::Greater => "win",
+            std::cmp::Ordering::Less => "lose",
+            std::cmp::Ordering::Equal => "push",
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Progress {
+    pub rounds_done: u64,
+    pub rounds_total: u64,
+    pub elapsed: Duration,
+}
+
+impl Progress {
+    pub fn fraction(&self) -> f64 {
+        self.rounds_done as f64 / self.rounds_total.max(1) as f64
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Report {
+    pub rounds: u64,
+    /// rounds that settled at least one bet
+    pub decisions: u64,
+    pub wagered_cents: u64,
+    pub net_cents: i64,
+    /// net result per unit wagered, `net_cents / wagered_cents`; negative when the house
+    /// has the edge
+    pub ev_per_unit: f64,
+    /// standard deviation of a decision's result about `ev_per_unit` times its stake, in
+    /// units of the average stake
+    pub sd_per_unit: f64,
+    /// rounds per `Scenario::outcome` name
+    pub outcomes: BTreeMap<&'static str, u64>,
+    pub elapsed: Duration,
+}
+
+impl Report {
+    // Standard error of `ev_per_unit` as a ratio estimator, so each decision weighs by its
+    // stake.
+    pub fn standard_error(&self) -> f64 {
+        if self.decisions == 0 {
+            return 0.0;
+        }
+        self.sd_per_unit / (self.decisions as f64).sqrt()
+    }
+
+    // 95% confidence interval for `ev_per_unit`, by the normal approximation.
+    pub fn confidence_95(&self) -> (f64, f64) {
+        let margin = Z_95 * self.standard_error();
+        (self.ev_per_unit - margin, self.ev_per_unit + margin)
+    }
+
+    // Share of rounds with this outcome.
+    pub fn frequency(&self, outcome: &str) -> f64 {
+        self.outcomes.get(outcome).copied().unwrap_or(0) as f64 / self.rounds.max(1) as f64
+    }
+
+    pub fn rounds_per_second(&self) -> f64 {
+        self.rounds as f64 / self.elapsed.as_secs_f64().max(f64::EPSILON)
+    }
+}
+
+pub struct Simulation {
+    rounds: u64,
+    seed: u64,
+    threads: usize,
+    shard_rounds: u64,
+    progress: Option<Box<dyn Fn(Progress) + Send + Sync>>,
+}
+
+impl Simulation {
+    // Runs on every available core in shards of 10,000 rounds.
+    pub fn new(rounds: u64, seed: u64) -> Self {
+        Self {
+            rounds,
+            seed,
+            threads: thread::available_parallelism().map_or(1, |n| n.get()),
+            shard_rounds: 10_000,
+            progress: None,
+        }
+    }
+
+    pub fn with_threads(mut self, threads: usize) -> Self {
+        self.threads = threads.max(1);
+        self
+    }
+
+    // Changing the shard size changes the generator streams, and so the report.
+    pub fn with_shard_rounds(mut self, shard_rounds: u64) -> Self {
+        self.shard_rounds = shard_rounds.max(1);
+        self
+    }
+
+    // Called from the worker threads each time a shard finishes.
+    pub fn with_progress(mut self, report: impl Fn(Progress) + Send + Sync + 'static) -> Self {
+        self.progress = Some(Box::new(report));
+        self
+    }
+
+    // Play every shard and merge the results. Stops at the first error a table returns.
+    pub fn run<S: Scenario>(&self, scenario: &S) -> Result<Report, Error> {
+        let start = Instant::now();
+        let shards = self.rounds.div_ceil(self.shard_rounds);
+        let next = AtomicU64::new(0);
+        let done = AtomicU64::new(0);
+        let failed = AtomicBool::new(false);
+        let results: Mutex<Vec<(u64, Result<Tally, Error>)>> = Mutex::new(Vec::new());
+        thread::scope(|scope| {
+            for _ in 0..self.threads.min(shards.max(1) as usize) {
+                scope.spawn(|| {
+                    while !failed.load(Ordering::Relaxed) {
+                        let shard = next.fetch_add(1, Ordering::Relaxed);
+                        if shard >= shards {
+                            break;
+                        }
+                        let rounds = self.shard_rounds.min(self.rounds - shard * self.shard_rounds);
+                        let result = play_shard(scenario, rounds, shard_rng(self.seed, shard));
+                        failed.fetch_or(result.is_err(), Ordering::Relaxed);
+                        results.lock().expect("no worker panics holding the lock").push((shard, result));
+                        let rounds_done = done.fetch_add(rounds, Ordering::Relaxed) + rounds;
+                        if let Some(progress) = &self.progress {
+                            progress(Progress { rounds_done, rounds_total: self.rounds, elapsed: start.elapsed() });
+                        }
+                    }
+                });
+            }
+        });
+        let mut results = results.into_inner().expect("workers have finished");
+        results.sort_by_key(|&(shard, _)| shard);
+        let mut total = Tally::default();
+        for (_, result) in results {
+            total.merge(result?);
+        }
+        Ok(total.report(start.elapsed()))
+    }
+}
+
+// Keyed by both numbers so no two shards of any run share a stream.
+fn shard_rng(seed: u64, shard: u64) -> StdRng {
+    let mut key = [0u8; 32];
+    key[..8].copy_from_slice(&seed.to_le_bytes());
+    key[8..16].copy_from_slice(&shard.to_le_bytes());
+    StdRng::from_seed(key)
+}
+
+fn play_shard<S: Scenario>(scenario: &S, rounds: u64, mut rng: StdRng) -> Result<Tally, Error> {
+    let mut game = scenario.table();
+    let mut tally = Tally::default();
+    for _ in 0..rounds {
+        scenario.bet(&mut game)?;
+        let round = game.play_round_with(&mut rng)?;
+        tally.add(&round, scenario.outcome(&round));
+    }
+    Ok(tally)
+}
+
+// Running totals for one shard. Alongside the sums it keeps Welford's means of each
+// decision's stake and net result, with their sums of squared deviations and co-deviation.
+#[derive(Default)]
+struct Tally {
+    rounds: u64,
+    decisions: u64,
+    wagered_cents: u64,
+    net_cents: i64,
+    mean_staked: f64,
+    mean_net: f64,
+    m2_staked: f64,
+    m2_net: f64,
+    co_moment: f64,
+    outcomes: BTreeMap<&'static str, u64>,
+}
+
+impl Tally {
+    fn add<O, B>(&mut self, round: &Round<O, B>, outcome: &'static str) {
+        self.rounds += 1;
+        *self.outcomes.entry(outcome).or_insert(0) += 1;
+        let staked = round.staked_cents();
+        if staked == 0 {
+            return;
+        }
+        let net = round.returned_cents() as i64 - staked as i64;
+        self.decisions += 1;
+        self.wagered_cents += staked;
+        self.net_cents += net;
+        let (x, y) = (staked as f64, net as f64);
+        let (dx, dy) = (x - self.mean_staked, y - self.mean_net);
+        self.mean_staked += dx / self.decisions as f64;
+        self.mean_net += dy / self.decisions as f64;
+        self.m2_staked += dx * (x - self.mean_staked);
+        self.m2_net += dy * (y - self.mean_net);
+        self.co_moment += dx * (y - self.mean_net);
+    }
+
+    // Chan et al.'s pairwise update, so merged shards match one long run.
+    fn merge(&mut self, other: Tally) {
+        let n = self.decisions + other.decisions;
+        if n > 0 {
+            let (a, b) = (self.decisions as f64, other.decisions as f64);
+            let (dx, dy) = (other.mean_staked - self.mean_staked, other.mean_net - self.mean_net);
+            self.mean_staked += dx * b / n as f64;
+            self.mean_net += dy * b / n as f64;
+            self.m2_staked += other.m2_staked + dx * dx * a * b / n as f64;
+            self.m2_net += other.m2_net + dy * dy * a * b / n as f64;
+            self.co_moment += other.co_moment + dx * dy * a * b / n as f64;
+        }
+        self.rounds += other.rounds;
+        self.decisions = n;
+        self.wagered_cents += other.wagered_cents;
+        self.net_cents += other.net_cents;
+        for (outcome, count) in other.outcomes {
+            *self.outcomes.entry(outcome).or_insert(0) += count;
+        }
+    }
+
+    fn report(self, elapsed: Duration) -> Report {
+        let ev = if self.wagered_cents > 0 { self.net_cents as f64 / self.wagered_cents as f64 } else { 0.0 };
+        // spread of net - ev * staked, which has mean zero when ev is the ratio of the means
+        let residual = (self.m2_net - 2.0 * ev * self.co_moment + ev * ev * self.m2_staked).max(0.0);
+        let variance = if self.decisions > 1 { residual / (self.decisions - 1) as f64 } else { 0.0 };
+        Report {
+            rounds: self.rounds,
+            decisions: self.decisions,
+            wagered_cents: self.wagered_cents,
+            net_cents: self.net_cents,
+            ev_per_unit: ev,
+            sd_per_unit: if self.mean_staked > 0.0 { variance.sqrt() / self.mean_staked } else { 0.0 },
+            outcomes: self.outcomes,
+            elapsed,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+    use std::sync::Arc;
+
+    use super::*;
+    use crate::bet::{Chip, ChipSet};
+    use crate::games::Settlement;
+    use crate::games::baccarat::{BaccaratBet, BaccaratGame, Winner};
+    use crate::games::blackjack::{BlackjackBet, BlackjackGame};
+    use crate::player::Player;
+
+    fn chip(value_cents: u64) -> Chip {
+        ChipSet::standard().chip(value_cents).unwrap().clone()
+    }
+
+    struct BankerBets;
+
+    impl Scenario for BankerBets {
+        type Game = BaccaratGame;
+
+        fn table(&self) -> BaccaratGame {
+            let mut player = Player::new("Sim");
+            player.deposit(chip(500), 20_000).unwrap();
+            BaccaratGame::new(vec![player])
+        }
+
+        fn bet(&self, game: &mut BaccaratGame) -> Result<(), Error> {
+            if game.cards_remaining() < 6 {
+                game.reload_shoe();
+            }
+            game.place_bet(0, BaccaratBet::Banker, HashMap::from([(chip(500), 1)]))
+        }
+
+        fn outcome(&self, round: &RoundOf<BaccaratGame>) -> &'static str {
+            match round.outcome.winner {
+                Winner::Player => "player",
+                Winner::Banker => "banker",
+                Winner::Tie => "tie",
+            }
+        }
+    }
+
+    #[test]
+    fn test_report_does_not_depend_on_threads() {
+        let run = |threads| Simulation::new(6_000, 50).with_shard_rounds(1_000).with_threads(threads).run(&BankerBets);
+        let (one, four) = (run(1).unwrap(), run(4).unwrap());
+        assert_eq!((one.rounds, one.decisions), (6_000, 6_000));
+        assert_eq!(one.outcomes, four.outcomes);
+        assert_eq!((one.net_cents, one.wagered_cents), (four.net_cents, four.wagered_cents));
+        assert_eq!(one.ev_per_unit, four.ev_per_unit);
+        assert!((one.sd_per_unit - four.sd_per_unit).abs() < 1e-12);
+        assert_ne!(run(1).unwrap().outcomes, Simulation::new(6_000, 51).run(&BankerBets).unwrap().outcomes);
+    }
+
+    #[test]
+    fn test_banker_edge_within_interval() {
+        let report = Simulation::new(20_000, 7).with_shard_rounds(2_500).run(&BankerBets).unwrap();
+        let (low, high) = report.confidence_95();
+        assert!(low < -0.0106 && -0.0106 < high, "{low}..{high}");
+        assert!((report.frequency("tie") - 0.0952).abs() < 0.01);
+        assert!((report.ev_per_unit - report.net_cents as f64 / report.wagered_cents as f64).abs() < 1e-12);
+        // a banker bet wins 0.95, loses 1 or pushes
+        assert!((report.sd_per_unit - 0.93).abs() < 0.02);
+    }
+
+    fn decided(staked_cents: u64, returned_cents: u64) -> Round<(), ()> {
+        let settlement = Settlement { seat: 0, bet: (), staked_cents, returned_cents, payout: None };
+        Round { number: 1, outcome: (), settlements: vec![settlement] }
+    }
+
+    #[test]
+    fn test_mixed_stakes_weigh_by_stake() {
+        // won $1 on $1 and lost $9 on $9, twice: even on average per decision, but 80 cents
+        // lost per dollar wagered
+        let rounds = [decided(100, 200), decided(900, 0), decided(100, 200), decided(900, 0)];
+        let (mut whole, mut first, mut second) = (Tally::default(), Tally::default(), Tally::default());
+        for (i, round) in rounds.iter().enumerate() {
+            whole.add(round, "any");
+            if i < 2 { &mut first } else { &mut second }.add(round, "any");
+        }
+        first.merge(second);
+        let (whole, merged) = (whole.report(Duration::ZERO), first.report(Duration::ZERO));
+        assert_eq!(whole.ev_per_unit, -0.8);
+        // net - ev * staked is +180 and -180 cents about an average stake of 500
+        let sd = (4.0 * 180.0f64 * 180.0 / 3.0).sqrt() / 500.0;
+        assert!((whole.sd_per_unit - sd).abs() < 1e-12);
+        assert!((whole.standard_error() - sd / 2.0).abs() < 1e-12);
+        assert_eq!(merged.ev_per_unit, whole.ev_per_unit);
+        assert!((merged.sd_per_unit - whole.sd_per_unit).abs() < 1e-12);
+    }
+
+    struct MixedBankerBets;
+
+    impl Scenario for MixedBankerBets {
+        type Game = BaccaratGame;
+
+        // winnings come back as larger chips, so bring enough reds for every stake
+        fn table(&self) -> BaccaratGame {
+            let mut player = Player::new("Sim");
+            player.deposit(chip(500), 100_000).unwrap();
+            BaccaratGame::new(vec![player])
+        }
+
+        // $20 and $200 in turn
+        fn bet(&self, game: &mut BaccaratGame) -> Result<(), Error> {
+            if game.cards_remaining() < 6 {
+                game.reload_shoe();
+            }
+            let count = if game.history().len().is_multiple_of(2) { 4 } else { 40 };
+            game.place_bet(0, BaccaratBet::Banker, HashMap::from([(chip(500), count)]))
+        }
+    }
+
+    #[test]
+    fn test_mixed_stake_edge_within_interval() {
+        let report = Simulation::new(20_000, 11).with_shard_rounds(2_500).run(&MixedBankerBets).unwrap();
+        let (low, high) = report.confidence_95();
+        assert!(low < -0.0106 && -0.0106 < high, "{low}..{high}");
+        assert_eq!(report.ev_per_unit, report.net_cents as f64 / report.wagered_cents as f64);
+    }
+
+    #[test]
+    fn test_progress_reaches_the_total() {
+        let seen = Arc::new(Mutex::new(Vec::new()));
+        let sink = Arc::clone(&seen);
+        Simulation::new(2_500, 1)
+            .with_shard_rounds(1_000)
+            .with_threads(2)
+            .with_progress(move |p| sink.lock().unwrap().push(p.rounds_done))
+            .run(&BankerBets)
+            .unwrap();
+        let mut seen = seen.lock().unwrap().clone();
+        seen.sort();
+        assert_eq!(seen, [1_000, 2_000, 2_500]);
+    }
+
+    struct Broke;
+
+    impl Scenario for Broke {
+        type Game = BlackjackGame;
+
+        fn table(&self) -> BlackjackGame {
+            let mut player = Player::new("Sim");
+            player.deposit(chip(500), 3).unwrap();
+            BlackjackGame::new(vec![player], 6, 5, 500)
+        }
+
+        fn bet(&self, game: &mut BlackjackGame) -> Result<(), Error> {
+            TableGame::place_bet(game, 0, BlackjackBet::Hand, HashMap::from([(chip(500), 1)]))
+        }
+    }
+
+    #[test]
+    fn test_errors_stop_the_run() {
+        let result = Simulation::new(1_000, 3).with_shard_rounds(100).run(&Broke);
+        assert!(matches!(result, Err(Error::InsufficientChips { .. })));
+    }
+}
@@ -0,0 +1,674 @@
+//! Declarative slot game definitions in TOML.
+//!
+//! A file describes one complete game: its symbols and pay table, reel strips, paylines,
+//! how wins are evaluated and any bonus features. `GameConfig::load` parses and validates
+//! it, and `GameConfig::build` turns it into a `FeatureGame` ready to spin.
+//!
+//! ```
+//! use casino_rs::slots::config::GameConfig;
+//!
+//! let config = GameConfig::from_toml_str(r#"
+//! name = "Lucky Sevens"
+//! rows = 3
+//! pay_mode = "left-to-right"      # "both-ways", "anywhere", "ways" or "clusters"
+//! ## cluster_min_size = 5          # required for "clusters", at least 1
+//! ## bet_multiplier = 20           # defaults to the number of paylines
+//! ## cascade_multipliers = [2, 3]  # enables tumbling reels
+//! ## reel_heights = [[[2, 1], [3, 1]], ...]  # per reel (height, weight), Megaways-style
+//! paylines = [[1, 1, 1], [0, 0, 0], [2, 2, 2]]
+//!
+//! [[symbols]]
+//! name = "seven"
+//! pays = [0, 0, 100]              # pays[k - 1] for k of a kind; must not decrease
+//!
+//! [[symbols]]
+//! name = "wild"
+//! kind = "wild"                   # "regular" (default), "wild" or "scatter"
+//! pays = [0, 0, 250]
+//! excludes = ["bonus"]            # wild only; names of defined symbols
+//! multiplier = 2                  # wild only, defaults to 1
+//!
+//! [[symbols]]
+//! name = "bonus"
+//! kind = "scatter"
+//! pays = [0, 2, 5]
+//! trigger_count = 3               # scatter only
+//!
+//! [[symbols]]
+//! name = "coin"
+//! kind = "scatter"
+//! pays = []
+//! trigger_count = 6
+//!
+//! [[symbols]]
+//! name = "chest"
+//! kind = "scatter"
+//! pays = []
+//! trigger_count = 3
+//!
+//! [[reels]]
+//! strip = ["seven", "wild", "bonus", "coin", "seven"]
+//! weights = [1, 1, 1, 1, 2]       # optional, one per stop
+//!
+//! [[reels]]
+//! strip = ["seven", "chest", "bonus", "coin"]
+//!
+//! [[reels]]
+//! strip = ["wild", "chest", "bonus", "coin"]
+//!
+//! [[features]]                    # at most one feature per trigger
+//! type = "free-spins"
+//! trigger = "bonus"
+//! spins = 10
+//! retrigger_spins = 5             # optional
+//! multiplier = 2                  # optional, defaults to 1
+//! multiplier_step = 0             # optional
+//! ## reels = [{ strip = [...] }]   # optional free-game reel set
+//!
+//! [[features]]
+//! type = "hold-and-spin"
+//! trigger = "coin"
+//! respins = 3
+//! values = [[5, 10], [25, 2]]     # (value, weight); at least one weight above 0
+//! full_screen_bonus = 1000
+//!
+//! [[features]]
+//! type = "pick-em"
+//! trigger = "chest"
+//! picks = 3
+//! prizes = [10, 20, 50]
+//! end_tiles = 1                   # tiles that end the game early
+//! "#)?;
+//! assert_eq!(config.validate(), Ok(()));
+//! let game = config.build()?;
+//! assert_eq!(game.features().len(), 3);
+//! # Ok::<(), casino_rs::slots::config::ConfigError>(())
+//! ```
+
+use std::fmt;
+use std::path::Path;
+
+use serde::Deserialize;
+
+use crate::slots::features::{Feature, FeatureGame, PickPrize};
+use crate::slots::{PayMode, Reel, Slot, Symbol, SymbolKind};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ConfigError {
+    Io(String),
+    Parse(String),
+    NoReels,
+    NoRows,
+    DuplicateSymbol(String),
+    UnknownSymbol { reel: usize, position: usize, symbol: String },
+    EmptyReel(usize),
+    WeightCount { reel: usize, stops: usize, weights: usize },
+    ZeroWeight(usize),
+    WeightOverflow(usize),
+    PaylineLength { line: usize, expected: usize, found: usize },
+    PaylineOutOfRange { line: usize, reel: usize, row: usize },
+    NonMonotonicPays(String),
+    KindMismatch { symbol: String, field: &'static str },
+    MissingClusterSize,
+    ReelHeights(String),
+    UnknownTrigger { feature: usize, trigger: String },
+    FeatureReels { feature: usize, reason: String },
+    NoFeatureValues(usize),
+    DuplicateTrigger(String),
+    UnknownExclude { symbol: String, excluded: String },
+    ZeroClusterSize,
+    ZeroTriggerCount(String),
+    ZeroMultiplier(String),
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConfigError::Io(e) => write!(f, "could not read game file: {e}"),
+            ConfigError::Parse(e) => write!(f, "invalid game file: {e}"),
+            ConfigError::NoReels => write!(f, "the game has no reels"),
+            ConfigError::NoRows => write!(f, "rows must be at least 1"),
+            ConfigError::DuplicateSymbol(s) => write!(f, "symbol '{s}' is defined more than once"),
+            ConfigError::UnknownSymbol { reel, position, symbol } => {
+                write!(f, "reel {reel} position {position}: unknown symbol '{symbol}'")
+            }
+            ConfigError::EmptyReel(reel) => write!(f, "reel {reel} has an empty strip"),
+            ConfigError::WeightCount { reel, stops, weights } => {
+                write!(f, "reel {reel} has {stops} stops but {weights} weights")
+            }
+            ConfigError::ZeroWeight(reel) => write!(f, "reel {reel} weights add up to zero"),
+            ConfigError::WeightOverflow(reel) => write!(f, "reel {reel} weights add up to more than {}", u32::MAX),
+            ConfigError::PaylineLength { line, expected, found } => {
+                write!(f, "payline {line} covers {found} reels, expected {expected}")
+            }
+            ConfigError::PaylineOutOfRange { line, reel, row } => {
+                write!(f, "payline {line} uses row {row} on reel {reel}, which is not visible")
+            }
+            ConfigError::NonMonotonicPays(s) => write!(f, "pays for '{s}' decrease as the count grows"),
+            ConfigError::KindMismatch { symbol, field } => {
+                write!(f, "symbol '{symbol}' sets '{field}', which does not apply to its kind")
+            }
+            ConfigError::MissingClusterSize => write!(f, "pay_mode \"clusters\" needs cluster_min_size"),
+            ConfigError::ReelHeights(reason) => write!(f, "invalid reel_heights: {reason}"),
+            ConfigError::UnknownTrigger { feature, trigger } => {
+                write!(f, "feature {feature} is triggered by '{trigger}', which is not a triggering scatter")
+            }
+            ConfigError::FeatureReels { feature, reason } => write!(f, "feature {feature} reels: {reason}"),
+            ConfigError::NoFeatureValues(feature) => write!(f, "feature {feature} has no value with a positive weight"),
+            ConfigError::DuplicateTrigger(trigger) => write!(f, "more than one feature is triggered by '{trigger}'"),
+            ConfigError::UnknownExclude { symbol, excluded } => {
+                write!(f, "wild '{symbol}' excludes '{excluded}', which is not a symbol")
+            }
+            ConfigError::ZeroClusterSize => write!(f, "cluster_min_size must be at least 1"),
+            ConfigError::ZeroTriggerCount(s) => write!(f, "scatter '{s}' has a trigger_count of zero"),
+            ConfigError::ZeroMultiplier(s) => write!(f, "wild '{s}' has a multiplier of zero"),
+        }
+    }
+}
+
+impl std::error::Error for ConfigError {}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum PayModeConfig {
+    LeftToRight,
+    BothWays,
+    Anywhere,
+    Ways,
+    Clusters,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum SymbolKindConfig {
+    #[default]
+    Regular,
+    Wild,
+    Scatter,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SymbolConfig {
+    pub name: String,
+    #[serde(default)]
+    pub kind: SymbolKindConfig,
+    pub pays: Vec<u32>,
+    pub excludes: Option<Vec<String>>,
+    pub multiplier: Option<u32>,
+    pub trigger_count: Option<usize>,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ReelConfig {
+    pub strip: Vec<String>,
+    pub weights: Option<Vec<u32>>,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(tag = "type", rename_all = "kebab-case", deny_unknown_fields)]
+pub enum FeatureConfig {
+    FreeSpins {
+        trigger: String,
+        spins: u32,
+        #[serde(default)]
+        retrigger_spins: u32,
+        multiplier: Option<u32>,
+        #[serde(default)]
+        multiplier_step: u32,
+        reels: Option<Vec<ReelConfig>>,
+    },
+    HoldAndSpin {
+        trigger: String,
+        respins: u32,
+        values: Vec<(u64, u32)>,
+        #[serde(default)]
+        full_screen_bonus: u64,
+    },
+    PickEm {
+        trigger: String,
+        picks: u32,
+        prizes: Vec<u64>,
+        #[serde(default)]
+        end_tiles: usize,
+    },
+}
+
+impl FeatureConfig {
+    fn trigger(&self) -> &str {
+        match self {
+            FeatureConfig::FreeSpins { trigger, .. }
+            | FeatureConfig::HoldAndSpin { trigger, .. }
+            | FeatureConfig::PickEm { trigger, .. } => trigger,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct GameConfig {
+    pub name: String,
+    pub rows: usize,
+    pub pay_mode: PayModeConfig,
+    pub cluster_min_size: Option<usize>,
+    pub bet_multiplier: Option<u32>,
+    pub cascade_multipliers: Option<Vec<u64>>,
+    pub reel_heights: Option<Vec<Vec<(usize, u32)>>>,
+    #[serde(default)]
+    pub paylines: Vec<Vec<usize>>,
+    pub symbols: Vec<SymbolConfig>,
+    pub reels: Vec<ReelConfig>,
+    #[serde(default)]
+    pub features: Vec<FeatureConfig>,
+}
+
+impl GameConfig {
+    // Read, parse and validate a game file.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, ConfigError> {
+        let text = std::fs::read_to_string(path).map_err(|e| ConfigError::Io(e.to_string()))?;
+        Self::from_toml_str(&text)
+    }
+
+    pub fn from_toml_str(text: &str) -> Result<Self, ConfigError> {
+        let config: GameConfig = toml::from_str(text).map_err(|e| ConfigError::Parse(e.to_string()))?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        if self.reels.is_empty() {
+            return Err(ConfigError::NoReels);
+        }
+        if self.rows == 0 {
+            return Err(ConfigError::NoRows);
+        }
+        for (i, symbol) in self.symbols.iter().enumerate() {
+            if self.symbols[..i].iter().any(|s| s.name == symbol.name) {
+                return Err(ConfigError::DuplicateSymbol(symbol.name.clone()));
+            }
+            if symbol.pays.windows(2).any(|w| w[1] < w[0]) {
+                return Err(ConfigError::NonMonotonicPays(symbol.name.clone()));
+            }
+            let mismatch = |field| ConfigError::KindMismatch { symbol: symbol.name.clone(), field };
+            if symbol.kind != SymbolKindConfig::Wild && symbol.excludes.is_some() {
+                return Err(mismatch("excludes"));
+            }
+            if symbol.kind != SymbolKindConfig::Wild && symbol.multiplier.is_some() {
+                return Err(mismatch("multiplier"));
+            }
+            if symbol.kind != SymbolKindConfig::Scatter && symbol.trigger_count.is_some() {
+                return Err(mismatch("trigger_count"));
+            }
+            if symbol.trigger_count == Some(0) {
+                return Err(ConfigError::ZeroTriggerCount(symbol.name.clone()));
+            }
+            if symbol.multiplier == Some(0) {
+                return Err(ConfigError::ZeroMultiplier(symbol.name.clone()));
+            }
+            if let Some(excluded) = symbol.excludes.iter().flatten().find(|e| !self.symbols.iter().any(|s| &&s.name == e)) {
+                return Err(ConfigError::UnknownExclude { symbol: symbol.name.clone(), excluded: excluded.clone() });
+            }
+        }
+        self.validate_reels(&self.reels)?;
+
+        let shortest = match &self.reel_heights {
+            Some(heights) => self.validate_heights(heights)?,
+            None => vec![self.rows; self.reels.len()],
+        };
+        for (line, rows) in self.paylines.iter().enumerate() {
+            if rows.len() != self.reels.len() {
+                return Err(ConfigError::PaylineLength { line, expected: self.reels.len(), found: rows.len() });
+            }
+            if let Some((reel, &row)) = rows.iter().enumerate().find(|&(reel, &row)| row >= shortest[reel]) {
+                return Err(ConfigError::PaylineOutOfRange { line, reel, row });
+            }
+        }
+        if self.pay_mode == PayModeConfig::Clusters && self.cluster_min_size.is_none() {
+            return Err(ConfigError::MissingClusterSize);
+        }
+        if self.cluster_min_size == Some(0) {
+            return Err(ConfigError::ZeroClusterSize);
+        }
+
+        for (i, feature) in self.features.iter().enumerate() {
+            let triggers = self
+                .symbols
+                .iter()
+                .any(|s| s.name == feature.trigger() && s.kind == SymbolKindConfig::Scatter && s.trigger_count.is_some());
+            if !triggers {
+                return Err(ConfigError::UnknownTrigger { feature: i, trigger: feature.trigger().to_string() });
+            }
+            // a spin starts the first feature with its trigger, so a second would never run
+            if self.features[..i].iter().any(|f| f.trigger() == feature.trigger()) {
+                return Err(ConfigError::DuplicateTrigger(feature.trigger().to_string()));
+            }
+            if let FeatureConfig::FreeSpins { reels: Some(reels), .. } = feature {
+                if reels.len() != self.reels.len() {
+                    return Err(ConfigError::FeatureReels {
+                        feature: i,
+                        reason: format!("{} reels, expected {}", reels.len(), self.reels.len()),
+                    });
+                }
+                self.validate_reels(reels)
+                    .map_err(|e| ConfigError::FeatureReels { feature: i, reason: e.to_string() })?;
+            }
+            if let FeatureConfig::HoldAndSpin { values, .. } = feature
+                && values.iter().all(|&(_, w)| w == 0)
+            {
+                return Err(ConfigError::NoFeatureValues(i));
+            }
+        }
+        Ok(())
+    }
+
+    fn validate_reels(&self, reels: &[ReelConfig]) -> Result<(), ConfigError> {
+        for (reel, config) in reels.iter().enumerate() {
+            if config.strip.is_empty() {
+                return Err(ConfigError::EmptyReel(reel));
+            }
+            if let Some((position, symbol)) =
+                config.strip.iter().enumerate().find(|(_, name)| !self.symbols.iter().any(|s| &s.name == *name))
+            {
+                return Err(ConfigError::UnknownSymbol { reel, position, symbol: symbol.clone() });
+            }
+            if let Some(weights) = &config.weights {
+                if weights.len() != config.strip.len() {
+                    return Err(ConfigError::WeightCount { reel, stops: config.strip.len(), weights: weights.len() });
+                }
+                let total: u64 = weights.iter().map(|&w| w as u64).sum();
+                if total == 0 {
+                    return Err(ConfigError::ZeroWeight(reel));
+                }
+                if total > u32::MAX as u64 {
+                    return Err(ConfigError::WeightOverflow(reel));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    // Returns the shortest possible height of each reel.
+    fn validate_heights(&self, heights: &[Vec<(usize, u32)>]) -> Result<Vec<usize>, ConfigError> {
+        if heights.len() != self.reels.len() {
+            return Err(ConfigError::ReelHeights(format!("{} entries for {} reels", heights.len(), self.reels.len())));
+        }
+        heights
+            .iter()
+            .enumerate()
+            .map(|(reel, choices)| {
+                if choices.iter().any(|&(h, _)| h == 0) {
+                    return Err(ConfigError::ReelHeights(format!("reel {reel} has a zero height")));
+                }
+                choices
+                    .iter()
+                    .filter(|&&(_, w)| w > 0)
+                    .map(|&(h, _)| h)
+                    .min()
+                    .ok_or_else(|| ConfigError::ReelHeights(format!("reel {reel} has no weighted height")))
+            })
+            .collect()
+    }
+
+    fn symbol(&self, name: &str) -> Symbol {
+        let config = self.symbols.iter().find(|s| s.name == name).expect("strip symbols validated");
+        let kind = match config.kind {
+            SymbolKindConfig::Regular => SymbolKind::Regular,
+            SymbolKindConfig::Wild => SymbolKind::Wild {
+                excludes: config.excludes.clone().unwrap_or_default(),
+                multiplier: config.multiplier.unwrap_or(1),
+            },
+            SymbolKindConfig::Scatter => SymbolKind::Scatter { trigger_count: config.trigger_count },
+        };
+        // runs longer than the pay table pay its last entry
+        let full_line = config.pays.last().copied().unwrap_or(0);
+        Symbol::new(config.name.clone(), full_line).with_kind(kind).with_pays(config.pays.clone())
+    }
+
+    fn build_reels(&self, reels: &[ReelConfig]) -> Vec<Reel> {
+        reels
+            .iter()
+            .map(|reel| {
+                let weights = reel.weights.clone().unwrap_or_else(|| vec![1; reel.strip.len()]);
+                let stops = reel.strip.iter().map(|name| self.symbol(name)).zip(weights).collect();
+                Reel::weighted(stops).expect("reel weights validated")
+            })
+            .collect()
+    }
+
+    fn build_slot(&self, reels: &[ReelConfig]) -> Slot {
+        let pay_mode = match self.pay_mode {
+            PayModeConfig::LeftToRight => PayMode::LeftToRight,
+            PayModeConfig::BothWays => PayMode::BothWays,
+            PayModeConfig::Anywhere => PayMode::AnywherePays,
+            PayModeConfig::Ways => PayMode::Ways,
+            PayModeConfig::Clusters => PayMode::Clusters { min_size: self.cluster_min_size.unwrap_or(1) },
+        };
+        let mut slot = Slot::new(self.build_reels(reels), self.rows, self.paylines.clone())
+            .expect("slot validated")
+            .with_pay_mode(pay_mode);
+        if let Some(bet_multiplier) = self.bet_multiplier {
+            slot = slot.with_bet_multiplier(bet_multiplier);
+        }
+        if let Some(multipliers) = &self.cascade_multipliers {
+            slot = slot.with_cascades(multipliers.clone());
+        }
+        if let Some(heights) = &self.reel_heights {
+            slot = slot.with_reel_heights(heights.clone()).expect("reel heights validated");
+        }
+        slot
+    }
+
+    // The base game on its own.
+    pub fn slot(&self) -> Result<Slot, ConfigError> {
+        self.validate()?;
+        Ok(self.build_slot(&self.reels))
+    }
+
+    // The base game with its features.
+    pub fn build(&self) -> Result<FeatureGame, ConfigError> {
+        let slot = self.slot()?;
+        let features = self
+            .features
+            .iter()
+            .map(|feature| match feature {
+                FeatureConfig::FreeSpins { trigger, spins, retrigger_spins, multiplier, multiplier_step, reels } => {
+                    Feature::FreeSpins {
+                        trigger: trigger.clone(),
+                        spins: *spins,
+                        retrigger_spins: *retrigger_spins,
+                        multiplier: multiplier.unwrap_or(1),
+                        multiplier_step: *multiplier_step,
+                        slot: reels.as_ref().map(|reels| Box::new(self.build_slot(reels))),
+                    }
+                }
+                FeatureConfig::HoldAndSpin { trigger, respins, values, full_screen_bonus } => Feature::HoldAndSpin {
+                    trigger: trigger.clone(),
+                    respins: *respins,
+                    values: values.clone(),
+                    full_screen_bonus: *full_screen_bonus,
+                },
+                FeatureConfig::PickEm { trigger, picks, prizes, end_tiles } => Feature::PickEm {
+                    trigger: trigger.clone(),
+                    picks: *picks,
+                    board: prizes
+                        .iter()
+                        .map(|&p| PickPrize::Credits(p))
+                        .chain(std::iter::repeat_n(PickPrize::End, *end_tiles))
+                        .collect(),
+                },
+            })
+            .collect();
+        Ok(FeatureGame::new(slot, features).expect("feature values validated"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const GAME: &str = r#"
+        name = "Test Sevens"
+        rows = 3
+        pay_mode = "left-to-right"
+        paylines = [[1, 1, 1], [0, 0, 0], [2, 2, 2]]
+
+        [[symbols]]
+        name = "seven"
+        pays = [0, 0, 100]
+
+        [[symbols]]
+        name = "wild"
+        kind = "wild"
+        pays = [0, 0, 250]
+        excludes = ["bonus"]
+        multiplier = 2
+
+        [[symbols]]
+        name = "bonus"
+        kind = "scatter"
+        pays = [0, 2, 5]
+        trigger_count = 3
+
+        [[symbols]]
+        name = "chest"
+        kind = "scatter"
+        pays = []
+        trigger_count = 2
+
+        [[reels]]
+        strip = ["seven", "wild", "bonus", "seven"]
+        weights = [1, 1, 1, 2]
+
+        [[reels]]
+        strip = ["seven", "wild", "bonus"]
+
+        [[reels]]
+        strip = ["seven", "bonus", "wild"]
+
+        [[features]]
+        type = "free-spins"
+        trigger = "bonus"
+        spins = 10
+        multiplier = 2
+
+        [[features]]
+        type = "pick-em"
+        trigger = "chest"
+        picks = 2
+        prizes = [10, 20]
+        end_tiles = 1
+    "#;
+
+    fn with(text: &str, from: &str, to: &str) -> Result<GameConfig, ConfigError> {
+        assert!(text.contains(from));
+        GameConfig::from_toml_str(&text.replacen(from, to, 1))
+    }
+
+    #[test]
+    fn test_load_and_build() {
+        let config = GameConfig::from_toml_str(GAME).unwrap();
+        assert_eq!(config.name, "Test Sevens");
+        let game = config.build().unwrap();
+        assert_eq!(game.slot.reels().len(), 3);
+        assert_eq!(game.slot.reels()[0].weights(), &[1, 1, 1, 2]);
+        assert_eq!(game.slot.paylines().len(), 3);
+        assert_eq!(game.slot.bet_multiplier(), 3);
+        assert!(game.slot.reels()[0].symbol(1).is_wild());
+        assert_eq!(game.slot.reels()[0].symbol(1).wild_multiplier(), 2);
+        assert_eq!(game.features().len(), 2);
+        let Feature::PickEm { board, .. } = &game.features()[1] else { panic!("expected pick-em") };
+        assert_eq!(board, &vec![PickPrize::Credits(10), PickPrize::Credits(20), PickPrize::End]);
+        // a full line of sevens on the middle row
+        let outcome = game.slot.outcome_at(vec![3, 2, 2]).unwrap();
+        assert!(outcome.wins.iter().any(|w| w.symbol == "seven" && w.count == 3 && w.multiplier == 100));
+    }
+
+    #[test]
+    fn test_unknown_strip_symbol() {
+        let err = with(GAME, r#"strip = ["seven", "wild", "bonus"]"#, r#"strip = ["seven", "cherry", "bonus"]"#).unwrap_err();
+        assert_eq!(err, ConfigError::UnknownSymbol { reel: 1, position: 1, symbol: "cherry".to_string() });
+        assert_eq!(err.to_string(), "reel 1 position 1: unknown symbol 'cherry'");
+    }
+
+    #[test]
+    fn test_payline_validation() {
+        let err = with(GAME, "[2, 2, 2]", "[2, 3, 2]").unwrap_err();
+        assert_eq!(err, ConfigError::PaylineOutOfRange { line: 2, reel: 1, row: 3 });
+        let err = with(GAME, "[2, 2, 2]", "[2, 2]").unwrap_err();
+        assert_eq!(err, ConfigError::PaylineLength { line: 2, expected: 3, found: 2 });
+    }
+
+    #[test]
+    fn test_monotonic_pays() {
+        let err = with(GAME, "pays = [0, 0, 100]", "pays = [0, 50, 10]").unwrap_err();
+        assert_eq!(err, ConfigError::NonMonotonicPays("seven".to_string()));
+    }
+
+    #[test]
+    fn test_other_errors() {
+        assert!(matches!(GameConfig::from_toml_str("name = 1"), Err(ConfigError::Parse(_))));
+        assert!(matches!(with(GAME, "weights = [1, 1, 1, 2]", "weights = [1, 1]"), Err(ConfigError::WeightCount { .. })));
+        assert!(matches!(with(GAME, "weights = [1, 1, 1, 2]", "weights = [0, 0, 0, 0]"), Err(ConfigError::ZeroWeight(0))));
+        assert_eq!(
+            with(GAME, "weights = [1, 1, 1, 2]", "weights = [4294967295, 1, 0, 0]").unwrap_err(),
+            ConfigError::WeightOverflow(0)
+        );
+        assert!(matches!(with(GAME, "trigger_count = 3", ""), Err(ConfigError::UnknownTrigger { feature: 0, .. })));
+        assert_eq!(
+            with(GAME, "trigger_count = 3", "trigger_count = 0").unwrap_err(),
+            ConfigError::ZeroTriggerCount("bonus".to_string())
+        );
+        assert_eq!(
+            with(GAME, "multiplier = 2\n", "multiplier = 0\n").unwrap_err(),
+            ConfigError::ZeroMultiplier("wild".to_string())
+        );
+        assert!(matches!(with(GAME, "name = \"wild\"", "name = \"seven\""), Err(ConfigError::DuplicateSymbol(_))));
+        assert!(matches!(
+            with(GAME, "pays = [0, 0, 100]", "pays = [0, 0, 100]\n        multiplier = 3"),
+            Err(ConfigError::KindMismatch { field: "multiplier", .. })
+        ));
+        assert_eq!(
+            with(GAME, "\"left-to-right\"", "\"clusters\"").unwrap_err(),
+            ConfigError::MissingClusterSize
+        );
+        assert!(matches!(with(GAME, "rows = 3", "rows = 3\n        colour = \"red\""), Err(ConfigError::Parse(_))));
+    }
+
+    #[test]
+    fn test_feature_and_symbol_references() {
+        assert_eq!(
+            with(GAME, "trigger = \"chest\"", "trigger = \"bonus\"").unwrap_err(),
+            ConfigError::DuplicateTrigger("bonus".to_string())
+        );
+        assert_eq!(
+            with(GAME, "excludes = [\"bonus\"]", "excludes = [\"bonsu\"]").unwrap_err(),
+            ConfigError::UnknownExclude { symbol: "wild".to_string(), excluded: "bonsu".to_string() }
+        );
+        let clusters = GAME.replacen("\"left-to-right\"", "\"clusters\"\n        cluster_min_size = 0", 1);
+        assert_eq!(GameConfig::from_toml_str(&clusters).unwrap_err(), ConfigError::ZeroClusterSize);
+
+        let hold_and_spin = |values: &str| {
+            let text = GAME.replacen(
+                "type = \"pick-em\"\n        trigger = \"chest\"\n        picks = 2\n        prizes = [10, 20]\n        end_tiles = 1",
+                &format!("type = \"hold-and-spin\"\n        trigger = \"chest\"\n        respins = 3\n        values = {values}"),
+                1,
+            );
+            assert_ne!(text, GAME);
+            GameConfig::from_toml_str(&text)
+        };
+        assert_eq!(hold_and_spin("[]").unwrap_err(), ConfigError::NoFeatureValues(1));
+        assert_eq!(hold_and_spin("[[5, 0], [10, 0]]").unwrap_err(), ConfigError::NoFeatureValues(1));
+        assert!(hold_and_spin("[[5, 0], [10, 1]]").unwrap().build().is_ok());
+    }
+
+    #[test]
+    fn test_ways_with_heights() {
+        let text = GAME
+            .replacen("\"left-to-right\"", "\"ways\"", 1)
+            .replacen("paylines = [[1, 1, 1], [0, 0, 0], [2, 2, 2]]", "reel_heights = [[[2, 1], [3, 1]], [[3, 1]], [[3, 1]]]", 1);
+        let game = GameConfig::from_toml_str(&text).unwrap().build().unwrap();
+        assert_eq!(game.slot.pay_mode(), PayMode::Ways);
+        assert_eq!(game.slot.reel_heights().unwrap()[0], vec![(2, 1), (3, 1)]);
+    }
+}
@@ -0,0 +1,650 @@
+//! Bonus features around a `Slot`: free spins, hold-and-spin respins and pick-em games.
+//!
+//! Features are keyed by the name of a scatter symbol; when a base-game spin reports that
+//! scatter in `SpinOutcome::triggered`, the feature is queued. `FeatureGame` is the state
+//! machine that carries remaining spins, multipliers and credits from one step to the next.
+//! All awards are in multiples of the base bet, like `SpinOutcome::total_multiplier`.
+
+use std::collections::VecDeque;
+
+use rand::Rng;
+use rand::distr::Distribution;
+use rand::distr::weighted::WeightedIndex;
+use rand::seq::SliceRandom;
+
+use crate::slots::{Slot, SpinOutcome};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PickPrize {
+    Credits(u64),
+    /// ends the pick-em game immediately
+    End,
+}
+
+#[derive(Clone)]
+pub enum Feature {
+    FreeSpins {
+        trigger: String,
+        spins: u32,
+        /// spins added when the trigger lands again during the feature
+        retrigger_spins: u32,
+        multiplier: u32,
+        /// added to the multiplier on every retrigger
+        multiplier_step: u32,
+        /// reel set for the free game; the base game's `Slot` when None
+        slot: Option<Box<Slot>>,
+    },
+    HoldAndSpin {
+        trigger: String,
+        /// respins granted at the start, and again whenever a new cash symbol lands
+        respins: u32,
+        /// weighted (value, weight) table for each cash symbol
+        values: Vec<(u64, u32)>,
+        /// paid on top when every position is filled
+        full_screen_bonus: u64,
+    },
+    PickEm {
+        trigger: String,
+        picks: u32,
+        board: Vec<PickPrize>,
+    },
+}
+
+impl Feature {
+    pub fn trigger(&self) -> &str {
+        match self {
+            Feature::FreeSpins { trigger, .. }
+            | Feature::HoldAndSpin { trigger, .. }
+            | Feature::PickEm { trigger, .. } => trigger,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum FeatureState {
+    Idle,
+    FreeSpins {
+        feature: usize,
+        remaining: u32,
+        multiplier: u32,
+        credits: u64,
+    },
+    HoldAndSpin {
+        feature: usize,
+        /// cash value locked at each position: `locked[reel][row]`
+        locked: Vec<Vec<Option<u64>>>,
+        respins: u32,
+    },
+    PickEm {
+        feature: usize,
+        board: Vec<PickPrize>,
+        revealed: Vec<bool>,
+        picks_left: u32,
+        credits: u64,
+    },
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum FeatureEvent {
+    FreeSpin { outcome: SpinOutcome, award: u64, retriggered: bool },
+    Respin { landed: usize },
+    Pick { position: usize, prize: PickPrize },
+    /// a feature finished, paying `award`
+    Completed { feature: usize, award: u64 },
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct PlayOutcome {
+    pub base: SpinOutcome,
+    pub events: Vec<FeatureEvent>,
+    /// sum of every feature completed as a result of this spin
+    pub feature_award: u64,
+}
+
+impl PlayOutcome {
+    pub fn total_multiplier(&self) -> u64 {
+        self.base.total_multiplier().saturating_add(self.feature_award)
+    }
+}
+
+// Free spins one base spin may lead to unless `with_max_free_spins` says otherwise.
+pub const DEFAULT_MAX_FREE_SPINS: u32 = 10_000;
+
+pub struct FeatureGame {
+    pub slot: Slot,
+    features: Vec<Feature>,
+    state: FeatureState,
+    /// queued features, each with the window that triggered it to seed hold-and-spin
+    pending: VecDeque<(usize, SpinOutcome)>,
+    /// free spins played since the last feature sequence began
+    free_spins_played: u32,
+    max_free_spins: u32,
+}
+
+impl FeatureGame {
+    // Returns None if a hold-and-spin feature has no value with a positive weight.
+    pub fn new(slot: Slot, features: Vec<Feature>) -> Option<Self> {
+        let valid = features.iter().all(|f| match f {
+            Feature::HoldAndSpin { values, .. } => values.iter().any(|&(_, w)| w > 0),
+            _ => true,
+        });
+        valid.then(|| Self {
+            slot,
+            features,
+            state: FeatureState::Idle,
+            pending: VecDeque::new(),
+            free_spins_played: 0,
+            max_free_spins: DEFAULT_MAX_FREE_SPINS,
+        })
+    }
+
+    // Cap the free spins one trigger can lead to, retriggers and chained features
+    // included. A feature that reaches the cap ends and pays what it has won so far, as
+    // does any free-spins feature queued behind it.
+    pub fn with_max_free_spins(mut self, max_free_spins: u32) -> Self {
+        self.max_free_spins = max_free_spins;
+        self
+    }
+
+    pub fn max_free_spins(&self) -> u32 {
+        self.max_free_spins
+    }
+
+    pub fn features(&self) -> &[Feature] {
+        &self.features
+    }
+
+    pub fn state(&self) -> &FeatureState {
+        &self.state
+    }
+
+    // True while a feature is running or queued.
+    pub fn in_feature(&self) -> bool {
+        self.state != FeatureState::Idle || !self.pending.is_empty()
+    }
+
+    pub fn play(&mut self) -> PlayOutcome {
+        let mut rng = rand::rng();
+        self.play_with(&mut rng)
+    }
+
+    // One base-game spin, with every feature it triggers played to completion, metered
+    // on the base slot.
+    pub fn play_with<R: Rng + ?Sized>(&mut self, rng: &mut R) -> PlayOutcome {
+        let base = self.slot.spin_with(rng);
+        self.queue_triggers(&base);
+        let mut events = Vec::new();
+        while self.in_feature() {
+            events.extend(self.step_with(rng));
+        }
+        let feature_award = events
+            .iter()
+            .map(|e| match e {
+                FeatureEvent::Completed { award, .. } => *award,
+                _ => 0,
+            })
+            .fold(0, u64::saturating_add);
+        self.slot.record_with(&base, feature_award, rng);
+        PlayOutcome { base, events, feature_award }
+    }
+
+    // Queue the features a spin triggered. Returns how many were queued. Triggers from
+    // outside a feature start a new sequence with the full free-spin allowance.
+    pub fn queue_triggers(&mut self, outcome: &SpinOutcome) -> usize {
+        if !self.in_feature() {
+            self.free_spins_played = 0;
+        }
+        let before = self.pending.len();
+        for name in &outcome.triggered {
+            if let Some(i) = self.features.iter().position(|f| f.trigger() == name) {
+                self.pending.push_back((i, outcome.clone()));
+            }
+        }
+        self.pending.len() - before
+    }
+
+    // Advance the running feature by one free spin, respin or pick, starting the next
+    // queued feature if none is running. The step that ends a feature also returns its
+    // `Completed` event. Returns nothing when idle with no feature queued.
+    pub fn step_with<R: Rng + ?Sized>(&mut self, rng: &mut R) -> Vec<FeatureEvent> {
+        if self.state == FeatureState::Idle {
+            let Some((feature, trigger_window)) = self.pending.pop_front() else {
+                return Vec::new();
+            };
+            self.state = self.start(feature, &trigger_window, rng);
+        }
+        match std::mem::replace(&mut self.state, FeatureState::Idle) {
+            FeatureState::FreeSpins { feature, remaining, multiplier, credits } => {
+                self.free_spin(feature, remaining, multiplier, credits, rng)
+            }
+            FeatureState::HoldAndSpin { feature, locked, respins } => self.respin(feature, locked, respins, rng),
+            FeatureState::PickEm { feature, board, revealed, picks_left, credits } => {
+                self.pick(feature, board, revealed, picks_left, credits, rng)
+            }
+            FeatureState::Idle => Vec::new(),
+        }
+    }
+
+    fn start<R: Rng + ?Sized>(&self, feature: usize, trigger_window: &SpinOutcome, rng: &mut R) -> FeatureState {
+        match &self.features[feature] {
+            Feature::FreeSpins { spins, multiplier, .. } => FeatureState::FreeSpins {
+                feature,
+                remaining: *spins,
+                multiplier: *multiplier,
+                credits: 0,
+            },
+            Feature::HoldAndSpin { trigger, respins, values, .. } => {
+                let locked = trigger_window
+                    .window
+                    .iter()
+                    .map(|col| col.iter().map(|s| (s.name() == trigger).then(|| pick_value(values, rng))).collect())
+                    .collect();
+                FeatureState::HoldAndSpin { feature, locked, respins: *respins }
+            }
+            Feature::PickEm { picks, board, .. } => {
+                let mut board = board.clone();
+                board.shuffle(rng);
+                let revealed = vec![false; board.len()];
+                FeatureState::PickEm { feature, board, revealed, picks_left: *picks, credits: 0 }
+            }
+        }
+    }
+
+    fn free_spin<R: Rng + ?Sized>(
+        &mut self,
+        feature: usize,
+        remaining: u32,
+        mut multiplier: u32,
+        mut credits: u64,
+        rng: &mut R,
+    ) -> Vec<FeatureEvent> {
+        let Feature::FreeSpins { trigger, retrigger_spins, multiplier_step, slot, .. } = &self.features[feature] else {
+            unreachable!("state matches feature kind");
+        };
+        if remaining == 0 || self.free_spins_played >= self.max_free_spins {
+            return vec![FeatureEvent::Completed { feature, award: credits }];
+        }
+        self.free_spins_played += 1;
+        let outcome = slot.as_deref().unwrap_or(&self.slot).spin_with(rng);
+        let award = outcome.total_multiplier().saturating_mul(multiplier as u64);
+        credits = credits.saturating_add(award);
+
+        let retriggered = outcome.triggered.iter().any(|t| t == trigger);
+        let mut remaining = remaining.saturating_sub(1);
+        if retriggered {
+            remaining = remaining.saturating_add(*retrigger_spins);
+            multiplier = multiplier.saturating_add(*multiplier_step);
+        }
+        // other features landing during free spins play once this one ends
+        let others: Vec<(usize, SpinOutcome)> = outcome
+            .triggered
+            .iter()
+            .filter(|t| *t != trigger)
+            .filter_map(|name| self.features.iter().position(|f| f.trigger() == name))
+            .map(|i| (i, outcome.clone()))
+            .collect();
+        self.pending.extend(others);
+
+        let mut events = vec![FeatureEvent::FreeSpin { outcome, award, retriggered }];
+        if remaining == 0 || self.free_spins_played >= self.max_free_spins {
+            events.push(FeatureEvent::Completed { feature, award: credits });
+        } else {
+            self.state = FeatureState::FreeSpins { feature, remaining, multiplier, credits };
+        }
+        events
+    }
+
+    // Every empty position spins its own reel; a cash symbol locks and resets the respins.
+    fn respin<R: Rng + ?Sized>(
+        &mut self,
+        feature: usize,
+        mut locked: Vec<Vec<Option<u64>>>,
+        respins: u32,
+        rng: &mut R,
+    ) -> Vec<FeatureEvent> {
+        let Feature::HoldAndSpin { trigger, respins: reset, values, full_screen_bonus } = &self.features[feature] else {
+            unreachable!("state matches feature kind");
+        };
+        if respins == 0 {
+            let award = locked.iter().flatten().flatten().fold(0, |sum, &value| u64::saturating_add(sum, value));
+            return vec![FeatureEvent::Completed { feature, award }];
+        }
+        let mut landed = 0;
+        for (reel, column) in self.slot.reels().iter().zip(locked.iter_mut()) {
+            for cell in column.iter_mut().filter(|c| c.is_none()) {
+                if reel.symbol(reel.stop_with(rng)).name() == trigger {
+                    *cell = Some(pick_value(values, rng));
+                    landed += 1;
+                }
+            }
+        }
+        let full = locked.iter().flatten().all(|c| c.is_some());
+        let respins = if landed > 0 { *reset } else { respins.saturating_sub(1) };
+
+        let mut events = vec![FeatureEvent::Respin { landed }];
+        if full || respins == 0 {
+            let mut award = locked.iter().flatten().flatten().fold(0, |sum, &value| u64::saturating_add(sum, value));
+            if full {
+                award = award.saturating_add(*full_screen_bonus);
+            }
+            events.push(FeatureEvent::Completed { feature, award });
+        } else {
+            self.state = FeatureState::HoldAndSpin { feature, locked, respins };
+        }
+        events
+    }
+
+    fn pick<R: Rng + ?Sized>(
+        &mut self,
+        feature: usize,
+        board: Vec<PickPrize>,
+        mut revealed: Vec<bool>,
+        picks_left: u32,
+        mut credits: u64,
+        rng: &mut R,
+    ) -> Vec<FeatureEvent> {
+        let hidden: Vec<usize> = (0..board.len()).filter(|&i| !revealed[i]).collect();
+        if picks_left == 0 || hidden.is_empty() {
+            return vec![FeatureEvent::Completed { feature, award: credits }];
+        }
+        let position = hidden[rng.random_range(0..hidden.len())];
+        revealed[position] = true;
+        let prize = board[position];
+        if let PickPrize::Credits(c) = prize {
+            credits = credits.saturating_add(c);
+        }
+        let picks_left = picks_left.saturating_sub(1);
+
+        let mut events = vec![FeatureEvent::Pick { position, prize }];
+        if prize == PickPrize::End || picks_left == 0 || hidden.len() == 1 {
+            events.push(FeatureEvent::Completed { feature, award: credits });
+        } else {
+            self.state = FeatureState::PickEm { feature, board, revealed, picks_left, credits };
+        }
+        events
+    }
+}
+
+fn pick_value<R: Rng + ?Sized>(values: &[(u64, u32)], rng: &mut R) -> u64 {
+    let dist = WeightedIndex::new(values.iter().map(|&(_, w)| w)).expect("values validated on construction");
+    values[dist.sample(rng)].0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::slots::{Reel, Symbol};
+    use rand::SeedableRng;
+    use rand::rngs::StdRng;
+
+    fn scatter(trigger_count: usize) -> Symbol {
+        Symbol::scatter("bonus", 0, Some(trigger_count))
+    }
+
+    // three reels that always show a full line of `symbol`
+    fn fixed(symbol: Symbol) -> Slot {
+        let reel = || Reel::new(vec![symbol.clone()]).unwrap();
+        Slot::new(vec![reel(), reel(), reel()], 1, vec![vec![0, 0, 0]]).unwrap()
+    }
+
+    #[test]
+    fn test_no_trigger_no_feature() {
+        let mut game = FeatureGame::new(fixed(Symbol::new("bar", 10)), vec![]).unwrap();
+        let mut rng = StdRng::seed_from_u64(1);
+        let outcome = game.play_with(&mut rng);
+        assert!(outcome.events.is_empty());
+        assert_eq!(outcome.feature_award, 0);
+        assert_eq!(outcome.total_multiplier(), 10);
+        assert!(game.step_with(&mut rng).is_empty());
+    }
+
+    #[test]
+    fn test_free_spins_with_separate_reels_and_multiplier() {
+        let free_game = fixed(Symbol::new("bar", 10));
+        let mut game = FeatureGame::new(
+            fixed(scatter(3)),
+            vec![Feature::FreeSpins {
+                trigger: "bonus".to_string(),
+                spins: 5,
+                retrigger_spins: 5,
+                multiplier: 3,
+                multiplier_step: 0,
+                slot: Some(Box::new(free_game)),
+            }],
+        ).unwrap();
+        let mut rng = StdRng::seed_from_u64(1);
+        let outcome = game.play_with(&mut rng);
+        assert_eq!(outcome.base.triggered, vec!["bonus".to_string()]);
+        let spins = outcome.events.iter().filter(|e| matches!(e, FeatureEvent::FreeSpin { .. })).count();
+        assert_eq!(spins, 5);
+        assert_eq!(outcome.feature_award, 5 * 10 * 3);
+        assert_eq!(outcome.events.last(), Some(&FeatureEvent::Completed { feature: 0, award: 150 }));
+        assert_eq!(game.state(), &FeatureState::Idle);
+    }
+
+    #[test]
+    fn test_free_spin_awards_saturate() {
+        // every free spin retriggers and pays u32::MAX at a multiplier that cannot grow further
+        let mut game = FeatureGame::new(
+            fixed(Symbol::scatter("bonus", 1, Some(3))),
+            vec![Feature::FreeSpins {
+                trigger: "bonus".to_string(),
+                spins: 1,
+                retrigger_spins: 1,
+                multiplier: u32::MAX,
+                multiplier_step: u32::MAX,
+                slot: Some(Box::new(fixed(Symbol::scatter("bonus", u32::MAX, Some(3))))),
+            }],
+        )
+        .unwrap()
+        .with_max_free_spins(3);
+        let outcome = game.play_with(&mut StdRng::seed_from_u64(1));
+        assert_eq!(outcome.events.iter().filter(|e| matches!(e, FeatureEvent::FreeSpin { .. })).count(), 3);
+        assert_eq!(outcome.feature_award, u64::MAX);
+        assert_eq!(outcome.total_multiplier(), u64::MAX);
+    }
+
+    #[test]
+    fn test_free_spin_state_carries_between_steps() {
+        // the free game is the base game, so a quarter of the free spins retrigger
+        let reel = || Reel::weighted(vec![(scatter(1), 1), (Symbol::new("blank", 0), 3)]).unwrap();
+        let slot = Slot::new(vec![reel()], 1, vec![vec![0]]).unwrap();
+        let mut game = FeatureGame::new(
+            slot,
+            vec![Feature::FreeSpins {
+                trigger: "bonus".to_string(),
+                spins: 3,
+                retrigger_spins: 2,
+                multiplier: 1,
+                multiplier_step: 1,
+                slot: None,
+            }],
+        ).unwrap();
+        let mut rng = StdRng::seed_from_u64(4);
+        let trigger = game.slot.outcome_at(vec![0]).unwrap();
+        assert_eq!(game.queue_triggers(&trigger), 1);
+        assert!(game.in_feature());
+
+        let mut expected_remaining = 3;
+        let mut expected_multiplier = 1;
+        loop {
+            let events = game.step_with(&mut rng);
+            let FeatureEvent::FreeSpin { retriggered, .. } = &events[0] else { panic!("expected a free spin") };
+            expected_remaining -= 1;
+            if *retriggered {
+                expected_remaining += 2;
+                expected_multiplier += 1;
+            }
+            match game.state() {
+                FeatureState::FreeSpins { remaining, multiplier, .. } => {
+                    assert_eq!(*remaining, expected_remaining);
+                    assert_eq!(*multiplier, expected_multiplier);
+                }
+                FeatureState::Idle => {
+                    assert_eq!(expected_remaining, 0);
+                    assert!(matches!(events.last(), Some(FeatureEvent::Completed { .. })));
+                    break;
+                }
+                _ => panic!("unexpected state"),
+            }
+        }
+    }
+
+    #[test]
+    fn test_certain_retrigger_stops_at_cap() {
+        // every free spin shows the trigger again, so only the cap ends the feature
+        let mut game = FeatureGame::new(
+            fixed(scatter(3)),
+            vec![Feature::FreeSpins {
+                trigger: "bonus".to_string(),
+                spins: 5,
+                retrigger_spins: 5,
+                multiplier: 1,
+                multiplier_step: 1,
+                slot: None,
+            }],
+        )
+        .unwrap()
+        .with_max_free_spins(50);
+        let mut rng = StdRng::seed_from_u64(2);
+        for _ in 0..2 {
+            let outcome = game.play_with(&mut rng);
+            let spins = outcome.events.iter().filter(|e| matches!(e, FeatureEvent::FreeSpin { .. })).count();
+            assert_eq!(spins, 50);
+            assert!(matches!(outcome.events.last(), Some(FeatureEvent::Completed { feature: 0, .. })));
+            assert!(!game.in_feature());
+        }
+    }
+
+    #[test]
+    fn test_empty_feature_awards_nothing() {
+        let free_spins = Feature::FreeSpins {
+            trigger: "bonus".to_string(),
+            spins: 0,
+            retrigger_spins: 0,
+            multiplier: 1,
+            multiplier_step: 0,
+            slot: None,
+        };
+        let pick_em = Feature::PickEm { trigger: "bonus".to_string(), picks: 0, board: vec![PickPrize::Credits(10)] };
+        for feature in [free_spins, pick_em] {
+            let mut game = FeatureGame::new(fixed(scatter(3)), vec![feature]).unwrap();
+            let outcome = game.play_with(&mut StdRng::seed_from_u64(5));
+            assert_eq!(outcome.events, vec![FeatureEvent::Completed { feature: 0, award: 0 }]);
+        }
+    }
+
+    #[test]
+    fn test_hold_and_spin_needs_weighted_values() {
+        let feature = |values| Feature::HoldAndSpin { trigger: "coin".to_string(), respins: 3, values, full_screen_bonus: 0 };
+        assert!(FeatureGame::new(fixed(scatter(3)), vec![feature(vec![])]).is_none());
+        assert!(FeatureGame::new(fixed(scatter(3)), vec![feature(vec![(5, 0)])]).is_none());
+        assert!(FeatureGame::new(fixed(scatter(3)), vec![feature(vec![(5, 0), (7, 1)])]).is_some());
+    }
+
+    #[test]
+    fn test_each_feature_seeded_from_its_own_trigger() {
+        // bonus on reel one and coin on reel two trigger free spins, then hold-and-spin; the
+        // free spin triggers a pick-em, whose window must not seed the hold-and-spin
+        let base = Slot::new(
+            vec![
+                Reel::new(vec![Symbol::scatter("bonus", 0, Some(1))]).unwrap(),
+                Reel::new(vec![Symbol::scatter("coin", 0, Some(1))]).unwrap(),
+            ],
+            1,
+            vec![],
+        )
+        .unwrap();
+        let chest = || Reel::new(vec![Symbol::scatter("chest", 0, Some(1))]).unwrap();
+        let free_game = Slot::new(vec![chest(), chest()], 1, vec![]).unwrap();
+        let mut game = FeatureGame::new(
+            base,
+            vec![
+                Feature::FreeSpins {
+                    trigger: "bonus".to_string(),
+                    spins: 1,
+                    retrigger_spins: 0,
+                    multiplier: 1,
+                    multiplier_step: 0,
+                    slot: Some(Box::new(free_game)),
+                },
+                Feature::HoldAndSpin { trigger: "coin".to_string(), respins: 1, values: vec![(5, 1)], full_screen_bonus: 0 },
+                Feature::PickEm { trigger: "chest".to_string(), picks: 1, board: vec![PickPrize::Credits(7)] },
+            ],
+        )
+        .unwrap();
+        let outcome = game.play_with(&mut StdRng::seed_from_u64(6));
+        let respins: Vec<&FeatureEvent> = outcome.events.iter().filter(|e| matches!(e, FeatureEvent::Respin { .. })).collect();
+        // the coin was locked from the base window, so the one respin lands nothing new
+        assert_eq!(respins, vec![&FeatureEvent::Respin { landed: 0 }]);
+        assert!(outcome.events.contains(&FeatureEvent::Completed { feature: 1, award: 5 }));
+        assert!(outcome.events.contains(&FeatureEvent::Completed { feature: 2, award: 7 }));
+    }
+
+    #[test]
+    fn test_hold_and_spin_fills_screen() {
+        // every position is a cash symbol, so the trigger fills the screen on the first respin
+        let mut game = FeatureGame::new(
+            fixed(Symbol::scatter("coin", 0, Some(3))),
+            vec![Feature::HoldAndSpin {
+                trigger: "coin".to_string(),
+                respins: 3,
+                values: vec![(5, 1)],
+                full_screen_bonus: 1000,
+            }],
+        ).unwrap();
+        let mut rng = StdRng::seed_from_u64(1);
+        let outcome = game.play_with(&mut rng);
+        assert_eq!(
+            outcome.events,
+            vec![FeatureEvent::Respin { landed: 0 }, FeatureEvent::Completed { feature: 0, award: 15 + 1000 }]
+        );
+    }
+
+    #[test]
+    fn test_hold_and_spin_runs_out_of_respins() {
+        let reel = || Reel::new(vec![Symbol::scatter("coin", 0, Some(1)), Symbol::new("blank", 0)]).unwrap();
+        let slot = Slot::new(vec![reel(), reel()], 1, vec![]).unwrap();
+        let mut game = FeatureGame::new(
+            slot,
+            vec![Feature::HoldAndSpin {
+                trigger: "coin".to_string(),
+                respins: 3,
+                values: vec![(2, 1), (7, 1)],
+                full_screen_bonus: 0,
+            }],
+        ).unwrap();
+        let mut rng = StdRng::seed_from_u64(8);
+        // coin on reel one, blank on reel two
+        game.queue_triggers(&game.slot.outcome_at(vec![0, 1]).unwrap());
+        let mut events = Vec::new();
+        while game.in_feature() {
+            events.extend(game.step_with(&mut rng));
+        }
+        let Some(FeatureEvent::Completed { award, .. }) = events.last() else { panic!("feature did not finish") };
+        let landed: usize = events.iter().map(|e| if let FeatureEvent::Respin { landed } = e { *landed } else { 0 }).sum();
+        assert!(landed <= 1);
+        assert!([2, 7, 4, 9, 14].contains(award));
+    }
+
+    #[test]
+    fn test_pick_em() {
+        let board = vec![PickPrize::Credits(10), PickPrize::Credits(20), PickPrize::Credits(30), PickPrize::End];
+        let mut game = FeatureGame::new(
+            fixed(scatter(3)),
+            vec![Feature::PickEm { trigger: "bonus".to_string(), picks: 2, board }],
+        ).unwrap();
+        let mut rng = StdRng::seed_from_u64(3);
+        for _ in 0..20 {
+            let outcome = game.play_with(&mut rng);
+            let picks: Vec<PickPrize> = outcome
+                .events
+                .iter()
+                .filter_map(|e| if let FeatureEvent::Pick { prize, .. } = e { Some(*prize) } else { None })
+                .collect();
+            assert!(!picks.is_empty() && picks.len() <= 2);
+            let credits: u64 = picks.iter().map(|p| if let PickPrize::Credits(c) = p { *c } else { 0 }).sum();
+            assert_eq!(outcome.feature_award, credits);
+        }
+    }
+}
@@ -0,0 +1,344 @@
+//! Per-machine accounting meters, as kept on a slot floor.
+//!
+//! Every game played through `Slot::play_with` (or `FeatureGame::play_with`) is metered
+//! in cents: the total bet goes to coin-in, wins go to coin-out unless they reach the
+//! machine's hand-pay limit, in which case an attendant pays them and they go to the
+//! hand-paid meter instead. Meters only ever count up; a shift report is the difference
+//! between two snapshots (`Meters::since`). A machine linked to a progressive feeds every
+//! bet to the pool, and any jackpot it hits is hand-paid.
+
+use std::collections::BTreeMap;
+use std::fmt;
+
+use rand::Rng;
+
+use crate::jackpot::{JackpotAward, JackpotPool};
+use crate::slots::{Slot, SpinOutcome};
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Meters {
+    pub coin_in: u64,
+    /// wins paid by the machine
+    pub coin_out: u64,
+    pub games_played: u64,
+    pub games_won: u64,
+    /// number of wins at or over the hand-pay limit
+    pub hand_pays: u64,
+    pub hand_paid: u64,
+    /// features triggered on the base game, by trigger symbol
+    pub features: BTreeMap<String, u64>,
+    /// expected hold on the coin-in so far, in cents; zero unless the slot has a theoretical RTP
+    pub theoretical_hold: f64,
+}
+
+impl Meters {
+    // Amount the machine has kept: coin-in less everything paid out.
+    pub fn actual_hold(&self) -> i64 {
+        self.coin_in as i64 - self.coin_out as i64 - self.hand_paid as i64
+    }
+
+    pub fn actual_hold_percent(&self) -> Option<f64> {
+        (self.coin_in > 0).then(|| self.actual_hold() as f64 / self.coin_in as f64 * 100.0)
+    }
+
+    pub fn theoretical_hold_percent(&self) -> Option<f64> {
+        (self.coin_in > 0).then(|| self.theoretical_hold / self.coin_in as f64 * 100.0)
+    }
+
+    pub fn hit_frequency(&self) -> Option<f64> {
+        (self.games_played > 0).then(|| self.games_won as f64 / self.games_played as f64)
+    }
+
+    // The activity between an `earlier` snapshot and this one. Returns None if any meter
+    // reads lower now than it did then, e.g. when the snapshots are passed the wrong way
+    // round or come from different machines.
+    pub fn since(&self, earlier: &Meters) -> Option<Meters> {
+        if earlier.features.keys().any(|name| !self.features.contains_key(name)) {
+            return None;
+        }
+        let mut features = BTreeMap::new();
+        for (name, &count) in &self.features {
+            let count = count.checked_sub(earlier.features.get(name).copied().unwrap_or(0))?;
+            if count > 0 {
+                features.insert(name.clone(), count);
+            }
+        }
+        Some(Meters {
+            coin_in: self.coin_in.checked_sub(earlier.coin_in)?,
+            coin_out: self.coin_out.checked_sub(earlier.coin_out)?,
+            games_played: self.games_played.checked_sub(earlier.games_played)?,
+            games_won: self.games_won.checked_sub(earlier.games_won)?,
+            hand_pays: self.hand_pays.checked_sub(earlier.hand_pays)?,
+            hand_paid: self.hand_paid.checked_sub(earlier.hand_paid)?,
+            features,
+            theoretical_hold: self.theoretical_hold - earlier.theoretical_hold,
+        })
+    }
+}
+
+fn dollars(cents: i64) -> String {
+    let sign = if cents < 0 { "-" } else { "" };
+    format!("{sign}${}.{:02}", cents.unsigned_abs() / 100, cents.unsigned_abs() % 100)
+}
+
+impl fmt::Display for Meters {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let percent = |p: Option<f64>| p.map_or("-".to_string(), |p| format!("{p:.2}%"));
+        writeln!(f, "Games played:      {}", self.games_played)?;
+        writeln!(f, "Games won:         {}", self.games_won)?;
+        writeln!(f, "Coin in:           {}", dollars(self.coin_in as i64))?;
+        writeln!(f, "Coin out:          {}", dollars(self.coin_out as i64))?;
+        writeln!(f, "Hand pays:         {} ({})", self.hand_pays, dollars(self.hand_paid as i64))?;
+        writeln!(f, "Actual hold:       {} ({})", dollars(self.actual_hold()), percent(self.actual_hold_percent()))?;
+        writeln!(
+            f,
+            "Theoretical hold:  {} ({})",
+            dollars(self.theoretical_hold.round() as i64),
+            percent(self.theoretical_hold_percent())
+        )?;
+        for (name, count) in &self.features {
+            writeln!(f, "Feature {name}: {count}")?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct GameRecord {
+    pub bet_cents: u64,
+    pub win_cents: u64,
+    /// true if the win reached the hand-pay limit
+    pub hand_paid: bool,
+    /// must-hit-by progressive hit by this game's bet
+    pub jackpot: Option<JackpotAward>,
+}
+
+impl Slot {
+    // Value of one base bet in cents. Defaults to 1.
+    pub fn with_denomination(mut self, cents: u64) -> Self {
+        self.denomination_cents = cents.max(1);
+        self
+    }
+
+    // Wins of at least `cents` are paid by hand rather than by the machine.
+    pub fn with_hand_pay_limit(mut self, cents: u64) -> Self {
+        self.hand_pay_limit_cents = Some(cents);
+        self
+    }
+
+    // RTP used for the theoretical hold meter, usually `ParSheet::total_rtp`.
+    pub fn with_theoretical_rtp(mut self, rtp: f64) -> Self {
+        self.theoretical_rtp = Some(rtp);
+        self
+    }
+
+    // Feed every bet to a progressive pool, contributing as `machine`.
+    pub fn with_jackpot(mut self, pool: JackpotPool, machine: impl Into<String>) -> Self {
+        self.jackpot = Some((pool, machine.into()));
+        self
+    }
+
+    pub fn jackpot(&self) -> Option<&JackpotPool> {
+        self.jackpot.as_ref().map(|(pool, _)| pool)
+    }
+
+    pub fn denomination_cents(&self) -> u64 {
+        self.denomination_cents
+    }
+
+    pub fn hand_pay_limit_cents(&self) -> Option<u64> {
+        self.hand_pay_limit_cents
+    }
+
+    pub fn theoretical_rtp(&self) -> Option<f64> {
+        self.theoretical_rtp
+    }
+
+    // Total bet per game in cents.
+    pub fn bet_cents(&self) -> u64 {
+        self.bet_multiplier as u64 * self.denomination_cents
+    }
+
+    pub fn meters(&self) -> &Meters {
+        &self.meters
+    }
+
+    // A copy of the meters for a shift report.
+    pub fn snapshot(&self) -> Meters {
+        self.meters.clone()
+    }
+
+    pub fn play(&mut self) -> SpinOutcome {
+        let mut rng = rand::rng();
+        self.play_with(&mut rng)
+    }
+
+    // Spin and meter one paid game.
+    pub fn play_with<R: Rng + ?Sized>(&mut self, rng: &mut R) -> SpinOutcome {
+        let outcome = self.spin_with(rng);
+        self.record_with(&outcome, 0, rng);
+        outcome
+    }
+
+    pub fn record(&mut self, outcome: &SpinOutcome, feature_award: u64) -> GameRecord {
+        let mut rng = rand::rng();
+        self.record_with(outcome, feature_award, &mut rng)
+    }
+
+    // Meter one paid game: its base outcome plus any feature award, in base-bet multiples.
+    // The bet is also fed to the linked progressive, if any.
+    pub fn record_with<R: Rng + ?Sized>(&mut self, outcome: &SpinOutcome, feature_award: u64, rng: &mut R) -> GameRecord {
+        let bet = self.bet_cents();
+        let win = outcome.total_multiplier().saturating_add(feature_award).saturating_mul(self.denomination_cents);
+        let meters = &mut self.meters;
+        meters.coin_in += bet;
+        meters.games_played += 1;
+        if win > 0 {
+            meters.games_won += 1;
+        }
+        let hand_paid = self.hand_pay_limit_cents.is_some_and(|limit| win >= limit);
+        if hand_paid {
+            meters.hand_pays += 1;
+            meters.hand_paid += win;
+        } else {
+            meters.coin_out += win;
+        }
+        for name in &outcome.triggered {
+            *meters.features.entry(name.clone()).or_insert(0) += 1;
+        }
+        if let Some(rtp) = self.theoretical_rtp {
+            meters.theoretical_hold += bet as f64 * (1.0 - rtp);
+        }
+        let jackpot = self.jackpot.as_ref().and_then(|(pool, machine)| pool.contribute_with(machine, bet, rng));
+        if let Some(award) = &jackpot {
+            meters.hand_pays += 1;
+            meters.hand_paid += award.amount_cents;
+        }
+        GameRecord { bet_cents: bet, win_cents: win, hand_paid, jackpot }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::slots::features::FeatureGame;
+    use crate::slots::par_sheet::ParSheet;
+    use crate::slots::{Reel, Symbol};
+    use rand::SeedableRng;
+    use rand::rngs::StdRng;
+
+    fn machine() -> Slot {
+        let reel = || {
+            Reel::new(vec![
+                Symbol::new("seven", 30),
+                Symbol::new("cherry", 0).with_pays(vec![0, 2, 5]),
+                Symbol::new("blank", 0),
+                Symbol::scatter("bonus", 0, Some(1)),
+            ]).unwrap()
+        };
+        Slot::new(vec![reel(), reel(), reel()], 1, vec![vec![0, 0, 0]]).unwrap()
+    }
+
+    #[test]
+    fn test_record_single_games() {
+        let mut slot = machine().with_denomination(25).with_hand_pay_limit(500);
+        // cherry, cherry, blank pays 2
+        slot.record(&slot.outcome_at(vec![1, 1, 2]).unwrap(), 0);
+        // blank line loses
+        slot.record(&slot.outcome_at(vec![2, 2, 2]).unwrap(), 0);
+        // three sevens: 30 x 25c = $7.50, hand-paid
+        assert!(slot.record(&slot.outcome_at(vec![0, 0, 0]).unwrap(), 0).hand_paid);
+        let m = slot.meters();
+        assert_eq!(m.games_played, 3);
+        assert_eq!(m.games_won, 2);
+        assert_eq!(m.coin_in, 75);
+        assert_eq!(m.coin_out, 50);
+        assert_eq!((m.hand_pays, m.hand_paid), (1, 750));
+        assert_eq!(m.actual_hold(), 75 - 50 - 750);
+        assert_eq!(m.theoretical_hold_percent(), Some(0.0));
+    }
+
+    #[test]
+    fn test_feature_counts_and_award() {
+        let mut slot = machine();
+        slot.record(&slot.outcome_at(vec![3, 2, 2]).unwrap(), 40);
+        assert_eq!(slot.meters().features.get("bonus"), Some(&1));
+        assert_eq!(slot.meters().coin_out, 40);
+        assert_eq!(slot.meters().hand_pays, 0);
+    }
+
+    #[test]
+    fn test_linked_progressive() {
+        let pool = JackpotPool::new("bank", 1_000, 1_000, 500).unwrap().with_must_hit_by(2_000).unwrap();
+        let mut bank: Vec<Slot> = (0..3)
+            .map(|i| machine().with_denomination(100).with_jackpot(pool.clone(), format!("slot-{i}")))
+            .collect();
+        let mut rng = StdRng::seed_from_u64(36);
+        let mut hits = 0;
+        for _ in 0..1_000 {
+            for slot in &mut bank {
+                let outcome = slot.spin_with(&mut rng);
+                if let Some(award) = slot.record_with(&outcome, 0, &mut rng).jackpot {
+                    assert!(award.winner.starts_with("slot-"));
+                    hits += 1;
+                }
+            }
+        }
+        assert!(hits > 0);
+        assert_eq!(pool.history().len(), hits);
+        assert!(bank[0].jackpot().unwrap().same_pool(&pool));
+        let hand_pays: u64 = bank.iter().map(|s| s.meters().hand_pays).sum();
+        assert_eq!(hand_pays as usize, hits);
+        assert_eq!(pool.total_contributed(), 3_000 * 5);
+    }
+
+    #[test]
+    fn test_feature_game_is_metered() {
+        let mut game = FeatureGame::new(machine(), vec![]).unwrap();
+        let mut rng = StdRng::seed_from_u64(3);
+        for _ in 0..10 {
+            game.play_with(&mut rng);
+        }
+        assert_eq!(game.slot.meters().games_played, 10);
+        assert_eq!(game.slot.meters().coin_in, 10);
+    }
+
+    #[test]
+    fn test_actual_converges_to_theoretical() {
+        let rtp = ParSheet::generate(&machine()).total_rtp();
+        let mut slot = machine().with_theoretical_rtp(rtp);
+        let mut rng = StdRng::seed_from_u64(35);
+        for _ in 0..200_000 {
+            slot.play_with(&mut rng);
+        }
+        let m = slot.snapshot();
+        let theoretical = m.theoretical_hold_percent().unwrap();
+        assert!((theoretical - (1.0 - rtp) * 100.0).abs() < 1e-9);
+        assert!((m.actual_hold_percent().unwrap() - theoretical).abs() < 5.0);
+    }
+
+    #[test]
+    fn test_shift_report() {
+        let mut slot = machine();
+        let mut rng = StdRng::seed_from_u64(7);
+        for _ in 0..50 {
+            slot.play_with(&mut rng);
+        }
+        let start = slot.snapshot();
+        for _ in 0..30 {
+            slot.play_with(&mut rng);
+        }
+        let shift = slot.snapshot().since(&start).unwrap();
+        assert_eq!(start.since(&slot.snapshot()), None);
+        assert_eq!(shift.games_played, 30);
+        assert_eq!(shift.coin_in, 30);
+        assert_eq!(start.games_played + shift.games_played, slot.meters().games_played);
+        assert_eq!(
+            start.features.get("bonus").unwrap_or(&0) + shift.features.get("bonus").unwrap_or(&0),
+            *slot.meters().features.get("bonus").unwrap_or(&0)
+        );
+        let report = shift.to_string();
+        assert!(report.contains("Games played:      30"));
+        assert!(report.contains("Coin in:           $0.30"));
+    }
+}
@@ -1,13 +1,1083 @@
-pub struct Symbol{
+//! Module for slot machines: symbols, weighted reel strips and win evaluation.
+
+pub mod config;
+pub mod features;
+pub mod meters;
+pub mod par_sheet;
+
+use rand::Rng;
+use rand::distr::Distribution;
+use rand::distr::weighted::WeightedIndex;
+
+use crate::jackpot::JackpotPool;
+use crate::slots::meters::Meters;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum SymbolKind {
+    Regular,
+    /// Substitutes for any regular symbol not named in `excludes`. Multipliers of all the
+    /// wilds taking part in a win are multiplied together.
+    Wild { excludes: Vec<String>, multiplier: u32 },
+    /// Pays anywhere on screen by count, times the total bet. Landing `trigger_count` or
+    /// more also triggers the feature keyed by this symbol's name.
+    Scatter { trigger_count: Option<usize> },
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Symbol {
     name: String,
+    kind: SymbolKind,
+    /// paid (times the line bet) for a full line of this symbol
     payout_multiplier: u32,
+    /// optional pay table: `pays[k - 1]` is paid for `k` of a kind; longer runs than it
+    /// covers pay `payout_multiplier`
+    pays: Vec<u32>,
 }
 
-pub struct Reel{
+impl Symbol {
+    pub fn new(name: impl Into<String>, payout_multiplier: u32) -> Self {
+        Self {
+            name: name.into(),
+            kind: SymbolKind::Regular,
+            payout_multiplier,
+            pays: Vec::new(),
+        }
+    }
+
+    // A plain wild: substitutes for every regular symbol, no multiplier.
+    pub fn wild(name: impl Into<String>, payout_multiplier: u32) -> Self {
+        Self::new(name, payout_multiplier).with_kind(SymbolKind::Wild { excludes: Vec::new(), multiplier: 1 })
+    }
+
+    pub fn scatter(name: impl Into<String>, payout_multiplier: u32, trigger_count: Option<usize>) -> Self {
+        Self::new(name, payout_multiplier).with_kind(SymbolKind::Scatter { trigger_count })
+    }
+
+    pub fn with_kind(mut self, kind: SymbolKind) -> Self {
+        self.kind = kind;
+        self
+    }
+
+    // Pay shorter runs too, e.g. `vec![0, 0, 5, 20, 100]` for 3/4/5 of a kind on five reels.
+    // The full-line `payout_multiplier` is left as it is.
+    pub fn with_pays(mut self, pays: Vec<u32>) -> Self {
+        self.pays = pays;
+        self
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn kind(&self) -> &SymbolKind {
+        &self.kind
+    }
+
+    pub fn is_wild(&self) -> bool {
+        matches!(self.kind, SymbolKind::Wild { .. })
+    }
+
+    pub fn is_scatter(&self) -> bool {
+        matches!(self.kind, SymbolKind::Scatter { .. })
+    }
+
+    // Whether this symbol can stand in for `other` in a win.
+    pub fn substitutes_for(&self, other: &Symbol) -> bool {
+        match &self.kind {
+            _ if self.name == other.name => true,
+            SymbolKind::Wild { excludes, .. } => {
+                matches!(other.kind, SymbolKind::Regular) && !excludes.contains(&other.name)
+            }
+            _ => false,
+        }
+    }
+
+    pub fn wild_multiplier(&self) -> u32 {
+        match self.kind {
+            SymbolKind::Wild { multiplier, .. } => multiplier,
+            _ => 1,
+        }
+    }
+
+    pub fn payout_multiplier(&self) -> u32 {
+        self.payout_multiplier
+    }
+
+    pub fn pays(&self) -> &[u32] {
+        &self.pays
+    }
+
+    // Multiplier for `count` of a kind on a machine with `reels` reels.
+    pub fn pay(&self, count: usize, reels: usize) -> u32 {
+        if count == 0 {
+            return 0;
+        }
+        if self.pays.is_empty() {
+            return if count >= reels { self.payout_multiplier } else { 0 };
+        }
+        self.pays.get(count - 1).copied().unwrap_or(self.payout_multiplier)
+    }
+}
+
+// A reel strip; each stop has a weight so virtual reels can be modelled.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Reel {
     symbols: Vec<Symbol>,
+    weights: Vec<u32>,
+    /// stop sampler, built once with the reel
+    index: WeightedIndex<u32>,
+}
+
+impl Reel {
+    // Every stop equally likely. Returns None for an empty strip.
+    pub fn new(symbols: Vec<Symbol>) -> Option<Self> {
+        if symbols.is_empty() {
+            return None;
+        }
+        Self::weighted(symbols.into_iter().map(|symbol| (symbol, 1)).collect())
+    }
+
+    // Returns None for an empty strip, one with no weight, or one whose weights add up to more
+    // than `u32::MAX`.
+    pub fn weighted(stops: Vec<(Symbol, u32)>) -> Option<Self> {
+        let total: u64 = stops.iter().map(|(_, w)| *w as u64).sum();
+        if total == 0 || total > u32::MAX as u64 {
+            return None;
+        }
+        let (symbols, weights): (Vec<Symbol>, Vec<u32>) = stops.into_iter().unzip();
+        let index = WeightedIndex::new(&weights).ok()?;
+        Some(Self { symbols, weights, index })
+    }
+
+    pub fn len(&self) -> usize {
+        self.symbols.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.symbols.is_empty()
+    }
+
+    pub fn symbols(&self) -> &[Symbol] {
+        &self.symbols
+    }
+
+    pub fn weights(&self) -> &[u32] {
+        &self.weights
+    }
+
+    pub fn total_weight(&self) -> u64 {
+        self.weights.iter().map(|&w| w as u64).sum()
+    }
+
+    // Symbol at a strip position, wrapping around the end of the strip.
+    pub fn symbol(&self, position: usize) -> &Symbol {
+        &self.symbols[position % self.symbols.len()]
+    }
+
+    pub fn stop_with<R: Rng + ?Sized>(&self, rng: &mut R) -> usize {
+        self.index.sample(rng)
+    }
 }
-pub struct Slot{
+
+// How a slot turns the visible window into wins.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PayMode {
+    /// Paylines pay from the leftmost reel.
+    LeftToRight,
+    /// Paylines pay from the leftmost and from the rightmost reel.
+    BothWays,
+    /// Paylines are ignored; each symbol pays by how many times it shows anywhere.
+    AnywherePays,
+    /// Paylines are ignored; a symbol pays for every combination of positions on adjacent
+    /// reels from the left (243 ways on 5x3, 1024 on 5x4, or per spin with variable heights).
+    Ways,
+    /// Paylines are ignored; orthogonally connected groups of at least `min_size` matching
+    /// symbols pay by group size.
+    Clusters { min_size: usize },
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum WinKind {
+    Line { line: usize, right_to_left: bool },
+    Scatter,
+    Anywhere,
+    Ways { ways: u64 },
+    Cluster,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Win {
+    pub kind: WinKind,
+    pub symbol: String,
+    pub count: usize,
+    /// win in multiples of the base (line) bet, wild multipliers included
+    pub multiplier: u64,
+    /// (reel, row) of every symbol taking part in the win
+    pub positions: Vec<(usize, usize)>,
+}
+
+// One tumble: the winning symbols were removed and new ones dropped in.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Cascade {
+    pub window: Vec<Vec<Symbol>>,
+    pub wins: Vec<Win>,
+    /// progression multiplier applied to this cascade's wins
+    pub multiplier: u64,
+}
+
+impl Cascade {
+    pub fn total_multiplier(&self) -> u64 {
+        self.wins.iter().map(|w| w.multiplier).sum::<u64>() * self.multiplier
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct SpinOutcome {
+    /// strip position shown on the top row of each reel
+    pub stops: Vec<usize>,
+    /// visible symbols, one column per reel: `window[reel][row]`
+    pub window: Vec<Vec<Symbol>>,
+    pub wins: Vec<Win>,
+    /// names of scatter symbols that landed enough times to trigger their feature
+    pub triggered: Vec<String>,
+    /// tumbles that followed the initial window, when the slot cascades
+    pub cascades: Vec<Cascade>,
+}
+
+impl SpinOutcome {
+    pub fn symbol_at(&self, row: usize, reel: usize) -> &Symbol {
+        &self.window[reel][row]
+    }
+
+    // Total win in multiples of the base bet, cascades included.
+    pub fn total_multiplier(&self) -> u64 {
+        self.wins.iter().map(|w| w.multiplier).sum::<u64>()
+            + self.cascades.iter().map(Cascade::total_multiplier).sum::<u64>()
+    }
+
+    pub fn is_win(&self) -> bool {
+        self.total_multiplier() > 0
+    }
+
+    // Number of ways this window offers: the product of the visible reel heights.
+    pub fn ways(&self) -> u64 {
+        self.window.iter().map(|col| col.len() as u64).product()
+    }
+}
+
+#[derive(Clone)]
+pub struct Slot {
     reels: Vec<Reel>,
-    paylines: Vec<Vec<usize>>, // Each payline is a vector of reel indices
+    rows: usize,
+    paylines: Vec<Vec<usize>>, // Each payline is the row index shown on each reel
+    /// per-reel weighted (height, weight) choices when reel heights vary per spin
+    reel_heights: Option<Vec<Vec<(usize, u32)>>>,
+    pay_mode: PayMode,
+    /// total bet in multiples of the base bet; defaults to the number of paylines
+    bet_multiplier: u32,
+    /// when set, winning symbols tumble; entry `i` multiplies the wins of cascade `i + 1`
+    cascade_multipliers: Option<Vec<u64>>,
+    /// value of one base bet in cents
+    denomination_cents: u64,
+    hand_pay_limit_cents: Option<u64>,
+    theoretical_rtp: Option<f64>,
+    meters: Meters,
+    /// progressive fed by this machine, and the machine id it contributes under
+    jackpot: Option<(JackpotPool, String)>,
 }
 
+// Upper bound on tumbles per spin, so a degenerate strip cannot cascade forever.
+const MAX_CASCADES: usize = 1000;
+
+impl Slot {
+    // Returns None unless every reel has stops and every payline has one in-range row per reel.
+    pub fn new(reels: Vec<Reel>, rows: usize, paylines: Vec<Vec<usize>>) -> Option<Self> {
+        if reels.is_empty() || rows == 0 || reels.iter().any(|r| r.is_empty() || r.total_weight() == 0) {
+            return None;
+        }
+        if paylines
+            .iter()
+            .any(|line| line.len() != reels.len() || line.iter().any(|&row| row >= rows))
+        {
+            return None;
+        }
+        let bet_multiplier = paylines.len().max(1) as u32;
+        Some(Self {
+            reels,
+            rows,
+            paylines,
+            reel_heights: None,
+            pay_mode: PayMode::LeftToRight,
+            bet_multiplier,
+            cascade_multipliers: None,
+            denomination_cents: 1,
+            hand_pay_limit_cents: None,
+            theoretical_rtp: None,
+            meters: Meters::default(),
+            jackpot: None,
+        })
+    }
+
+    // Megaways-style: each spin picks every reel's height from its weighted choices.
+    // `rows` becomes the tallest possible reel. Returns None if a reel has no valid height
+    // or a payline would fall off a short reel.
+    pub fn with_reel_heights(mut self, reel_heights: Vec<Vec<(usize, u32)>>) -> Option<Self> {
+        if reel_heights.len() != self.reels.len()
+            || reel_heights.iter().any(|choices| {
+                choices.iter().map(|&(_, w)| w as u64).sum::<u64>() == 0 || choices.iter().any(|&(h, _)| h == 0)
+            })
+        {
+            return None;
+        }
+        let shortest: Vec<usize> = reel_heights
+            .iter()
+            .map(|choices| choices.iter().filter(|&&(_, w)| w > 0).map(|&(h, _)| h).min().unwrap_or(0))
+            .collect();
+        if self
+            .paylines
+            .iter()
+            .any(|line| line.iter().zip(&shortest).any(|(&row, &min)| row >= min))
+        {
+            return None;
+        }
+        self.rows = reel_heights.iter().flatten().map(|&(h, _)| h).max().unwrap_or(self.rows);
+        self.reel_heights = Some(reel_heights);
+        Some(self)
+    }
+
+    pub fn with_pay_mode(mut self, pay_mode: PayMode) -> Self {
+        self.pay_mode = pay_mode;
+        self
+    }
+
+    pub fn with_bet_multiplier(mut self, bet_multiplier: u32) -> Self {
+        self.bet_multiplier = bet_multiplier.max(1);
+        self
+    }
+
+    // Enable tumbling reels. The initial window pays 1x; cascade `i` pays
+    // `multipliers[i - 1]`, repeating the last entry once the list runs out.
+    pub fn with_cascades(mut self, multipliers: Vec<u64>) -> Self {
+        self.cascade_multipliers = Some(if multipliers.is_empty() { vec![1] } else { multipliers });
+        self
+    }
+
+    pub fn cascade_multipliers(&self) -> Option<&[u64]> {
+        self.cascade_multipliers.as_deref()
+    }
+
+    pub fn pay_mode(&self) -> PayMode {
+        self.pay_mode
+    }
+
+    pub fn bet_multiplier(&self) -> u32 {
+        self.bet_multiplier
+    }
+
+    pub fn reels(&self) -> &[Reel] {
+        &self.reels
+    }
+
+    pub fn rows(&self) -> usize {
+        self.rows
+    }
+
+    pub fn paylines(&self) -> &[Vec<usize>] {
+        &self.paylines
+    }
+
+    pub fn reel_heights(&self) -> Option<&[Vec<(usize, u32)>]> {
+        self.reel_heights.as_deref()
+    }
+
+    pub fn spin(&self) -> SpinOutcome {
+        let mut rng = rand::rng();
+        self.spin_with(&mut rng)
+    }
+
+    pub fn spin_with<R: Rng + ?Sized>(&self, rng: &mut R) -> SpinOutcome {
+        let heights = match &self.reel_heights {
+            Some(choices) => choices.iter().map(|c| pick_height(c, rng)).collect(),
+            None => vec![self.rows; self.reels.len()],
+        };
+        let stops: Vec<usize> = self.reels.iter().map(|r| r.stop_with(rng)).collect();
+        self.outcome_with_heights(stops, &heights).expect("one stop and height per reel")
+    }
+
+    // Build the outcome for a given set of stop positions, every reel `rows` high. Returns
+    // None unless there is one stop per reel.
+    pub fn outcome_at(&self, stops: Vec<usize>) -> Option<SpinOutcome> {
+        self.outcome_with_heights(stops, &vec![self.rows; self.reels.len()])
+    }
+
+    // Returns None unless there is one stop and one height per reel, and every payline fits
+    // the heights.
+    pub fn outcome_with_heights(&self, stops: Vec<usize>, heights: &[usize]) -> Option<SpinOutcome> {
+        if stops.len() != self.reels.len() || heights.len() != self.reels.len() {
+            return None;
+        }
+        let window = self.window_with_heights(&stops, heights);
+        let wins = self.evaluate(&window)?;
+        let triggered = self.triggered(&window);
+        let cascades = match &self.cascade_multipliers {
+            Some(multipliers) => self.cascade(&stops, window.clone(), &wins, multipliers),
+            None => Vec::new(),
+        };
+        Some(SpinOutcome {
+            stops,
+            window: to_owned_window(&window),
+            wins,
+            triggered,
+            cascades,
+        })
+    }
+
+    // Remove winning symbols, let the rest fall and refill each reel from the strip above
+    // its stop, until a window has no wins. Scatters stay put and only pay on the initial
+    // window.
+    fn cascade<'a>(
+        &'a self,
+        stops: &[usize],
+        mut window: Vec<Vec<&'a Symbol>>,
+        wins: &[Win],
+        multipliers: &[u64],
+    ) -> Vec<Cascade> {
+        let mut next_above: Vec<usize> = stops.to_vec();
+        let mut wins: Vec<Win> = wins.iter().filter(|w| w.kind != WinKind::Scatter).cloned().collect();
+        let mut cascades = Vec::new();
+        while !wins.is_empty() && cascades.len() < MAX_CASCADES {
+            for (reel, column) in window.iter_mut().enumerate() {
+                let strip = &self.reels[reel];
+                let survivors: Vec<&Symbol> = column
+                    .iter()
+                    .enumerate()
+                    .filter(|(row, _)| !wins.iter().any(|w| w.positions.contains(&(reel, *row))))
+                    .map(|(_, &s)| s)
+                    .collect();
+                let removed = column.len() - survivors.len();
+                let mut refill: Vec<&Symbol> = (1..=removed)
+                    .map(|i| strip.symbol(next_above[reel] + strip.len() * removed - i))
+                    .collect();
+                refill.reverse();
+                next_above[reel] = (next_above[reel] + strip.len() * removed - removed) % strip.len();
+                refill.extend(survivors);
+                *column = refill;
+            }
+            wins = self.pay_wins(&window);
+            let multiplier = multipliers[cascades.len().min(multipliers.len() - 1)];
+            cascades.push(Cascade { window: to_owned_window(&window), wins: wins.clone(), multiplier });
+        }
+        cascades
+    }
+
+    pub fn window_at(&self, stops: &[usize]) -> Vec<Vec<&Symbol>> {
+        self.window_with_heights(stops, &vec![self.rows; self.reels.len()])
+    }
+
+    pub fn window_with_heights(&self, stops: &[usize], heights: &[usize]) -> Vec<Vec<&Symbol>> {
+        self.reels
+            .iter()
+            .zip(stops)
+            .zip(heights)
+            .map(|((reel, &stop), &height)| (0..height).map(|row| reel.symbol(stop + row)).collect())
+            .collect()
+    }
+
+    // Returns None unless the window has one column per reel and every payline row shows.
+    pub fn evaluate(&self, window: &[Vec<&Symbol>]) -> Option<Vec<Win>> {
+        if !self.fits(window) {
+            return None;
+        }
+        let mut wins = self.pay_wins(window);
+        wins.extend(self.scatter_wins(window));
+        Some(wins)
+    }
+
+    fn fits(&self, window: &[Vec<&Symbol>]) -> bool {
+        window.len() == self.reels.len()
+            && self
+                .paylines
+                .iter()
+                .all(|line| line.iter().zip(window).all(|(&row, column)| row < column.len()))
+    }
+
+    // Wins from the pay mode alone, without scatters.
+    fn pay_wins(&self, window: &[Vec<&Symbol>]) -> Vec<Win> {
+        match self.pay_mode {
+            PayMode::LeftToRight => self.line_wins(window, false),
+            PayMode::BothWays => self.line_wins(window, true),
+            PayMode::AnywherePays => anywhere_wins(window),
+            PayMode::Ways => ways_wins(window),
+            PayMode::Clusters { min_size } => cluster_wins(window, min_size),
+        }
+    }
+
+    fn line_wins(&self, window: &[Vec<&Symbol>], both_ways: bool) -> Vec<Win> {
+        let reels = window.len();
+        let mut wins = Vec::new();
+        for (line, rows) in self.paylines.iter().enumerate() {
+            let mut symbols: Vec<&Symbol> = rows.iter().enumerate().map(|(reel, &row)| window[reel][row]).collect();
+            let left = best_run(&symbols, reels);
+            if let Some((symbol, count, multiplier)) = &left {
+                wins.push(Win {
+                    kind: WinKind::Line { line, right_to_left: false },
+                    symbol: symbol.name.clone(),
+                    count: *count,
+                    multiplier: *multiplier,
+                    positions: rows.iter().copied().enumerate().take(*count).collect(),
+                });
+            }
+            // a full line already paid from the left
+            if !both_ways || left.as_ref().is_some_and(|(_, count, _)| *count == reels) {
+                continue;
+            }
+            symbols.reverse();
+            if let Some((symbol, count, multiplier)) = best_run(&symbols, reels) {
+                wins.push(Win {
+                    kind: WinKind::Line { line, right_to_left: true },
+                    symbol: symbol.name.clone(),
+                    count,
+                    multiplier,
+                    positions: rows.iter().copied().enumerate().rev().take(count).collect(),
+                });
+            }
+        }
+        wins
+    }
+
+    fn scatter_wins(&self, window: &[Vec<&Symbol>]) -> Vec<Win> {
+        distinct_symbols(window)
+            .into_iter()
+            .filter(|s| s.is_scatter())
+            .filter_map(|scatter| {
+                let positions = positions_where(window, |s| s.name == scatter.name);
+                let count = positions.len();
+                let multiplier = scatter.pay(count, window.len()) as u64 * self.bet_multiplier as u64;
+                (multiplier > 0).then(|| Win {
+                    kind: WinKind::Scatter,
+                    symbol: scatter.name.clone(),
+                    count,
+                    multiplier,
+                    positions,
+                })
+            })
+            .collect()
+    }
+
+    // Scatter symbols that landed at least their trigger count.
+    pub fn triggered(&self, window: &[Vec<&Symbol>]) -> Vec<String> {
+        distinct_symbols(window)
+            .into_iter()
+            .filter_map(|symbol| match symbol.kind {
+                SymbolKind::Scatter { trigger_count: Some(needed) }
+                    if count_on_screen(window, |s| s.name == symbol.name) >= needed =>
+                {
+                    Some(symbol.name.clone())
+                }
+                _ => None,
+            })
+            .collect()
+    }
+}
+
+// Best win for a run starting at the first symbol: either the wilds paying as themselves,
+// or the first regular symbol with the wilds substituting. Returns (symbol, count, multiplier).
+fn best_run<'a>(symbols: &[&'a Symbol], reels: usize) -> Option<(&'a Symbol, usize, u64)> {
+    let first = *symbols.first()?;
+    let mut best: Option<(&Symbol, usize, u64)> = None;
+
+    if first.is_wild() {
+        let count = symbols.iter().take_while(|s| s.name == first.name).count();
+        let pay = first.pay(count, reels) as u64;
+        if pay > 0 {
+            best = Some((first, count, pay));
+        }
+    }
+
+    if let Some(target) = symbols.iter().copied().find(|s| !s.is_wild())
+        && !target.is_scatter()
+    {
+        let run: Vec<&&Symbol> = symbols.iter().take_while(|s| s.substitutes_for(target)).collect();
+        let wild_multiplier: u64 = run.iter().map(|s| s.wild_multiplier() as u64).product();
+        let pay = target.pay(run.len(), reels) as u64 * wild_multiplier;
+        if pay > best.map_or(0, |(_, _, p)| p) {
+            best = Some((target, run.len(), pay));
+        }
+    }
+    best
+}
+
+// Each regular symbol pays once for every matching symbol or substituting wild on screen.
+fn anywhere_wins(window: &[Vec<&Symbol>]) -> Vec<Win> {
+    let reels = window.len();
+    distinct_symbols(window)
+        .into_iter()
+        .filter(|s| matches!(s.kind, SymbolKind::Regular))
+        .filter_map(|target| {
+            let positions = positions_where(window, |s| s.substitutes_for(target));
+            let wild_multiplier: u64 = positions.iter().map(|&(r, row)| window[r][row].wild_multiplier() as u64).product();
+            let multiplier = target.pay(positions.len(), reels) as u64 * wild_multiplier;
+            (multiplier > 0).then(|| Win {
+                kind: WinKind::Anywhere,
+                symbol: target.name.clone(),
+                count: positions.len(),
+                multiplier,
+                positions,
+            })
+        })
+        .collect()
+}
+
+// Each regular symbol pays for the run of adjacent reels (from the left) showing it or a
+// substituting wild, times the number of ways. Wild multipliers weight the ways they sit on.
+fn ways_wins(window: &[Vec<&Symbol>]) -> Vec<Win> {
+    let reels = window.len();
+    distinct_symbols(window)
+        .into_iter()
+        .filter(|s| matches!(s.kind, SymbolKind::Regular))
+        .filter_map(|target| {
+            let mut count = 0;
+            let mut ways: u64 = 1;
+            let mut weighted_ways: u64 = 1;
+            let mut positions = Vec::new();
+            for (reel, column) in window.iter().enumerate() {
+                let matching: Vec<usize> = (0..column.len()).filter(|&row| column[row].substitutes_for(target)).collect();
+                if matching.is_empty() {
+                    break;
+                }
+                count += 1;
+                ways *= matching.len() as u64;
+                weighted_ways *= matching.iter().map(|&row| column[row].wild_multiplier() as u64).sum::<u64>();
+                positions.extend(matching.into_iter().map(|row| (reel, row)));
+            }
+            let multiplier = target.pay(count, reels) as u64 * weighted_ways;
+            (multiplier > 0).then(|| Win {
+                kind: WinKind::Ways { ways },
+                symbol: target.name.clone(),
+                count,
+                positions,
+                multiplier,
+            })
+        })
+        .collect()
+}
+
+// Flood-fill orthogonally connected groups of a regular symbol (wilds join any group they
+// touch). Each group of at least `min_size` pays the symbol's pay for the group size.
+fn cluster_wins(window: &[Vec<&Symbol>], min_size: usize) -> Vec<Win> {
+    let reels = window.len();
+    let mut wins = Vec::new();
+    for target in distinct_symbols(window).into_iter().filter(|s| matches!(s.kind, SymbolKind::Regular)) {
+        let mut seen: Vec<Vec<bool>> = window.iter().map(|col| vec![false; col.len()]).collect();
+        for (reel, column) in window.iter().enumerate() {
+            for row in 0..column.len() {
+                // start each group from an actual target symbol, not a lone wild
+                if seen[reel][row] || column[row].name != target.name {
+                    continue;
+                }
+                let mut group = Vec::new();
+                let mut stack = vec![(reel, row)];
+                seen[reel][row] = true;
+                while let Some((r, y)) = stack.pop() {
+                    group.push((r, y));
+                    let mut neighbours = vec![(r + 1, y), (r, y + 1)];
+                    if r > 0 {
+                        neighbours.push((r - 1, y));
+                    }
+                    if y > 0 {
+                        neighbours.push((r, y - 1));
+                    }
+                    for (nr, ny) in neighbours {
+                        if nr < reels && ny < window[nr].len() && !seen[nr][ny] && window[nr][ny].substitutes_for(target) {
+                            seen[nr][ny] = true;
+                            stack.push((nr, ny));
+                        }
+                    }
+                }
+                // wilds may be shared with another symbol's group
+                for &(r, y) in &group {
+                    if window[r][y].is_wild() {
+                        seen[r][y] = false;
+                    }
+                }
+                if group.len() < min_size {
+                    continue;
+                }
+                group.sort();
+                let wild_multiplier: u64 = group.iter().map(|&(r, y)| window[r][y].wild_multiplier() as u64).product();
+                let multiplier = target.pay(group.len(), reels) as u64 * wild_multiplier;
+                if multiplier > 0 {
+                    wins.push(Win {
+                        kind: WinKind::Cluster,
+                        symbol: target.name.clone(),
+                        count: group.len(),
+                        multiplier,
+                        positions: group,
+                    });
+                }
+            }
+        }
+    }
+    wins
+}
+
+fn pick_height<R: Rng + ?Sized>(choices: &[(usize, u32)], rng: &mut R) -> usize {
+    let dist = WeightedIndex::new(choices.iter().map(|&(_, w)| w)).expect("heights validated on construction");
+    choices[dist.sample(rng)].0
+}
+
+fn distinct_symbols<'a>(window: &[Vec<&'a Symbol>]) -> Vec<&'a Symbol> {
+    let mut seen: Vec<&Symbol> = Vec::new();
+    for &symbol in window.iter().flatten() {
+        if !seen.iter().any(|s| s.name == symbol.name) {
+            seen.push(symbol);
+        }
+    }
+    seen
+}
+
+fn count_on_screen(window: &[Vec<&Symbol>], matches: impl Fn(&Symbol) -> bool) -> usize {
+    window.iter().flatten().filter(|s| matches(s)).count()
+}
+
+fn positions_where(window: &[Vec<&Symbol>], matches: impl Fn(&Symbol) -> bool) -> Vec<(usize, usize)> {
+    window
+        .iter()
+        .enumerate()
+        .flat_map(|(reel, column)| column.iter().enumerate().map(move |(row, &s)| (reel, row, s)))
+        .filter(|&(_, _, s)| matches(s))
+        .map(|(reel, row, _)| (reel, row))
+        .collect()
+}
+
+fn to_owned_window(window: &[Vec<&Symbol>]) -> Vec<Vec<Symbol>> {
+    window.iter().map(|col| col.iter().map(|&s| s.clone()).collect()).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+    use rand::rngs::StdRng;
+
+    fn cherry() -> Symbol {
+        Symbol::new("cherry", 5).with_pays(vec![0, 2, 5])
+    }
+
+    fn bar() -> Symbol {
+        Symbol::new("bar", 50)
+    }
+
+    fn three_by_three() -> Slot {
+        let reel = || Reel::new(vec![cherry(), bar(), cherry(), Symbol::new("blank", 0)]).unwrap();
+        Slot::new(vec![reel(), reel(), reel()], 3, vec![vec![0, 0, 0], vec![1, 1, 1], vec![0, 1, 2]]).unwrap()
+    }
+
+    // lines in these tests are straight rows, line `n` running along row `n`
+    fn line_win(line: usize, symbol: &str, count: usize, multiplier: u64) -> Win {
+        Win {
+            kind: WinKind::Line { line, right_to_left: false },
+            symbol: symbol.to_string(),
+            count,
+            multiplier,
+            positions: (0..count).map(|reel| (reel, line)).collect(),
+        }
+    }
+
+    // single-row machine showing exactly `symbols`, one per reel
+    fn one_row(symbols: Vec<Symbol>) -> Slot {
+        let reels = symbols.into_iter().map(|s| Reel::new(vec![s]).unwrap()).collect::<Vec<_>>();
+        let line = vec![0; reels.len()];
+        Slot::new(reels, 1, vec![line]).unwrap()
+    }
+
+    #[test]
+    fn test_symbol_pay() {
+        assert_eq!(bar().pay(3, 3), 50);
+        assert_eq!(bar().pay(2, 3), 0);
+        assert_eq!(cherry().pay(1, 3), 0);
+        assert_eq!(cherry().pay(2, 3), 2);
+        assert_eq!(cherry().pay(3, 3), 5);
+        assert_eq!(cherry().payout_multiplier(), 5);
+        // a pay table leaves the full-line pay alone, which still pays longer runs
+        let seven = Symbol::new("seven", 100).with_pays(vec![0, 10]);
+        assert_eq!(seven.payout_multiplier(), 100);
+        assert_eq!(seven.pay(2, 3), 10);
+        assert_eq!(seven.pay(3, 3), 100);
+    }
+
+    #[test]
+    fn test_slot_validation() {
+        let reel = || Reel::new(vec![bar()]).unwrap();
+        assert!(Slot::new(vec![], 1, vec![]).is_none());
+        assert!(Slot::new(vec![reel(), reel()], 1, vec![vec![0]]).is_none());
+        assert!(Slot::new(vec![reel(), reel()], 1, vec![vec![0, 1]]).is_none());
+        assert!(Reel::new(vec![]).is_none());
+        assert!(Reel::weighted(vec![(bar(), 0)]).is_none());
+        assert!(Slot::new(vec![reel(), reel()], 1, vec![vec![0, 0]]).is_some());
+    }
+
+    #[test]
+    fn test_window_wraps_strip() {
+        let slot = three_by_three();
+        let outcome = slot.outcome_at(vec![3, 0, 1]).unwrap();
+        assert_eq!(outcome.symbol_at(0, 0).name(), "blank");
+        assert_eq!(outcome.symbol_at(1, 0).name(), "cherry");
+        assert_eq!(outcome.symbol_at(2, 0).name(), "bar");
+        assert_eq!(outcome.symbol_at(0, 2).name(), "bar");
+    }
+
+    #[test]
+    fn test_outcome_needs_a_stop_per_reel() {
+        let slot = three_by_three();
+        assert!(slot.outcome_at(vec![0, 0]).is_none());
+        assert!(slot.outcome_at(vec![0, 0, 0, 0]).is_none());
+        // the diagonal line needs a third row on the last reel
+        assert!(slot.outcome_with_heights(vec![0, 0, 0], &[3, 3, 2]).is_none());
+        assert!(slot.evaluate(&slot.window_at(&[0, 0])).is_none());
+    }
+
+    #[test]
+    fn test_payline_evaluation() {
+        let slot = three_by_three();
+        // top row: cherry cherry bar, middle: bar bar cherry, diagonal: cherry bar blank
+        let outcome = slot.outcome_at(vec![0, 2, 1]).unwrap();
+        assert_eq!(outcome.wins, vec![line_win(0, "cherry", 2, 2)]);
+        // all three reels show cherry / bar / cherry
+        let outcome = slot.outcome_at(vec![0, 0, 0]).unwrap();
+        assert_eq!(outcome.wins.len(), 2);
+        assert_eq!(outcome.wins[1], line_win(1, "bar", 3, 50));
+        assert_eq!(outcome.total_multiplier(), 55);
+        assert!(outcome.is_win());
+    }
+
+    #[test]
+    fn test_weighted_reel_stops() {
+        let reel = Reel::weighted(vec![(bar(), 0), (cherry(), 1), (bar(), 0)]).unwrap();
+        let slot = Slot::new(vec![reel.clone(), reel.clone(), reel], 1, vec![vec![0, 0, 0]]).unwrap();
+        let mut rng = StdRng::seed_from_u64(9);
+        for _ in 0..50 {
+            let outcome = slot.spin_with(&mut rng);
+            assert_eq!(outcome.stops, vec![1, 1, 1]);
+            assert_eq!(outcome.total_multiplier(), 5);
+        }
+        // the weights must fit the u32 sampler
+        assert!(Reel::weighted(vec![(bar(), u32::MAX), (cherry(), 1)]).is_none());
+        assert!(Reel::weighted(vec![(bar(), u32::MAX - 1), (cherry(), 1)]).is_some());
+        assert!(Reel::weighted(vec![]).is_none());
+    }
+
+    #[test]
+    fn test_wild_substitutes_and_multiplies() {
+        let wild = || Symbol::wild("wild", 0).with_kind(SymbolKind::Wild { excludes: vec![], multiplier: 2 });
+        let slot = one_row(vec![wild(), cherry(), wild()]);
+        let outcome = slot.outcome_at(vec![0, 0, 0]).unwrap();
+        assert_eq!(outcome.wins, vec![line_win(0, "cherry", 3, 5 * 4)]);
+    }
+
+    #[test]
+    fn test_wild_excludes_and_pays_as_itself() {
+        let wild = || {
+            Symbol::wild("wild", 100).with_kind(SymbolKind::Wild { excludes: vec!["bar".to_string()], multiplier: 1 })
+        };
+        // the wild does not complete bars, so only its own two-of-a-kind would count (and pays nothing)
+        let outcome = one_row(vec![wild(), bar(), bar()]).outcome_at(vec![0, 0, 0]).unwrap();
+        assert!(outcome.wins.is_empty());
+        // a full line of wilds pays the wild's own pay
+        let outcome = one_row(vec![wild(), wild(), wild()]).outcome_at(vec![0, 0, 0]).unwrap();
+        assert_eq!(outcome.wins, vec![line_win(0, "wild", 3, 100)]);
+        // wilds ahead of a cherry run: substitution wins
+        let outcome = one_row(vec![wild(), cherry(), cherry()]).outcome_at(vec![0, 0, 0]).unwrap();
+        assert_eq!(outcome.wins, vec![line_win(0, "cherry", 3, 5)]);
+    }
+
+    #[test]
+    fn test_scatter_pays_anywhere_and_triggers() {
+        let scatter = || Symbol::scatter("bonus", 0, Some(2)).with_pays(vec![0, 1, 10]);
+        let reel = || Reel::new(vec![scatter(), Symbol::new("blank", 0), Symbol::new("blank", 0)]).unwrap();
+        let slot = Slot::new(vec![reel(), reel(), reel()], 3, vec![vec![0, 0, 0], vec![1, 1, 1]]).unwrap();
+        assert_eq!(slot.bet_multiplier(), 2);
+        // scatters on rows 0, 1 and 2: no line win, 10x total bet scatter win
+        let outcome = slot.outcome_at(vec![0, 2, 1]).unwrap();
+        assert_eq!(outcome.wins.len(), 1);
+        assert_eq!(outcome.wins[0].kind, WinKind::Scatter);
+        assert_eq!(outcome.wins[0].count, 3);
+        assert_eq!(outcome.wins[0].multiplier, 20);
+        assert_eq!(outcome.triggered, vec!["bonus".to_string()]);
+    }
+
+    #[test]
+    fn test_both_ways() {
+        let slot = one_row(vec![bar(), Symbol::new("blank", 0), cherry(), cherry()]).with_pay_mode(PayMode::BothWays);
+        let outcome = slot.outcome_at(vec![0, 0, 0, 0]).unwrap();
+        assert_eq!(
+            outcome.wins,
+            vec![Win {
+                kind: WinKind::Line { line: 0, right_to_left: true },
+                symbol: "cherry".to_string(),
+                count: 2,
+                multiplier: 2,
+                positions: vec![(3, 0), (2, 0)],
+            }]
+        );
+        // a full line only pays once
+        let slot = one_row(vec![bar(), bar(), bar()]).with_pay_mode(PayMode::BothWays);
+        assert_eq!(slot.outcome_at(vec![0, 0, 0]).unwrap().wins.len(), 1);
+    }
+
+    #[test]
+    fn test_anywhere_pays() {
+        let wild = || Symbol::wild("wild", 0);
+        let reel = || Reel::new(vec![cherry(), wild(), bar()]).unwrap();
+        let slot = Slot::new(vec![reel(), reel(), reel()], 1, vec![])
+            .unwrap()
+            .with_pay_mode(PayMode::AnywherePays);
+        // cherry, wild, bar: cherry x2 thanks to the wild; bar only pays a full screen
+        let outcome = slot.outcome_at(vec![0, 1, 2]).unwrap();
+        assert_eq!(
+            outcome.wins,
+            vec![Win {
+                kind: WinKind::Anywhere,
+                symbol: "cherry".to_string(),
+                count: 2,
+                multiplier: 2,
+                positions: vec![(0, 0), (1, 0)],
+            }]
+        );
+        // bar, wild, bar: three bars
+        let outcome = slot.outcome_at(vec![2, 1, 2]).unwrap();
+        assert_eq!(outcome.total_multiplier(), 50);
+    }
+
+    #[test]
+    fn test_ways_243() {
+        // every reel shows cherry, cherry, blank (3 rows), so 2 * 2 * 2 ways for three cherries
+        let reel = || Reel::new(vec![cherry(), cherry(), Symbol::new("blank", 0)]).unwrap();
+        let slot = Slot::new(vec![reel(), reel(), reel(), reel(), reel()], 3, vec![])
+            .unwrap()
+            .with_pay_mode(PayMode::Ways)
+            .with_bet_multiplier(20);
+        let outcome = slot.outcome_at(vec![0; 5]).unwrap();
+        assert_eq!(outcome.ways(), 243);
+        assert_eq!(outcome.wins.len(), 1);
+        // cherries run across all five reels: 2^5 ways, full-line pay of 5
+        assert_eq!(outcome.wins[0].kind, WinKind::Ways { ways: 32 });
+        assert_eq!(outcome.wins[0].count, 5);
+        assert_eq!(outcome.wins[0].multiplier, 32 * 5);
+    }
+
+    #[test]
+    fn test_ways_with_multiplier_wild() {
+        let wild = || Symbol::wild("wild", 0).with_kind(SymbolKind::Wild { excludes: vec![], multiplier: 3 });
+        let slot = Slot::new(
+            vec![Reel::new(vec![cherry(), bar()]).unwrap(), Reel::new(vec![wild(), cherry()]).unwrap(), Reel::new(vec![bar(), bar()]).unwrap()],
+            2,
+            vec![],
+        )
+        .unwrap()
+        .with_pay_mode(PayMode::Ways);
+        // reel 1: cherry, bar; reel 2: wild(3x), cherry; reel 3: bar, bar
+        let outcome = slot.outcome_at(vec![0, 0, 0]).unwrap();
+        let cherry_win = outcome.wins.iter().find(|w| w.symbol == "cherry").unwrap();
+        // cherry x2 over (1) * (3 + 1) weighted ways
+        assert_eq!(cherry_win.kind, WinKind::Ways { ways: 2 });
+        assert_eq!(cherry_win.multiplier, 2 * 4);
+        let bar_win = outcome.wins.iter().find(|w| w.symbol == "bar").unwrap();
+        // bar x3 over 1 * 1 * 2 ways, wild weighted 3
+        assert_eq!(bar_win.kind, WinKind::Ways { ways: 2 });
+        assert_eq!(bar_win.multiplier, 50 * 3 * 2);
+    }
+
+    #[test]
+    fn test_variable_reel_heights() {
+        let reel = || Reel::new(vec![cherry(), bar(), Symbol::new("blank", 0)]).unwrap();
+        let slot = Slot::new(vec![reel(), reel(), reel()], 2, vec![])
+            .unwrap()
+            .with_pay_mode(PayMode::Ways);
+        assert!(Slot::new(vec![reel(), reel()], 2, vec![]).unwrap().with_reel_heights(vec![vec![(2, 1)]]).is_none());
+        assert!(
+            Slot::new(vec![reel(), reel()], 3, vec![vec![2, 2]])
+                .unwrap()
+                .with_reel_heights(vec![vec![(2, 1), (3, 1)], vec![(3, 1)]])
+                .is_none()
+        );
+        let slot = slot.with_reel_heights(vec![vec![(2, 1), (7, 1)]; 3]).unwrap();
+        assert_eq!(slot.rows(), 7);
+        let mut rng = StdRng::seed_from_u64(2);
+        let mut seen = Vec::new();
+        for _ in 0..100 {
+            let outcome = slot.spin_with(&mut rng);
+            assert!(outcome.window.iter().all(|col| col.len() == 2 || col.len() == 7));
+            seen.push(outcome.ways());
+        }
+        assert!(seen.contains(&8));
+        assert!(seen.contains(&343));
+
+        let outcome = slot.outcome_with_heights(vec![0, 0, 0], &[2, 3, 1]).unwrap();
+        assert_eq!(outcome.ways(), 6);
+    }
+
+    #[test]
+    fn test_clusters() {
+        let a = || Symbol::new("a", 0).with_pays(vec![0, 0, 0, 4, 8]);
+        let b = || Symbol::new("b", 6).with_pays(vec![0, 0, 0, 6]);
+        let wild = || Symbol::wild("wild", 0);
+        // reels (top to bottom): [a, a, b], [wild, a, b], [b, b, b]
+        let slot = Slot::new(
+            vec![Reel::new(vec![a(), a(), b()]).unwrap(), Reel::new(vec![wild(), a(), b()]).unwrap(), Reel::new(vec![b(), b(), b()]).unwrap()],
+            3,
+            vec![],
+        )
+        .unwrap()
+        .with_pay_mode(PayMode::Clusters { min_size: 4 });
+        let outcome = slot.outcome_at(vec![0, 0, 0]).unwrap();
+        let a_win = outcome.wins.iter().find(|w| w.symbol == "a").unwrap();
+        assert_eq!(a_win.kind, WinKind::Cluster);
+        assert_eq!(a_win.positions, vec![(0, 0), (0, 1), (1, 0), (1, 1)]);
+        assert_eq!(a_win.multiplier, 4);
+        // five b's along the bottom and right edge, joined by the wild next to (2, 0)
+        let b_win = outcome.wins.iter().find(|w| w.symbol == "b").unwrap();
+        assert_eq!(b_win.count, 6);
+        assert_eq!(b_win.multiplier, 6);
+        assert!(b_win.positions.contains(&(1, 0)));
+
+        // groups below the minimum don't pay
+        let slot = slot.with_pay_mode(PayMode::Clusters { min_size: 7 });
+        assert!(slot.outcome_at(vec![0, 0, 0]).unwrap().wins.is_empty());
+    }
+
+    #[test]
+    fn test_cascades_with_multiplier_progression() {
+        // strip: x x x y ; window rows show 3 symbols from the stop
+        let x = || Symbol::new("x", 0).with_pays(vec![0, 0, 1]);
+        let y = || Symbol::new("y", 0);
+        let reel = || Reel::new(vec![x(), x(), x(), y()]).unwrap();
+        let slot = Slot::new(vec![reel(), reel(), reel()], 1, vec![vec![0, 0, 0]])
+            .unwrap()
+            .with_cascades(vec![2, 3]);
+        // stop 2 shows x on every reel; each tumble pulls in the symbol above
+        let outcome = slot.outcome_at(vec![2, 2, 2]).unwrap();
+        // initial x line, then x (pos 1) at 2x, x (pos 0) at 3x, then y (pos 3): no win
+        assert_eq!(outcome.cascades.len(), 3);
+        assert_eq!(outcome.cascades[0].multiplier, 2);
+        assert_eq!(outcome.cascades[1].multiplier, 3);
+        assert_eq!(outcome.cascades[2].multiplier, 3);
+        assert!(outcome.cascades[2].wins.is_empty());
+        assert_eq!(outcome.cascades[2].window[0][0].name(), "y");
+        assert_eq!(outcome.total_multiplier(), 1 + 2 + 3);
+    }
+
+    #[test]
+    fn test_cascade_drops_survivors() {
+        let x = || Symbol::new("x", 0).with_pays(vec![0, 0, 1]);
+        let reel = || Reel::new(vec![Symbol::new("p", 0), Symbol::new("q", 0), x(), Symbol::new("r", 0)]).unwrap();
+        let slot = Slot::new(vec![reel(), reel(), reel()], 2, vec![vec![1, 1, 1]])
+            .unwrap()
+            .with_cascades(vec![1]);
+        // window rows: q / x on every reel; x line pays then q falls to row 1, p drops in on top
+        let outcome = slot.outcome_at(vec![1, 1, 1]).unwrap();
+        assert_eq!(outcome.cascades.len(), 1);
+        let column: Vec<&str> = outcome.cascades[0].window[0].iter().map(|s| s.name()).collect();
+        assert_eq!(column, vec!["p", "q"]);
+    }
+}
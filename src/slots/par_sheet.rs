@@ -0,0 +1,731 @@
+//! Par sheet generation by full enumeration of reel-strip stop combinations.
+//!
+//! Every combination of stops (and of reel heights, for variable-height slots) is weighted
+//! by the product of its stop weights. Pays are summed as integers and divided once at the
+//! end, so results are exact for the base game. Feature games are added on top: free spins
+//! by recursive expectation where that is exact, and everything else by simulation.
+
+use std::collections::BTreeMap;
+use std::fmt;
+
+use num_bigint::{BigInt, BigUint};
+use num_rational::BigRational;
+use num_traits::ToPrimitive;
+use rand::Rng;
+
+use crate::slots::features::{Feature, FeatureEvent, FeatureGame};
+use crate::slots::{Cascade, Slot, Win, WinKind};
+
+// z-score used for the volatility index (90% confidence).
+const VOLATILITY_Z: f64 = 1.645;
+
+// Upper bounds (in multiples of the total bet) of the win-size buckets.
+const WIN_BUCKETS: [f64; 10] = [0.0, 1.0, 2.0, 5.0, 10.0, 20.0, 50.0, 100.0, 500.0, f64::INFINITY];
+
+// A simulated feature gets this many times the base spins it should need on average.
+const ATTEMPT_ALLOWANCE: f64 = 10.0;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum ParSheetError {
+    /// neither a base spin nor a free spin of a reachable feature can start the feature
+    Unreachable { feature: usize, trigger: String },
+    /// fewer than `runs` feature sequences started within the attempt allowance; `feature` is
+    /// the first feature that needed simulating
+    TooRare { feature: usize, completed: usize, runs: usize },
+}
+
+impl fmt::Display for ParSheetError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParSheetError::Unreachable { feature, trigger } => {
+                write!(f, "feature {feature} can never run: no base or free spin starts it with '{trigger}'")
+            }
+            ParSheetError::TooRare { feature, completed, runs } => {
+                write!(f, "feature {feature}: {completed} of {runs} simulated sequences started before giving up")
+            }
+        }
+    }
+}
+
+impl std::error::Error for ParSheetError {}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum PayKind {
+    Line,
+    Scatter,
+    Anywhere,
+    Ways,
+    Cluster,
+}
+
+impl From<WinKind> for PayKind {
+    fn from(kind: WinKind) -> Self {
+        match kind {
+            WinKind::Line { .. } => PayKind::Line,
+            WinKind::Scatter => PayKind::Scatter,
+            WinKind::Anywhere => PayKind::Anywhere,
+            WinKind::Ways { .. } => PayKind::Ways,
+            WinKind::Cluster => PayKind::Cluster,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct CombinationStats {
+    pub symbol: String,
+    pub count: usize,
+    pub kind: PayKind,
+    /// weighted number of stop combinations producing at least one such win
+    pub hits: u128,
+    pub probability: f64,
+    /// share of the RTP, per unit of total bet
+    pub contribution: f64,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct WinBucket {
+    /// exclusive lower bound, in multiples of the total bet
+    pub low: f64,
+    /// inclusive upper bound
+    pub high: f64,
+    pub probability: f64,
+}
+
+impl WinBucket {
+    // "1 in N" spins land in this bucket.
+    pub fn frequency(&self) -> f64 {
+        if self.probability > 0.0 { 1.0 / self.probability } else { f64::INFINITY }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum FeatureMethod {
+    /// expected spins from the retrigger rate, expected pay by enumerating the free-game reels
+    RecursiveExpectation,
+    Simulated { runs: usize },
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct FeatureStats {
+    pub feature: usize,
+    pub trigger: String,
+    /// chance a base spin lands the trigger; zero for a feature only started from free spins
+    pub trigger_probability: f64,
+    /// expected runs of the feature per base spin, runs started inside other features included
+    pub runs_per_spin: f64,
+    /// mean award per run, in multiples of the base bet
+    pub expected_award: f64,
+    pub method: FeatureMethod,
+    pub contribution: f64,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct ParSheet {
+    /// weighted number of stop combinations (the game cycle)
+    pub cycle: u128,
+    pub bet_multiplier: u32,
+    /// base game return per unit of total bet
+    pub base_rtp: f64,
+    pub hit_frequency: f64,
+    pub variance: f64,
+    pub std_dev: f64,
+    pub volatility_index: f64,
+    pub symbol_contributions: BTreeMap<String, f64>,
+    pub combinations: Vec<CombinationStats>,
+    pub win_distribution: Vec<WinBucket>,
+    /// names of triggering scatters and the probability a base spin triggers them
+    pub trigger_probabilities: BTreeMap<String, f64>,
+    pub features: Vec<FeatureStats>,
+}
+
+impl ParSheet {
+    // Number of weighted stop combinations `slot` has; enumeration visits each once.
+    pub fn cycle(slot: &Slot) -> u128 {
+        let stops: u128 = slot.reels().iter().map(|r| r.total_weight() as u128).product();
+        match slot.reel_heights() {
+            Some(heights) => {
+                stops * heights.iter().map(|c| c.iter().map(|&(_, w)| w as u128).sum::<u128>()).product::<u128>()
+            }
+            None => stops,
+        }
+    }
+
+    // Base-game par sheet by full enumeration.
+    pub fn generate(slot: &Slot) -> Self {
+        let mut acc = Accumulator::new(slot.bet_multiplier());
+        for_each_combination(slot, |weight, wins, cascades, triggered| acc.add(weight, wins, cascades, triggered));
+        acc.finish(slot)
+    }
+
+    // Base game by enumeration plus every feature of `game`. Free spins without a
+    // multiplier step use recursive expectation; the other features are simulated together:
+    // `simulations` whole feature sequences, each from a random base spin that starts one,
+    // with every award credited to the feature that paid it. Fails for a feature that can
+    // never run, or when too few sequences start to simulate.
+    pub fn generate_with_features<R: Rng + ?Sized>(
+        game: &FeatureGame,
+        simulations: usize,
+        rng: &mut R,
+    ) -> Result<Self, ParSheetError> {
+        let mut sheet = Self::generate(&game.slot);
+        let bet = sheet.bet_multiplier as f64;
+        let features = game.features();
+        let reachable = reachable_features(game, &sheet.trigger_probabilities);
+        if let Some(i) = reachable.iter().position(|&r| !r) {
+            return Err(ParSheetError::Unreachable { feature: i, trigger: features[i].trigger().to_string() });
+        }
+        let recursive: Vec<Option<f64>> = features.iter().map(|f| recursive_free_spins(game, f)).collect();
+        let simulated = match recursive.iter().position(Option::is_none) {
+            Some(first) => simulate_features(game, first, simulations, rng)?,
+            None => Vec::new(),
+        };
+        for (i, feature) in features.iter().enumerate() {
+            let trigger = feature.trigger().to_string();
+            let trigger_probability = sheet.trigger_probabilities.get(&trigger).copied().unwrap_or(0.0);
+            let (runs_per_spin, expected_award, method) = match recursive[i] {
+                Some(award) => (trigger_probability, award, FeatureMethod::RecursiveExpectation),
+                None => (simulated[i].0, simulated[i].1, FeatureMethod::Simulated { runs: simulations }),
+            };
+            sheet.features.push(FeatureStats {
+                feature: i,
+                trigger,
+                trigger_probability,
+                runs_per_spin,
+                expected_award,
+                method,
+                contribution: runs_per_spin * expected_award / bet,
+            });
+        }
+        Ok(sheet)
+    }
+
+    pub fn feature_rtp(&self) -> f64 {
+        self.features.iter().map(|f| f.contribution).sum()
+    }
+
+    pub fn total_rtp(&self) -> f64 {
+        self.base_rtp + self.feature_rtp()
+    }
+}
+
+impl fmt::Display for ParSheet {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "PAR SHEET")?;
+        writeln!(f, "Cycle:              {}", self.cycle)?;
+        writeln!(f, "Bet multiplier:     {}", self.bet_multiplier)?;
+        writeln!(f, "Base game RTP:      {:.4}%", self.base_rtp * 100.0)?;
+        writeln!(f, "Feature RTP:        {:.4}%", self.feature_rtp() * 100.0)?;
+        writeln!(f, "Total RTP:          {:.4}%", self.total_rtp() * 100.0)?;
+        writeln!(f, "Hit frequency:      {:.4}%", self.hit_frequency * 100.0)?;
+        writeln!(f, "Standard deviation: {:.4}", self.std_dev)?;
+        writeln!(f, "Volatility index:   {:.4}", self.volatility_index)?;
+        writeln!(f)?;
+        writeln!(f, "{:<16} {:>5} {:>9} {:>14} {:>12} {:>12}", "Symbol", "Count", "Kind", "Hits", "Probability", "Contribution")?;
+        for c in &self.combinations {
+            writeln!(
+                f,
+                "{:<16} {:>5} {:>9} {:>14} {:>12.8} {:>11.4}%",
+                c.symbol,
+                c.count,
+                format!("{:?}", c.kind),
+                c.hits,
+                c.probability,
+                c.contribution * 100.0
+            )?;
+        }
+        writeln!(f)?;
+        writeln!(f, "{:<16} {:>12}", "Symbol", "Contribution")?;
+        for (symbol, contribution) in &self.symbol_contributions {
+            writeln!(f, "{:<16} {:>11.4}%", symbol, contribution * 100.0)?;
+        }
+        writeln!(f)?;
+        writeln!(f, "{:<16} {:>12} {:>14}", "Win (x bet)", "Probability", "1 in")?;
+        for bucket in &self.win_distribution {
+            let range = if bucket.high == 0.0 {
+                "0".to_string()
+            } else if bucket.high.is_infinite() {
+                format!("> {}", bucket.low)
+            } else {
+                format!("{} - {}", bucket.low, bucket.high)
+            };
+            writeln!(f, "{:<16} {:>12.8} {:>14.2}", range, bucket.probability, bucket.frequency())?;
+        }
+        if !self.features.is_empty() {
+            writeln!(f)?;
+            writeln!(
+                f,
+                "{:<16} {:>12} {:>13} {:>14} {:>12}",
+                "Feature", "Trigger", "Runs per spin", "Mean award", "Contribution"
+            )?;
+            for feature in &self.features {
+                writeln!(
+                    f,
+                    "{:<16} {:>12.8} {:>13.8} {:>14.4} {:>11.4}%",
+                    feature.trigger,
+                    feature.trigger_probability,
+                    feature.runs_per_spin,
+                    feature.expected_award,
+                    feature.contribution * 100.0
+                )?;
+            }
+        }
+        Ok(())
+    }
+}
+
+struct Accumulator {
+    bet: u32,
+    cycle: u128,
+    hits: u128,
+    // exact weighted sums of pay (in base bets) and of its square
+    pay: BigUint,
+    pay_squared: BigUint,
+    symbols: BTreeMap<String, BigUint>,
+    combinations: BTreeMap<(String, usize, PayKind), (u128, BigUint)>,
+    buckets: [u128; WIN_BUCKETS.len()],
+    triggers: BTreeMap<String, u128>,
+}
+
+impl Accumulator {
+    fn new(bet: u32) -> Self {
+        Self {
+            bet,
+            cycle: 0,
+            hits: 0,
+            pay: BigUint::ZERO,
+            pay_squared: BigUint::ZERO,
+            symbols: BTreeMap::new(),
+            combinations: BTreeMap::new(),
+            buckets: [0; WIN_BUCKETS.len()],
+            triggers: BTreeMap::new(),
+        }
+    }
+
+    fn add(&mut self, weight: u128, wins: &[Win], cascades: &[Cascade], triggered: &[String]) {
+        let total = wins.iter().map(|w| w.multiplier).sum::<u64>()
+            + cascades.iter().map(Cascade::total_multiplier).sum::<u64>();
+        self.cycle += weight;
+        if total > 0 {
+            self.hits += weight;
+            let paid = BigUint::from(weight) * total;
+            self.pay_squared += &paid * total;
+            self.pay += paid;
+        }
+
+        let mut seen = Vec::new();
+        let cascaded = cascades.iter().flat_map(|c| c.wins.iter().map(move |win| (win, c.multiplier)));
+        for (win, multiplier) in wins.iter().map(|win| (win, 1)).chain(cascaded) {
+            let amount = BigUint::from(weight) * (win.multiplier * multiplier);
+            *self.symbols.entry(win.symbol.clone()).or_default() += &amount;
+            let key = (win.symbol.clone(), win.count, PayKind::from(win.kind));
+            let entry = self.combinations.entry(key.clone()).or_insert((0, BigUint::ZERO));
+            if !seen.contains(&key) {
+                entry.0 += weight;
+                seen.push(key);
+            }
+            entry.1 += amount;
+        }
+        for name in triggered {
+            *self.triggers.entry(name.clone()).or_insert(0) += weight;
+        }
+        self.bucket(weight, total);
+    }
+
+    // Win sizes are bucketed in multiples of the total bet.
+    fn bucket(&mut self, weight: u128, total: u64) {
+        let x = total as f64 / self.bet as f64;
+        let index = WIN_BUCKETS.iter().position(|&high| x <= high).unwrap_or(WIN_BUCKETS.len() - 1);
+        self.buckets[index] += weight;
+    }
+
+    fn finish(self, slot: &Slot) -> ParSheet {
+        let cycle = BigInt::from(self.cycle.max(1));
+        let stake = &cycle * self.bet;
+        // share of every stake, or of every spin when `per` is the cycle alone
+        let ratio = |amount: &BigUint, per: &BigInt| {
+            BigRational::new(BigInt::from(amount.clone()), per.clone()).to_f64().unwrap_or(f64::NAN)
+        };
+        let probability = |hits: u128| ratio(&BigUint::from(hits), &cycle);
+
+        // Var = (cycle * sum(x^2) - sum(x)^2) / (cycle * bet)^2, with no cancellation
+        let pay = BigInt::from(self.pay.clone());
+        let spread = &cycle * BigInt::from(self.pay_squared) - &pay * &pay;
+        let variance = BigRational::new(spread, &stake * &stake).to_f64().unwrap_or(f64::NAN);
+        let base_rtp = ratio(&self.pay, &stake);
+        let std_dev = variance.max(0.0).sqrt();
+
+        let combinations = self
+            .combinations
+            .into_iter()
+            .map(|((symbol, count, kind), (hits, amount))| CombinationStats {
+                symbol,
+                count,
+                kind,
+                hits,
+                probability: probability(hits),
+                contribution: ratio(&amount, &stake),
+            })
+            .collect();
+        let win_distribution = WIN_BUCKETS
+            .iter()
+            .enumerate()
+            .map(|(i, &high)| WinBucket {
+                low: if i == 0 { 0.0 } else { WIN_BUCKETS[i - 1] },
+                high,
+                probability: probability(self.buckets[i]),
+            })
+            .collect();
+
+        ParSheet {
+            cycle: self.cycle,
+            bet_multiplier: slot.bet_multiplier(),
+            base_rtp,
+            hit_frequency: probability(self.hits),
+            variance,
+            std_dev,
+            volatility_index: VOLATILITY_Z * std_dev,
+            symbol_contributions: self.symbols.into_iter().map(|(s, amount)| (s, ratio(&amount, &stake))).collect(),
+            combinations,
+            win_distribution,
+            trigger_probabilities: self.triggers.into_iter().map(|(s, w)| (s, probability(w))).collect(),
+            features: Vec::new(),
+        }
+    }
+}
+
+// Visit every (weighted) stop and height combination of `slot` with its wins, cascades and
+// triggers. Windows are only cloned when the slot cascades.
+fn for_each_combination(slot: &Slot, mut visit: impl FnMut(u128, &[Win], &[Cascade], &[String])) {
+    let reels = slot.reels();
+    let height_choices: Vec<Vec<(usize, u32)>> = match slot.reel_heights() {
+        Some(choices) => choices.iter().map(|c| c.iter().copied().filter(|&(_, w)| w > 0).collect()).collect(),
+        None => vec![vec![(slot.rows(), 1)]; reels.len()],
+    };
+    let mut heights_at = vec![0usize; reels.len()];
+    loop {
+        let heights: Vec<usize> = heights_at.iter().zip(&height_choices).map(|(&i, c)| c[i].0).collect();
+        let height_weight: u128 = heights_at.iter().zip(&height_choices).map(|(&i, c)| c[i].1 as u128).product();
+
+        let mut stops = vec![0usize; reels.len()];
+        loop {
+            let weight: u128 = stops.iter().zip(reels).map(|(&s, r)| r.weights()[s] as u128).product::<u128>() * height_weight;
+            if weight > 0 && slot.cascade_multipliers().is_some() {
+                let outcome = slot.outcome_with_heights(stops.clone(), &heights).expect("one stop and height per reel");
+                visit(weight, &outcome.wins, &outcome.cascades, &outcome.triggered);
+            } else if weight > 0 {
+                let window = slot.window_with_heights(&stops, &heights);
+                visit(weight, &slot.evaluate(&window).expect("window built from this slot"), &[], &slot.triggered(&window));
+            }
+            if !advance(&mut stops, |i| reels[i].len()) {
+                break;
+            }
+        }
+        if !advance(&mut heights_at, |i| height_choices[i].len()) {
+            break;
+        }
+    }
+}
+
+// Odometer increment; returns false once every position has wrapped.
+fn advance(digits: &mut [usize], limit: impl Fn(usize) -> usize) -> bool {
+    for i in (0..digits.len()).rev() {
+        digits[i] += 1;
+        if digits[i] < limit(i) {
+            return true;
+        }
+        digits[i] = 0;
+    }
+    false
+}
+
+// Free spins with a fixed multiplier: by Wald's identity the expected award is
+// E[spins] * E[pay per spin] * multiplier, with E[spins] = spins / (1 - p * retrigger_spins).
+// The game's free-spin cap is left out; it only matters for runs that come close to it.
+fn recursive_free_spins(game: &FeatureGame, feature: &Feature) -> Option<f64> {
+    let Feature::FreeSpins { trigger, spins, retrigger_spins, multiplier, multiplier_step, slot } = feature else {
+        return None;
+    };
+    let free_slot = slot.as_deref().unwrap_or(&game.slot);
+    // other features triggering inside free spins need simulation
+    let other_triggers = game.features().iter().any(|f| f.trigger() != trigger);
+    if *multiplier_step != 0 || other_triggers {
+        return None;
+    }
+    let sheet = ParSheet::generate(free_slot);
+    let retrigger = sheet.trigger_probabilities.get(trigger).copied().unwrap_or(0.0);
+    let growth = retrigger * *retrigger_spins as f64;
+    if growth >= 1.0 {
+        return None;
+    }
+    let expected_spins = *spins as f64 / (1.0 - growth);
+    let pay_per_spin = sheet.base_rtp * free_slot.bet_multiplier() as f64;
+    Some(expected_spins * pay_per_spin * *multiplier as f64)
+}
+
+// Which features can ever run: those whose trigger a base spin can land, then those whose
+// trigger lands on the free-spin reels of a feature that can run. A feature sharing its
+// trigger with an earlier one never runs, since a trigger starts the first feature keyed by it.
+fn reachable_features(game: &FeatureGame, base_triggers: &BTreeMap<String, f64>) -> Vec<bool> {
+    let features = game.features();
+    let shadowed = |i: usize| features[..i].iter().any(|f| f.trigger() == features[i].trigger());
+    // triggers each free-spins feature's own reels can land
+    let free_triggers: Vec<Option<BTreeMap<String, f64>>> = features
+        .iter()
+        .map(|f| match f {
+            Feature::FreeSpins { slot: Some(slot), .. } => Some(ParSheet::generate(slot).trigger_probabilities),
+            _ => None,
+        })
+        .collect();
+    let lands =
+        |triggers: &BTreeMap<String, f64>, i: usize| triggers.get(features[i].trigger()).is_some_and(|&p| p > 0.0);
+    let mut reachable: Vec<bool> = (0..features.len()).map(|i| !shadowed(i) && lands(base_triggers, i)).collect();
+    loop {
+        let next: Vec<bool> = (0..features.len())
+            .map(|i| {
+                reachable[i]
+                    || (!shadowed(i)
+                        && (0..features.len())
+                            .any(|j| reachable[j] && free_triggers[j].as_ref().is_some_and(|t| lands(t, i))))
+            })
+            .collect();
+        if next == reachable {
+            return reachable;
+        }
+        reachable = next;
+    }
+}
+
+// Simulate `runs` whole feature sequences, each from a random base spin that starts at least
+// one feature, and return (runs per base spin, mean award per run) for every feature. Each
+// `Completed` award goes to the feature that paid it, wherever in the sequence it ran. The
+// chance a base spin starts a sequence is exact, by enumeration. Gives up after
+// `ATTEMPT_ALLOWANCE` times the base spins that should take on average.
+fn simulate_features<R: Rng + ?Sized>(
+    game: &FeatureGame,
+    first: usize,
+    runs: usize,
+    rng: &mut R,
+) -> Result<Vec<(f64, f64)>, ParSheetError> {
+    let features = game.features();
+    let starts = |triggered: &[String]| triggered.iter().any(|t| features.iter().any(|f| f.trigger() == t));
+    let mut hits: u128 = 0;
+    let mut cycle: u128 = 0;
+    for_each_combination(&game.slot, |weight, _, _, triggered| {
+        cycle += weight;
+        if starts(triggered) {
+            hits += weight;
+        }
+    });
+    let start_probability = hits as f64 / cycle as f64;
+
+    let mut sim = FeatureGame::new(game.slot.clone(), features.to_vec())
+        .expect("features taken from a valid game")
+        .with_max_free_spins(game.max_free_spins());
+    let mut feature_runs = vec![0usize; features.len()];
+    let mut awards = vec![0.0; features.len()];
+    let mut sequences = 0;
+    let mut attempts: usize = 0;
+    let max_attempts = (ATTEMPT_ALLOWANCE * runs.max(1) as f64 / start_probability).ceil() as usize;
+    while sequences < runs {
+        if attempts == max_attempts {
+            return Err(ParSheetError::TooRare { feature: first, completed: sequences, runs });
+        }
+        attempts += 1;
+        let outcome = game.slot.spin_with(rng);
+        if sim.queue_triggers(&outcome) == 0 {
+            continue;
+        }
+        sequences += 1;
+        while sim.in_feature() {
+            for event in sim.step_with(rng) {
+                if let FeatureEvent::Completed { feature, award } = event {
+                    feature_runs[feature] += 1;
+                    awards[feature] += award as f64;
+                }
+            }
+        }
+    }
+    let per_sequence = |count: f64| if sequences == 0 { 0.0 } else { start_probability * count / sequences as f64 };
+    Ok((0..features.len())
+        .map(|i| {
+            let mean = if feature_runs[i] == 0 { 0.0 } else { awards[i] / feature_runs[i] as f64 };
+            (per_sequence(feature_runs[i] as f64), mean)
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::slots::features::PickPrize;
+    use crate::slots::{Reel, Symbol};
+    use rand::SeedableRng;
+    use rand::rngs::StdRng;
+
+    // 3 reels of [a, b, c], one line: 27 combinations, one each of a-a-a, b-b-b, c-c-c
+    fn simple() -> Slot {
+        let reel = || Reel::new(vec![Symbol::new("a", 27), Symbol::new("b", 9), Symbol::new("c", 0)]).unwrap();
+        Slot::new(vec![reel(), reel(), reel()], 1, vec![vec![0, 0, 0]]).unwrap()
+    }
+
+    #[test]
+    fn test_simple_par_sheet() {
+        let sheet = ParSheet::generate(&simple());
+        assert_eq!(sheet.cycle, 27);
+        assert!((sheet.base_rtp - 36.0 / 27.0).abs() < 1e-12);
+        assert!((sheet.hit_frequency - 2.0 / 27.0).abs() < 1e-12);
+        assert!((sheet.symbol_contributions["a"] - 1.0).abs() < 1e-12);
+        assert!((sheet.symbol_contributions["b"] - 9.0 / 27.0).abs() < 1e-12);
+        assert_eq!(sheet.combinations.len(), 2);
+        assert_eq!(sheet.combinations[0].hits, 1);
+        // variance: E[X^2] - E[X]^2
+        let mean: f64 = 36.0 / 27.0;
+        let expected_variance = (27.0 * 27.0 + 81.0) / 27.0 - mean * mean;
+        assert!((sheet.variance - expected_variance).abs() < 1e-9);
+        assert!((sheet.volatility_index - 1.645 * expected_variance.sqrt()).abs() < 1e-9);
+        let distribution: f64 = sheet.win_distribution.iter().map(|b| b.probability).sum();
+        assert!((distribution - 1.0).abs() < 1e-12);
+        assert!((sheet.win_distribution[0].probability - 25.0 / 27.0).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_variance_exact_on_heavy_strips() {
+        // nearly every spin pays 1,000,000 and the rest 999,999: a variance of p(1 - p)
+        // that summing squares in f64 would lose under E[X^2] of 10^12
+        let reel = Reel::weighted(vec![(Symbol::new("a", 1_000_000), 4_000_000_000), (Symbol::new("b", 999_999), 1)]).unwrap();
+        let slot = Slot::new(vec![reel], 1, vec![vec![0]]).unwrap();
+        let sheet = ParSheet::generate(&slot);
+        assert_eq!(sheet.cycle, 4_000_000_001);
+        let p = 1.0 / 4_000_000_001.0;
+        assert!((sheet.variance - p * (1.0 - p)).abs() / (p * (1.0 - p)) < 1e-9);
+        assert!((sheet.base_rtp - (1_000_000.0 - p)).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_weighted_strips() {
+        let reel = || Reel::weighted(vec![(Symbol::new("a", 10), 1), (Symbol::new("c", 0), 3)]).unwrap();
+        let slot = Slot::new(vec![reel(), reel()], 1, vec![vec![0, 0]]).unwrap();
+        let sheet = ParSheet::generate(&slot);
+        assert_eq!(ParSheet::cycle(&slot), 16);
+        assert_eq!(sheet.cycle, 16);
+        assert!((sheet.base_rtp - 10.0 / 16.0).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_variable_heights_enumerated() {
+        let reel = || Reel::new(vec![Symbol::new("a", 0).with_pays(vec![0, 1]), Symbol::new("c", 0)]).unwrap();
+        let slot = Slot::new(vec![reel(), reel()], 1, vec![])
+            .unwrap()
+            .with_pay_mode(crate::slots::PayMode::Ways)
+            .with_reel_heights(vec![vec![(1, 1), (2, 1)]; 2])
+            .unwrap();
+        assert_eq!(ParSheet::cycle(&slot), 16);
+        let sheet = ParSheet::generate(&slot);
+        assert_eq!(sheet.cycle, 16);
+        assert!(sheet.base_rtp > 0.0);
+    }
+
+    #[test]
+    fn test_free_spins_recursive_expectation() {
+        // base: scatter on every reel with probability 1/2 each; triggers on three
+        let reel = || Reel::new(vec![Symbol::scatter("bonus", 0, Some(3)), Symbol::new("c", 0)]).unwrap();
+        let base = Slot::new(vec![reel(), reel(), reel()], 1, vec![vec![0, 0, 0]]).unwrap();
+        // free game always pays 2 on its single line and never retriggers
+        let free_reel = || Reel::new(vec![Symbol::new("a", 2)]).unwrap();
+        let free = Slot::new(vec![free_reel(), free_reel(), free_reel()], 1, vec![vec![0, 0, 0]]).unwrap();
+        let game = FeatureGame::new(
+            base,
+            vec![Feature::FreeSpins {
+                trigger: "bonus".to_string(),
+                spins: 10,
+                retrigger_spins: 10,
+                multiplier: 2,
+                multiplier_step: 0,
+                slot: Some(Box::new(free)),
+            }],
+        ).unwrap();
+        let mut rng = StdRng::seed_from_u64(1);
+        let sheet = ParSheet::generate_with_features(&game, 100, &mut rng).unwrap();
+        assert_eq!(sheet.features.len(), 1);
+        assert_eq!(sheet.features[0].method, FeatureMethod::RecursiveExpectation);
+        assert!((sheet.features[0].trigger_probability - 0.125).abs() < 1e-12);
+        assert!((sheet.features[0].expected_award - 40.0).abs() < 1e-9);
+        assert!((sheet.total_rtp() - 0.125 * 40.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_unreachable_and_starved_features_fail() {
+        let reel = || Reel::new(vec![Symbol::scatter("bonus", 0, Some(2)), Symbol::new("c", 0)]).unwrap();
+        let base = || Slot::new(vec![reel(), reel()], 1, vec![vec![0, 0]]).unwrap();
+        let pick_em = |trigger: &str| Feature::PickEm { trigger: trigger.to_string(), picks: 1, board: vec![PickPrize::Credits(10)] };
+        let mut rng = StdRng::seed_from_u64(1);
+
+        let game = FeatureGame::new(base(), vec![pick_em("chest")]).unwrap();
+        assert_eq!(
+            ParSheet::generate_with_features(&game, 10, &mut rng).unwrap_err(),
+            ParSheetError::Unreachable { feature: 0, trigger: "chest".to_string() }
+        );
+        // the second feature shares its trigger with the first, so it never runs
+        let game = FeatureGame::new(base(), vec![pick_em("bonus"), pick_em("bonus")]).unwrap();
+        assert_eq!(
+            ParSheet::generate_with_features(&game, 10, &mut rng).unwrap_err(),
+            ParSheetError::Unreachable { feature: 1, trigger: "bonus".to_string() }
+        );
+    }
+
+    #[test]
+    fn test_features_started_in_free_spins_are_credited() {
+        // base: "bonus" on both reels with chance 1/4; every free spin lands "chest"
+        let reel = || Reel::new(vec![Symbol::scatter("bonus", 0, Some(2)), Symbol::new("c", 0)]).unwrap();
+        let base = Slot::new(vec![reel(), reel()], 1, vec![vec![0, 0]]).unwrap();
+        let chest = || Reel::new(vec![Symbol::scatter("chest", 0, Some(2))]).unwrap();
+        let free = Slot::new(vec![chest(), chest()], 1, vec![vec![0, 0]]).unwrap();
+        let game = FeatureGame::new(
+            base,
+            vec![
+                Feature::FreeSpins {
+                    trigger: "bonus".to_string(),
+                    spins: 2,
+                    retrigger_spins: 0,
+                    multiplier: 1,
+                    multiplier_step: 0,
+                    slot: Some(Box::new(free)),
+                },
+                Feature::PickEm { trigger: "chest".to_string(), picks: 1, board: vec![PickPrize::Credits(10)] },
+            ],
+        )
+        .unwrap();
+        let mut rng = StdRng::seed_from_u64(3);
+        let sheet = ParSheet::generate_with_features(&game, 20, &mut rng).unwrap();
+        let (free_spins, pick_em) = (&sheet.features[0], &sheet.features[1]);
+        assert!((free_spins.runs_per_spin - 0.25).abs() < 1e-12);
+        assert_eq!(free_spins.expected_award, 0.0);
+        // two pick-em games per free-spins feature, none from the base game
+        assert_eq!(pick_em.trigger_probability, 0.0);
+        assert!((pick_em.runs_per_spin - 0.5).abs() < 1e-12);
+        assert!((pick_em.expected_award - 10.0).abs() < 1e-12);
+        assert!((sheet.feature_rtp() - 5.0).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_simulated_pick_em() {
+        let reel = || Reel::new(vec![Symbol::scatter("bonus", 0, Some(2)), Symbol::new("c", 0)]).unwrap();
+        let base = Slot::new(vec![reel(), reel()], 1, vec![vec![0, 0]]).unwrap();
+        let game = FeatureGame::new(
+            base,
+            vec![Feature::PickEm {
+                trigger: "bonus".to_string(),
+                picks: 1,
+                board: vec![PickPrize::Credits(10), PickPrize::Credits(10)],
+            }],
+        ).unwrap();
+        let mut rng = StdRng::seed_from_u64(1);
+        let sheet = ParSheet::generate_with_features(&game, 50, &mut rng).unwrap();
+        assert_eq!(sheet.features[0].method, FeatureMethod::Simulated { runs: 50 });
+        assert!((sheet.features[0].expected_award - 10.0).abs() < 1e-12);
+        assert!((sheet.feature_rtp() - 0.25 * 10.0).abs() < 1e-12);
+        let report = sheet.to_string();
+        assert!(report.contains("Total RTP"));
+        assert!(report.contains("bonus"));
+    }
+}
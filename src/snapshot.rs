@@ -0,0 +1,197 @@
+//! Save and restore of running tables.
+//!
+//! A snapshot is JSON holding a format version and the whole game: shoe order, discard tray,
+//! seats with their wallets, ledgers and limits, history, rules and the cashier's books.
+//! Restoring one gives back the same shoe in the same order, which is what a server needs
+//! across restarts and what a bug report needs to be reproduced. The table's own generator is
+//! saved too, so `play`, `play_n` and `play_round` on a restored table shuffle exactly as the
+//! original would have; that matters most for baccarat, which shuffles before every coup.
+//! `play_with` still draws on whatever generator it is given. Chip sets, odds, limits and
+//! players are checked as they are read, and a snapshot that breaks their rules is refused.
+
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+
+use crate::error::Error;
+
+/// bumped whenever a saved type changes shape
+pub const VERSION: u32 = 7;
+
+#[derive(Serialize)]
+struct Saving<'a, T> {
+    version: u32,
+    game: &'a T,
+}
+
+// Read first, so a snapshot in another format fails on its version and not its contents.
+#[derive(Deserialize)]
+struct Version {
+    version: u32,
+}
+
+#[derive(Deserialize)]
+struct Restoring<T> {
+    game: T,
+}
+
+pub fn save<T: Serialize>(game: &T) -> Result<String, Error> {
+    serde_json::to_string_pretty(&Saving { version: VERSION, game }).map_err(|e| Error::Snapshot(e.to_string()))
+}
+
+// Fails on malformed JSON, a missing field, or a snapshot from another format version.
+pub fn restore<T: DeserializeOwned>(json: &str) -> Result<T, Error> {
+    let Version { version } = serde_json::from_str(json).map_err(|e| Error::Snapshot(e.to_string()))?;
+    if version != VERSION {
+        return Err(Error::Snapshot(format!("version {version} is not supported, expected {VERSION}")));
+    }
+    let restoring: Restoring<T> = serde_json::from_str(json).map_err(|e| Error::Snapshot(e.to_string()))?;
+    Ok(restoring.game)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use rand::SeedableRng;
+    use rand::rngs::StdRng;
+    use serde_json::Value;
+
+    use super::*;
+    use crate::bet::ChipSet;
+    use crate::games::baccarat::{BaccaratBet, BaccaratGame};
+    use crate::games::TableGame;
+    use crate::games::blackjack::BlackjackGame;
+    use crate::limits::{Limits, Period};
+    use crate::odds::Odds;
+    use crate::player::Player;
+
+    fn same(a: &str, b: &str) -> bool {
+        serde_json::from_str::<Value>(a).unwrap() == serde_json::from_str::<Value>(b).unwrap()
+    }
+
+    #[test]
+    fn test_blackjack_round_trip_deals_the_same_cards() {
+        let green = ChipSet::standard().chip(2_500).unwrap().clone();
+        let mut alice = Player::new("Alice").with_limits(Limits::new().with_loss_limit(50_000, Period::Day));
+        alice.deposit(green.clone(), 20).unwrap();
+        let mut game = BlackjackGame::new(vec![alice, Player::new("Bob")], 6, 10, 500)
+            .with_blackjack_odds(Odds::new(6, 5).unwrap())
+            .with_table("7");
+        for _ in 0..3 {
+            game.place_initial_bet(0, HashMap::from([(green.clone(), 1)])).unwrap();
+            game.play().unwrap();
+        }
+        game.place_initial_bet(0, HashMap::from([(green.clone(), 2)])).unwrap();
+
+        let json = game.save().unwrap();
+        let mut restored = BlackjackGame::restore(&json).unwrap();
+        assert!(same(&json, &restored.save().unwrap()));
+        assert_eq!(restored.cards_remaining(), game.cards_remaining());
+        assert_eq!((restored.table(), restored.blackjack_odds()), ("7", Odds::new(6, 5).unwrap()));
+        assert_eq!(restored.player(0).unwrap().ledger(), game.player(0).unwrap().ledger());
+        assert_eq!(restored.player(0).unwrap().limits(), game.player(0).unwrap().limits());
+
+        // the bet left on the layout and the shoe order both survive
+        assert_eq!(restored.play(), game.play());
+        assert_eq!(restored.player(0).unwrap().total_cents(), game.player(0).unwrap().total_cents());
+        let amounts = |game: &BlackjackGame| -> Vec<i64> {
+            game.player(0).unwrap().ledger().entries().iter().map(|tx| tx.amount_cents).collect()
+        };
+        assert_eq!(amounts(&restored), amounts(&game));
+    }
+
+    #[test]
+    fn test_baccarat_round_trip() {
+        let red = ChipSet::standard().chip(500).unwrap().clone();
+        let mut player = Player::new("Carol");
+        player.deposit(red.clone(), 10).unwrap();
+        let mut game = BaccaratGame::new(vec![player]);
+        game.place_bet(0, BaccaratBet::Banker, HashMap::from([(red.clone(), 2)])).unwrap();
+        game.play_n(5).unwrap();
+        game.place_bet(0, BaccaratBet::Tie, HashMap::from([(red.clone(), 1)])).unwrap();
+
+        let json = game.save().unwrap();
+        let restored = BaccaratGame::restore(&json).unwrap();
+        assert!(same(&json, &restored.save().unwrap()));
+        assert_eq!(restored.history(), game.history());
+        assert_eq!(restored.cards_remaining(), game.cards_remaining());
+        assert_eq!(restored.players()[0].bets[&BaccaratBet::Tie].total_cents(), 500);
+        assert_eq!(restored.cashier, game.cashier);
+        // dealt cards are in the discard tray, all but the last coup's still on the table
+        let table: Value = serde_json::from_str(&json).unwrap();
+        let discards = table["game"]["shoe"]["discards"]["cards"].as_array().unwrap().len();
+        assert!(discards >= 4 * 4 && discards + game.cards_remaining() < 312);
+    }
+
+    #[test]
+    fn test_baccarat_replays_with_the_same_generator() {
+        let json = BaccaratGame::new(vec![]).save().unwrap();
+        let (mut first, mut second) = (BaccaratGame::restore(&json).unwrap(), BaccaratGame::restore(&json).unwrap());
+        for seed in 0..5 {
+            let coup = first.play_with(&mut StdRng::seed_from_u64(seed)).unwrap();
+            assert_eq!(second.play_with(&mut StdRng::seed_from_u64(seed)).unwrap(), coup);
+        }
+    }
+
+    #[test]
+    fn test_restored_baccarat_plays_the_next_coups() {
+        let red = ChipSet::standard().chip(500).unwrap().clone();
+        let mut player = Player::new("Erin");
+        player.deposit(red.clone(), 10).unwrap();
+        let mut game = BaccaratGame::new(vec![player]).with_seed(11);
+        game.play_n(3).unwrap();
+        game.place_bet(0, BaccaratBet::Player, HashMap::from([(red, 1)])).unwrap();
+
+        let mut restored = BaccaratGame::restore(&game.save().unwrap()).unwrap();
+        for _ in 0..5 {
+            assert_eq!(restored.play().unwrap(), game.play().unwrap());
+        }
+        assert_eq!(restored.players()[0].player.total_cents(), game.players()[0].player.total_cents());
+        assert_eq!(restored.play_round().unwrap().outcome, game.play_round().unwrap().outcome);
+    }
+
+    #[test]
+    fn test_broken_rules_are_refused() {
+        let red = ChipSet::standard().chip(500).unwrap().clone();
+        let mut player = Player::new("Dan");
+        player.deposit(red, 4).unwrap();
+        let json = BaccaratGame::new(vec![player]).save().unwrap();
+        let refused = |change: &dyn Fn(&mut Value)| {
+            let mut table: Value = serde_json::from_str(&json).unwrap();
+            change(&mut table["game"]);
+            BaccaratGame::restore(&table.to_string()).err().expect("the snapshot is refused")
+        };
+        assert!(BaccaratGame::restore(&json).is_ok());
+
+        let err = refused(&|game| game["cashier"]["chips"]["denominations"] = Value::Array(vec![]));
+        assert!(matches!(err, Error::Snapshot(reason) if reason.contains("chip set")));
+        let err = refused(&|game| game["pay_table"]["banker"]["ratio"] = serde_json::json!([2, 2]));
+        assert!(matches!(err, Error::Snapshot(reason) if reason.contains("2:2")));
+        let err = refused(&|game| game["pay_table"]["banker"]["ratio"] = serde_json::json!([1, 0]));
+        assert!(matches!(err, Error::Snapshot(_)));
+
+        fn dan(game: &mut Value) -> &mut Value {
+            &mut game["players"]["seats"][0]["player"]
+        }
+        let err = refused(&|game| {
+            dan(game).as_object_mut().unwrap().remove("session");
+        });
+        assert!(matches!(err, Error::Snapshot(reason) if reason.contains("session")));
+        let err = refused(&|game| dan(game)["wallet"][0][1] = Value::from(5));
+        assert!(matches!(err, Error::Snapshot(reason) if reason.contains("wallet")));
+        let err = refused(&|game| dan(game)["session"]["session"] = Value::from(3));
+        assert!(matches!(err, Error::Snapshot(reason) if reason.contains("session stats")));
+        let forever = serde_json::json!({ "secs": u64::MAX, "nanos": 0 });
+        let err = refused(&|game| dan(game)["limits"]["cool_off"] = forever.clone());
+        assert!(matches!(err, Error::Snapshot(reason) if reason.contains("cool-off")));
+    }
+
+    #[test]
+    fn test_bad_snapshots_are_refused() {
+        let json = BaccaratGame::new(vec![]).save().unwrap();
+        let future = json.replacen(&format!("\"version\": {VERSION}"), "\"version\": 99", 1);
+        assert!(matches!(BaccaratGame::restore(&future), Err(Error::Snapshot(reason)) if reason.contains("99")));
+        assert!(matches!(BaccaratGame::restore("{}"), Err(Error::Snapshot(_))));
+        assert!(matches!(BlackjackGame::restore(&json), Err(Error::Snapshot(_))));
+    }
+}
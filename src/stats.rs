@@ -0,0 +1,231 @@
+//! Small statistics toolkit used by the audit and analysis code.
+//!
+//! Only what the crate needs is implemented here: the chi-square survival function and
+//! exact binomial tail probabilities, both built on the regularized incomplete gamma and
+//! beta functions.
+
+const EPSILON: f64 = 1e-14;
+const MAX_ITERATIONS: usize = 1000;
+
+/// Natural log of the gamma function (Lanczos approximation, g = 7).
+pub fn ln_gamma(x: f64) -> f64 {
+    const COEFFICIENTS: [f64; 9] = [
+        0.999_999_999_999_809_9,
+        676.520_368_121_885_1,
+        -1_259.139_216_722_402_8,
+        771.323_428_777_653_1,
+        -176.615_029_162_140_6,
+        12.507_343_278_686_905,
+        -0.138_571_095_265_720_12,
+        9.984_369_578_019_572e-6,
+        1.505_632_735_149_311_6e-7,
+    ];
+    if x < 0.5 {
+        // reflection formula
+        let pi = std::f64::consts::PI;
+        return (pi / (pi * x).sin()).ln() - ln_gamma(1.0 - x);
+    }
+    let x = x - 1.0;
+    let mut a = COEFFICIENTS[0];
+    let t = x + 7.5;
+    for (i, &c) in COEFFICIENTS.iter().enumerate().skip(1) {
+        a += c / (x + i as f64);
+    }
+    0.5 * (2.0 * std::f64::consts::PI).ln() + (x + 0.5) * t.ln() - t + a.ln()
+}
+
+/// Regularized upper incomplete gamma function Q(a, x).
+pub fn gamma_q(a: f64, x: f64) -> f64 {
+    if x <= 0.0 {
+        return 1.0;
+    }
+    if x < a + 1.0 {
+        1.0 - gamma_p_series(a, x)
+    } else {
+        gamma_q_continued_fraction(a, x)
+    }
+}
+
+fn gamma_p_series(a: f64, x: f64) -> f64 {
+    let mut sum = 1.0 / a;
+    let mut term = sum;
+    let mut ap = a;
+    for _ in 0..MAX_ITERATIONS {
+        ap += 1.0;
+        term *= x / ap;
+        sum += term;
+        if term.abs() < sum.abs() * EPSILON {
+            break;
+        }
+    }
+    sum * (-x + a * x.ln() - ln_gamma(a)).exp()
+}
+
+fn gamma_q_continued_fraction(a: f64, x: f64) -> f64 {
+    let tiny = f64::MIN_POSITIVE / EPSILON;
+    let mut b = x + 1.0 - a;
+    let mut c = 1.0 / tiny;
+    let mut d = 1.0 / b;
+    let mut h = d;
+    for i in 1..MAX_ITERATIONS {
+        let an = -(i as f64) * (i as f64 - a);
+        b += 2.0;
+        d = an * d + b;
+        if d.abs() < tiny {
+            d = tiny;
+        }
+        c = b + an / c;
+        if c.abs() < tiny {
+            c = tiny;
+        }
+        d = 1.0 / d;
+        let delta = d * c;
+        h *= delta;
+        if (delta - 1.0).abs() < EPSILON {
+            break;
+        }
+    }
+    (-x + a * x.ln() - ln_gamma(a)).exp() * h
+}
+
+/// Regularized incomplete beta function I_x(a, b).
+pub fn beta_i(a: f64, b: f64, x: f64) -> f64 {
+    if x <= 0.0 {
+        return 0.0;
+    }
+    if x >= 1.0 {
+        return 1.0;
+    }
+    let ln_front = ln_gamma(a + b) - ln_gamma(a) - ln_gamma(b) + a * x.ln() + b * (1.0 - x).ln();
+    if x < (a + 1.0) / (a + b + 2.0) {
+        ln_front.exp() * beta_continued_fraction(a, b, x) / a
+    } else {
+        1.0 - ln_front.exp() * beta_continued_fraction(b, a, 1.0 - x) / b
+    }
+}
+
+fn beta_continued_fraction(a: f64, b: f64, x: f64) -> f64 {
+    let tiny = f64::MIN_POSITIVE / EPSILON;
+    let qab = a + b;
+    let qap = a + 1.0;
+    let qam = a - 1.0;
+    let mut c = 1.0;
+    let mut d = 1.0 - qab * x / qap;
+    if d.abs() < tiny {
+        d = tiny;
+    }
+    d = 1.0 / d;
+    let mut h = d;
+    for m in 1..MAX_ITERATIONS {
+        let m = m as f64;
+        let m2 = 2.0 * m;
+        let aa = m * (b - m) * x / ((qam + m2) * (a + m2));
+        d = 1.0 + aa * d;
+        if d.abs() < tiny {
+            d = tiny;
+        }
+        c = 1.0 + aa / c;
+        if c.abs() < tiny {
+            c = tiny;
+        }
+        d = 1.0 / d;
+        h *= d * c;
+        let aa = -(a + m) * (qab + m) * x / ((a + m2) * (qap + m2));
+        d = 1.0 + aa * d;
+        if d.abs() < tiny {
+            d = tiny;
+        }
+        c = 1.0 + aa / c;
+        if c.abs() < tiny {
+            c = tiny;
+        }
+        d = 1.0 / d;
+        let delta = d * c;
+        h *= delta;
+        if (delta - 1.0).abs() < EPSILON {
+            break;
+        }
+    }
+    h
+}
+
+/// P(X >= statistic) for a chi-square distribution with `df` degrees of freedom.
+pub fn chi_square_sf(statistic: f64, df: usize) -> f64 {
+    if df == 0 {
+        return 1.0;
+    }
+    gamma_q(df as f64 / 2.0, statistic / 2.0)
+}
+
+/// Pearson's chi-square statistic for observed counts against expected counts.
+pub fn chi_square_statistic(observed: &[u64], expected: &[f64]) -> f64 {
+    observed
+        .iter()
+        .zip(expected)
+        .filter(|(_, e)| **e > 0.0)
+        .map(|(&o, &e)| {
+            let diff = o as f64 - e;
+            diff * diff / e
+        })
+        .sum()
+}
+
+/// P(X >= k) for X ~ Binomial(n, p).
+pub fn binomial_upper_tail(k: u64, n: u64, p: f64) -> f64 {
+    if k == 0 {
+        return 1.0;
+    }
+    if k > n {
+        return 0.0;
+    }
+    beta_i(k as f64, (n - k + 1) as f64, p)
+}
+
+/// P(X <= k) for X ~ Binomial(n, p).
+pub fn binomial_lower_tail(k: u64, n: u64, p: f64) -> f64 {
+    if k >= n {
+        return 1.0;
+    }
+    1.0 - binomial_upper_tail(k + 1, n, p)
+}
+
+/// Two-sided exact binomial test p-value (doubling the smaller tail).
+pub fn binomial_two_sided(k: u64, n: u64, p: f64) -> f64 {
+    let lower = binomial_lower_tail(k, n, p);
+    let upper = binomial_upper_tail(k, n, p);
+    (2.0 * lower.min(upper)).min(1.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn close(a: f64, b: f64, tol: f64) -> bool {
+        (a - b).abs() < tol
+    }
+
+    #[test]
+    fn test_ln_gamma() {
+        // Gamma(5) = 24, Gamma(0.5) = sqrt(pi)
+        assert!(close(ln_gamma(5.0), 24f64.ln(), 1e-10));
+        assert!(close(ln_gamma(0.5), std::f64::consts::PI.sqrt().ln(), 1e-10));
+    }
+
+    #[test]
+    fn test_chi_square_critical_values() {
+        assert!(close(chi_square_sf(3.841_458_820_694_124, 1), 0.05, 1e-6));
+        assert!(close(chi_square_sf(18.307_038_053_275_146, 10), 0.05, 1e-6));
+        assert!(close(chi_square_sf(50.998_460_165_710_4, 36), 0.05, 1e-6));
+        assert_eq!(chi_square_sf(0.0, 5), 1.0);
+    }
+
+    #[test]
+    fn test_binomial_tails() {
+        // X ~ Bin(10, 0.5): P(X >= 8) = 56 / 1024
+        assert!(close(binomial_upper_tail(8, 10, 0.5), 56.0 / 1024.0, 1e-12));
+        assert!(close(binomial_lower_tail(2, 10, 0.5), 56.0 / 1024.0, 1e-12));
+        assert!(close(binomial_two_sided(2, 10, 0.5), 112.0 / 1024.0, 1e-12));
+        assert_eq!(binomial_upper_tail(0, 10, 0.3), 1.0);
+        assert_eq!(binomial_lower_tail(10, 10, 0.3), 1.0);
+    }
+}